@@ -8,8 +8,9 @@
 )]
 
 use linkme::distributed_slice;
-use std::collections::HashMap;
-use std::io::prelude::Read;
+use std::collections::BTreeMap;
+use std::io::prelude::{Read, Write};
+use std::sync::Arc;
 
 mod p2015_01;
 mod p2015_02;
@@ -29,14 +30,22 @@ mod p2015_15;
 mod p2015_16;
 mod p2015_17;
 mod p2015_18;
-//mod p2015_19;
+mod p2015_19;
 mod p2015_20;
 mod p2015_21;
-//mod p2015_22;
+mod p2015_22;
 mod p2015_23;
-//mod p2015_24;
+mod p2015_24;
 mod p2015_25;
 
+mod run_context;
+mod server;
+mod tour;
+mod vm;
+mod watch;
+
+use run_context::{CancellationToken, ProgressLine, RunContext};
+
 fn get_input_path(year: i32, day: i32) -> String {
     format!("inputs/{year}/{day}.txt")
 }
@@ -60,7 +69,16 @@ fn get_problem_input(year: i32, day: i32) -> String {
     input_string.trim().to_owned()
 }
 
-type SolveFn = fn(&str, Option<fn(&str)>) -> (String, String);
+type SolveFn = fn(&str, Option<fn(&str)>, &RunContext) -> (String, String);
+
+// Runs an interactive debugger on the given problem input, reading commands from the given input and writing to the
+// given output. Long-running commands should stop when the given token is cancelled, which happens when Ctrl-C is
+// pressed.
+type DebugFn = fn(&str, &CancellationToken, &mut dyn std::io::BufRead, &mut dyn std::io::Write) -> std::io::Result<()>;
+
+// Runs a solution on the file at the given path, reading the file as it goes rather than holding it in memory, and
+// writes the results to the given output. Any further command line arguments are given to it as well.
+type FileFn = fn(&std::path::Path, &[&str], &mut dyn std::io::Write) -> std::io::Result<()>;
 
 // Represents a specific solution to an Advent of Code problem.
 #[derive(Clone, Copy)]
@@ -68,12 +86,51 @@ pub struct Solution {
     year: i32,
     day: i32,
     solve: SolveFn,
+    debug: Option<DebugFn>,
+    run_on_file: Option<FileFn>,
 }
 
 impl Solution {
     // Creates a new Solution for the problem for the given year and day that uses the given solve function.
     pub const fn new(year: i32, day: i32, solve: SolveFn) -> Self {
-        Self { year, day, solve }
+        Self {
+            year,
+            day,
+            solve,
+            debug: None,
+            run_on_file: None,
+        }
+    }
+
+    // Returns this solution with the given interactive debugger, which can be started with the debug command.
+    #[must_use]
+    pub const fn with_debugger(self, debug: DebugFn) -> Self {
+        Self {
+            debug: Some(debug),
+            ..self
+        }
+    }
+
+    // Returns this solution with the given way of running it on an input file of any size, which can be started with
+    // the file command.
+    #[must_use]
+    pub const fn with_file_runner(self, run_on_file: FileFn) -> Self {
+        Self {
+            run_on_file: Some(run_on_file),
+            ..self
+        }
+    }
+
+    // Runs this solution on the given problem input in the given context and returns its results along with how
+    // long it took. The CPU time is measured across the whole process, so it includes time spent by any threads the
+    // solution uses.
+    fn run(&self, problem_input: &str, log_fn: Option<fn(&str)>, context: &RunContext) -> SolutionRunResult {
+        let start_time = std::time::Instant::now();
+        let start_cpu_time = cpu_time::ProcessTime::now();
+        let results = (self.solve)(problem_input, log_fn, context);
+        let cpu_duration = start_cpu_time.elapsed();
+        let duration = start_time.elapsed();
+        SolutionRunResult::new(results.0, results.1, duration, cpu_duration, context.is_cancelled())
     }
 }
 
@@ -83,27 +140,58 @@ impl Solution {
 pub static SOLUTIONS: [Solution] = [..];
 
 // Represents the result of running a solution on a given problem input.
+// If the run was cancelled, then the part results are not meaningful.
 struct SolutionRunResult {
     part1_result: String,
     part2_result: String,
     duration: std::time::Duration,
+    cpu_duration: std::time::Duration,
+    was_cancelled: bool,
 }
 
 impl SolutionRunResult {
-    fn new(part1_result: String, part2_result: String, duration: std::time::Duration) -> Self {
+    fn new(
+        part1_result: String,
+        part2_result: String,
+        duration: std::time::Duration,
+        cpu_duration: std::time::Duration,
+        was_cancelled: bool,
+    ) -> Self {
         Self {
             part1_result,
             part2_result,
             duration,
+            cpu_duration,
+            was_cancelled,
         }
     }
+
+    // Creates a result for a solution that was never run because the run was cancelled before its turn came.
+    fn skipped() -> Self {
+        Self::new(
+            String::new(),
+            String::new(),
+            std::time::Duration::ZERO,
+            std::time::Duration::ZERO,
+            true,
+        )
+    }
+}
+
+// Returns the registered solution for the given year and day, if there is one.
+fn find_solution(year: i32, day: i32) -> Option<Solution> {
+    SOLUTIONS.iter().find(|s| s.year == year && s.day == day).copied()
 }
 
-// Generates a map of years to a list of solutions for that year. If a specific year or day is requested, then only
-// solutions for that year or day are included in the map.
-fn generate_solution_set(year: Option<i32>, day: Option<i32>) -> HashMap<i32, Vec<Option<Solution>>> {
-    let mut solution_set = HashMap::new();
-    for solution in SOLUTIONS {
+// Generates a map of years to a list of the given solutions for that year, ordered by year. If a specific year or day
+// is requested, then only solutions for that year or day are included in the map.
+fn generate_solution_set(
+    solutions: &[Solution],
+    year: Option<i32>,
+    day: Option<i32>,
+) -> BTreeMap<i32, Vec<Option<Solution>>> {
+    let mut solution_set = BTreeMap::new();
+    for solution in solutions {
         if let Some(year) = year {
             if solution.year != year {
                 continue;
@@ -123,25 +211,41 @@ fn generate_solution_set(year: Option<i32>, day: Option<i32>) -> HashMap<i32, Ve
 }
 
 // Runs the solutions for the given year and/or day, or for all years and days if no specific year or day were requested.
+// If the given cancellation token is cancelled, then the solution currently running is asked to stop, and no further
+// solutions are run. The results of the solutions that weren't run are marked as cancelled, unlike days that have no
+// solution, which have no result.
+// Any parallel work done by the solutions is done on the given thread pool, or on rayon's global thread pool if none
+// is given.
 fn run_solutions(
     requested_year: Option<i32>,
     requested_day: Option<i32>,
     log_fn: Option<fn(&str)>,
-) -> HashMap<i32, Vec<Option<SolutionRunResult>>> {
+    cancellation_token: &CancellationToken,
+    thread_pool: Option<&Arc<rayon::ThreadPool>>,
+) -> BTreeMap<i32, Vec<Option<SolutionRunResult>>> {
     // Generate a map of years to a list of solutions for that year.
-    let solution_map = generate_solution_set(requested_year, requested_day);
+    let solution_map = generate_solution_set(&SOLUTIONS, requested_year, requested_day);
 
-    // Run the solutions for each year.
-    let mut result_map = HashMap::new();
+    // Run the solutions for each year, in order.
+    let mut result_map = BTreeMap::new();
     for (year, solutions) in solution_map {
         let mut result_vec = Vec::new();
         for solution in solutions {
             if let Some(solution) = solution {
+                if cancellation_token.is_cancelled() {
+                    result_vec.push(Some(SolutionRunResult::skipped()));
+                    continue;
+                }
+                // Show any progress the solution reports on a progress line that is removed once it finishes.
+                let progress_line = ProgressLine::new(format!("Year {year}, day {}", solution.day));
+                let context = RunContext::new(cancellation_token.clone())
+                    .with_progress_fn(progress_line.progress_fn())
+                    .with_thread_pool(thread_pool.cloned());
+
                 let problem_input = get_problem_input(year, solution.day);
-                let start_time = std::time::Instant::now();
-                let results = (solution.solve)(&problem_input, log_fn);
-                let duration = start_time.elapsed();
-                result_vec.push(Some(SolutionRunResult::new(results.0, results.1, duration)));
+                let result = solution.run(&problem_input, log_fn, &context);
+                progress_line.clear();
+                result_vec.push(Some(result));
             } else {
                 result_vec.push(None);
             }
@@ -151,70 +255,363 @@ fn run_solutions(
     result_map
 }
 
+// Returns the message that a panic was raised with, given the payload that was caught when unwinding from it.
+fn get_panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| (*s).to_owned())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Unknown panic.".to_owned())
+}
+
+// Writes out the given results in table form, with one table per year, in order of year and then day. The CPU time is
+// shown alongside the wall clock time, and the ratio between them shows how effectively each solution makes use of
+// multiple threads. If any solution was cancelled, then a note that the run was interrupted follows the tables.
+fn write_results(
+    output: &mut impl Write,
+    result_map: &BTreeMap<i32, Vec<Option<SolutionRunResult>>>,
+) -> std::io::Result<()> {
+    for (year, results) in result_map {
+        writeln!(output, "Year {year}")?;
+        writeln!(
+            output,
+            "Day  Part 1        Part 2           Time           CPU time    CPU/Wall"
+        )?;
+        writeln!(
+            output,
+            "---  ------------  ------------     ----------     ----------  --------"
+        )?;
+
+        let mut total_duration = std::time::Duration::new(0, 0);
+        let mut total_cpu_duration = std::time::Duration::new(0, 0);
+        for (day, result) in results.iter().enumerate() {
+            let display_day = day + 1;
+            if let Some(result) = result {
+                let (part1_result, part2_result) = if result.was_cancelled {
+                    ("cancelled", "cancelled")
+                } else {
+                    (result.part1_result.as_str(), result.part2_result.as_str())
+                };
+                writeln!(
+                    output,
+                    "{:>3}  {:>12}  {:>12}  {}  {}  {:>8}",
+                    display_day,
+                    part1_result,
+                    part2_result,
+                    format_duration(result.duration),
+                    format_duration(result.cpu_duration),
+                    format_cpu_ratio(result.cpu_duration, result.duration)
+                )?;
+
+                total_duration += result.duration;
+                total_cpu_duration += result.cpu_duration;
+            } else {
+                writeln!(
+                    output,
+                    "{:>3}  {:>12}  {:>12}  {}  {}  {:>8}",
+                    display_day,
+                    "-",
+                    "-",
+                    format_duration(std::time::Duration::ZERO),
+                    format_duration(std::time::Duration::ZERO),
+                    "-"
+                )?;
+            }
+        }
+
+        writeln!(
+            output,
+            "---  ------------  ------------     ----------     ----------  --------"
+        )?;
+        writeln!(
+            output,
+            "     {:>12}  {:>12}  {}  {}  {:>8}",
+            "-",
+            "-",
+            format_duration(total_duration),
+            format_duration(total_cpu_duration),
+            format_cpu_ratio(total_cpu_duration, total_duration)
+        )?;
+        writeln!(output)?;
+    }
+
+    if result_map
+        .values()
+        .flatten()
+        .flatten()
+        .any(|result| result.was_cancelled)
+    {
+        writeln!(
+            output,
+            "The run was interrupted, so the days marked as cancelled were stopped or never started."
+        )?;
+    }
+    Ok(())
+}
+
+// Formats the given duration for display in a fixed-width column.
+fn format_duration(duration: std::time::Duration) -> String {
+    format!(
+        "{:>4}.{:03}_{:03}s",
+        duration.as_secs(),
+        duration.subsec_millis(),
+        duration.as_micros() % 1000
+    )
+}
+
+// Formats the ratio of the given CPU time to the given wall clock time, which is roughly the average number of threads
+// that were kept busy.
+fn format_cpu_ratio(cpu_duration: std::time::Duration, duration: std::time::Duration) -> String {
+    if duration.is_zero() {
+        "-".to_owned()
+    } else {
+        format!("{:.2}x", cpu_duration.as_secs_f64() / duration.as_secs_f64())
+    }
+}
+
+// Represents the options given on the command line.
+struct CommandLineOptions {
+    // The arguments that aren't options, such as the mode, year and day.
+    positional_args: Vec<String>,
+    port: Option<u16>,
+    thread_count: Option<usize>,
+    is_verbose: bool,
+}
+
+// Parses the given command line arguments, returning None if they aren't valid.
+fn parse_command_line(args: &[String]) -> Option<CommandLineOptions> {
+    let mut options = CommandLineOptions {
+        positional_args: Vec::new(),
+        port: None,
+        thread_count: None,
+        is_verbose: false,
+    };
+
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-v" => options.is_verbose = true,
+            "--port" => options.port = Some(args.next()?.parse().ok()?),
+            "--threads" => options.thread_count = Some(args.next()?.parse().ok().filter(|n| *n > 0)?),
+            _ => options.positional_args.push(arg.clone()),
+        }
+    }
+    Some(options)
+}
+
 // Prints out the usage information for this program and immediately exits.
-fn print_usage_and_exit() {
-    println!("Usage: {} [<year>] [<day>] [-v]", std::env::args().next().unwrap());
+fn print_usage_and_exit() -> ! {
+    let program_name = std::env::args().next().unwrap();
+    println!("Usage: {program_name} [<year>] [<day>] [-v] [--threads <count>]");
+    println!("       {program_name} watch <year> <day> [-v] [--threads <count>]");
+    println!("       {program_name} serve --port <port> [--threads <count>]");
+    println!("       {program_name} debug <year> <day>");
+    println!("       {program_name} file <year> <day> <path> [<argument>...]");
     std::process::exit(1);
 }
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
-    if args.len() > 4 {
+    let Some(options) = parse_command_line(&args) else {
         print_usage_and_exit();
-    }
-
-    let requested_year = args.get(1).and_then(|s| s.parse::<i32>().ok());
-    let requested_day = args.get(2).and_then(|s| s.parse::<i32>().ok());
+    };
 
     // If verbose mode is enabled, then define a logging callback that will print out the given string.
     // This may be used by solutions to print out intermediate results.
-    let log_fn: Option<fn(&str)> = if args.contains(&"-v".to_owned()) {
+    let log_fn: Option<fn(&str)> = if options.is_verbose {
         Some(|s| println!("{s}"))
     } else {
         None
     };
 
-    // Run the solutions for the requested year and/or day, or for all years and days if no specific year or day were requested.
-    let result_map = run_solutions(requested_year, requested_day, log_fn);
+    // If a specific number of threads was requested, then create a thread pool with that many threads for solutions
+    // to do their parallel work on. Otherwise, solutions use rayon's global thread pool.
+    let thread_pool = options.thread_count.map(|thread_count| {
+        Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(thread_count)
+                .build()
+                .expect("Unable to create the thread pool."),
+        )
+    });
 
-    // Print out the results in table form, with one table per year.
-    for (year, results) in result_map {
-        println!("Year {year}");
-        println!("Day  Part 1        Part 2           Time");
-        println!("---  ------------  ------------     ----------");
+    let positional_args = options.positional_args.iter().map(String::as_str).collect::<Vec<_>>();
+    match positional_args.as_slice() {
+        // If the server mode was requested, then serve solutions over HTTP instead of running them directly.
+        ["serve"] => {
+            let Some(port) = options.port else {
+                print_usage_and_exit();
+            };
 
-        let mut total_duration = std::time::Duration::new(0, 0);
-        for (day, result) in results.iter().enumerate() {
-            let display_day = day + 1;
-            if let Some(result) = result {
-                println!(
-                    "{:>3}  {:>12}  {:>12}  {:>4}.{:03}_{:03}s",
-                    display_day,
-                    result.part1_result,
-                    result.part2_result,
-                    result.duration.as_secs(),
-                    result.duration.subsec_millis(),
-                    result.duration.as_micros() % 1000
-                );
+            server::serve(port, thread_pool.as_ref());
+            return;
+        }
+        // If the watch mode was requested, then re-run the requested solution whenever its input changes.
+        ["watch", year, day] => {
+            let (Ok(year), Ok(day)) = (year.parse::<i32>(), day.parse::<i32>()) else {
+                print_usage_and_exit();
+            };
 
-                total_duration += result.duration;
-            } else {
-                println!(
-                    "{:>3}  {:>12}  {:>12}  {:>4}.{:03}_{:03}s",
-                    display_day, "-", "-", 0, 0, 0
-                );
+            watch::watch(year, day, log_fn, thread_pool.as_ref());
+            return;
+        }
+        // If the debug mode was requested, then run the requested solution's interactive debugger on its input.
+        ["debug", year, day] => {
+            let (Ok(year), Ok(day)) = (year.parse::<i32>(), day.parse::<i32>()) else {
+                print_usage_and_exit();
+            };
+            let Some(debug) = find_solution(year, day).and_then(|solution| solution.debug) else {
+                eprintln!("There is no debugger for year {year} day {day}.");
+                std::process::exit(1);
+            };
+
+            // Interrupt whatever the debugger is running when Ctrl-C is pressed. If Ctrl-C is pressed again before the
+            // debugger notices, then exit immediately.
+            let interrupt_token = CancellationToken::new();
+            let handler_interrupt_token = interrupt_token.clone();
+            ctrlc::set_handler(move || {
+                if handler_interrupt_token.is_cancelled() {
+                    std::process::exit(130);
+                }
+                handler_interrupt_token.cancel();
+            })
+            .expect("Unable to set the Ctrl-C handler.");
+
+            let problem_input = get_problem_input(year, day);
+            debug(
+                &problem_input,
+                &interrupt_token,
+                &mut std::io::stdin().lock(),
+                &mut std::io::stdout().lock(),
+            )
+            .expect("Unable to run the debugger.");
+            return;
+        }
+        // If the file mode was requested, then run the requested solution on the given file, which may be too large
+        // to be read into memory.
+        ["file", year, day, path, arguments @ ..] => {
+            let (Ok(year), Ok(day)) = (year.parse::<i32>(), day.parse::<i32>()) else {
+                print_usage_and_exit();
+            };
+            let Some(run_on_file) = find_solution(year, day).and_then(|solution| solution.run_on_file) else {
+                eprintln!("Year {year} day {day} can't be run on a file.");
+                std::process::exit(1);
+            };
+
+            if let Err(e) = run_on_file(std::path::Path::new(path), arguments, &mut std::io::stdout().lock()) {
+                eprintln!("Unable to run on \"{path}\": {e}");
+                std::process::exit(1);
             }
+            return;
         }
+        _ => {}
+    }
 
-        println!("---  ------------  ------------     ----------");
-        println!(
-            "     {:>12}  {:>12}  {:>4}.{:03}_{:03}s",
-            "-",
-            "-",
-            total_duration.as_secs(),
-            total_duration.subsec_millis(),
-            total_duration.as_micros() % 1000
+    if positional_args.len() > 2 || options.port.is_some() {
+        print_usage_and_exit();
+    }
+
+    let requested_year = positional_args.first().and_then(|s| s.parse::<i32>().ok());
+    let requested_day = positional_args.get(1).and_then(|s| s.parse::<i32>().ok());
+
+    // Stop the solution that is currently running when Ctrl-C is pressed, so that the results of the solutions that
+    // have already finished can still be shown. If Ctrl-C is pressed again, then exit immediately.
+    let cancellation_token = CancellationToken::new();
+    let handler_cancellation_token = cancellation_token.clone();
+    ctrlc::set_handler(move || {
+        if handler_cancellation_token.is_cancelled() {
+            std::process::exit(130);
+        }
+
+        eprintln!("Stopping... (press Ctrl-C again to exit immediately)");
+        handler_cancellation_token.cancel();
+    })
+    .expect("Unable to set the Ctrl-C handler.");
+
+    // Run the solutions for the requested year and/or day, or for all years and days if no specific year or day were requested.
+    let result_map = run_solutions(
+        requested_year,
+        requested_day,
+        log_fn,
+        &cancellation_token,
+        thread_pool.as_ref(),
+    );
+
+    write_results(&mut std::io::stdout().lock(), &result_map).expect("Unable to write the results.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    // A solve function that gives the same results for every input.
+    fn solve_stub(_input: &str, _log_fn: Option<fn(&str)>, _context: &RunContext) -> (String, String) {
+        ("123".to_owned(), "4567890".to_owned())
+    }
+
+    // The results table is ordered by year and then by day, whatever order the solutions were registered in, and each
+    // run is given a fixed duration so that the table is the same every time.
+    #[test]
+    fn results_table_matches_golden_output() {
+        let solutions = [
+            Solution::new(2016, 2, solve_stub),
+            Solution::new(2015, 25, solve_stub),
+            Solution::new(2015, 1, solve_stub),
+        ];
+        let context = RunContext::new(CancellationToken::new());
+        let result_map: BTreeMap<i32, Vec<Option<SolutionRunResult>>> = generate_solution_set(&solutions, None, None)
+            .into_iter()
+            .map(|(year, solutions)| {
+                let results = solutions
+                    .into_iter()
+                    .map(|solution| {
+                        solution.map(|solution| {
+                            let (part1_result, part2_result) = (solution.solve)("", None, &context);
+                            let day = u64::try_from(solution.day).unwrap();
+                            SolutionRunResult::new(
+                                part1_result,
+                                part2_result,
+                                Duration::from_micros(day * 1_234_567),
+                                Duration::from_micros(day * 2_469_134),
+                                solution.year == 2016,
+                            )
+                        })
+                    })
+                    .collect();
+                (year, results)
+            })
+            .collect();
+
+        let mut output = Vec::new();
+        write_results(&mut output, &result_map).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            include_str!("../testdata/results_table.txt")
+        );
+    }
+
+    #[test]
+    fn cancelled_run_marks_remaining_solutions_as_cancelled() {
+        let cancellation_token = CancellationToken::new();
+        cancellation_token.cancel();
+        let result_map = run_solutions(Some(2015), None, None, &cancellation_token, None);
+        let results = &result_map[&2015];
+        assert!(results.iter().flatten().all(|result| result.was_cancelled));
+        assert_eq!(
+            results.iter().map(Option::is_some).collect::<Vec<_>>(),
+            (1..=25)
+                .map(|day| find_solution(2015, day).is_some())
+                .collect::<Vec<_>>()
         );
-        println!();
+
+        let mut output = Vec::new();
+        write_results(&mut output, &result_map).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output
+            .lines()
+            .any(|line| line == "  1     cancelled     cancelled     0.000_000s     0.000_000s         -"));
+        assert!(output
+            .ends_with("\nThe run was interrupted, so the days marked as cancelled were stopped or never started.\n"));
     }
 }
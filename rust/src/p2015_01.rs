@@ -54,7 +54,7 @@ fn get_first_basement_char(instruction_str: &str) -> Result<usize, ()> {
     Err(())
 }
 
-fn solve(input: &str, _log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, _log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
     // Part 1: Find the resulting floor number after following the instructions.
     let part1_result = get_floor_number(input).unwrap();
 
@@ -44,7 +44,7 @@ fn get_ribbon_length_required(dimensions: (i32, i32, i32)) -> i32 {
     smallest_perimeter + volume
 }
 
-fn solve(input: &str, _log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, _log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
     // Part 1: Find the total area of wrapping paper required for all packages specified in the input.
     let total_paper_area =
         package_dimensions(input).fold(0, |total, dimensions| total + get_paper_area_required(dimensions));
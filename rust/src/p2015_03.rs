@@ -16,7 +16,7 @@ fn get_movement_vector_from_char(character: char) -> (isize, isize) {
 }
 
 // Returns the number of unique houses visited by the given number of actor(s) processing
-// the given instructions.
+// the given instructions. The actors are run on the current rayon thread pool.
 fn get_unique_house_count(instructions_string: &str, actor_count: usize) -> usize {
     // This implementation processes the multiple actors moving around between the houses
     // in parallel. This is probably not more efficient given the overhead, but
@@ -45,10 +45,9 @@ fn get_unique_house_count(instructions_string: &str, actor_count: usize) -> usiz
         .house_visitation_counts
         .insert((0, 0), 1);
 
-    // Create a scope that will guarantee all threads started within it have been joined
-    // and exited after the scope is exited.
-    crossbeam::thread::scope(|s| {
-        // Start a thread for each actor moving between the houses.
+    // Create a scope that will guarantee all tasks started within it have finished after the scope is exited.
+    rayon::scope(|s| {
+        // Start a task for each actor moving between the houses.
         for i in 0..actor_count {
             // Set up an iterator that will step through every nth item in the input command string,
             // corresponding to the instructions that this particular actor should process.
@@ -59,7 +58,7 @@ fn get_unique_house_count(instructions_string: &str, actor_count: usize) -> usiz
             let stepped_instructions_iterator = instructions_iterator.step_by(actor_count);
 
             // Create a reference to the shared visitation state that can be given to and owned by
-            // the thread associated with the current actor being started, and then start the thread
+            // the task associated with the current actor being started, and then start the task
             // for the current actor.
             let shared_visited_state = std::sync::Arc::clone(&shared_visited_state);
             s.spawn(move |_| {
@@ -88,20 +87,19 @@ fn get_unique_house_count(instructions_string: &str, actor_count: usize) -> usiz
                 }
             });
         }
-    })
-    .unwrap();
+    });
 
     return shared_visited_state.lock().unwrap().unique_house_count;
 }
 
-fn solve(input: &str, _log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, _log_fn: Option<fn(&str)>, context: &crate::RunContext) -> (String, String) {
     // Part 1: Find the number of unique houses visited when only one actor is processing
     // the instruction string.
-    let unique_house_count = get_unique_house_count(input, 1);
+    let unique_house_count = context.install(|| get_unique_house_count(input, 1));
 
     // Part 2: Find the number of unique houses visited when two actors are processing
     // the instruction string.
-    let unique_house_count_two_actors = get_unique_house_count(input, 2);
+    let unique_house_count_two_actors = context.install(|| get_unique_house_count(input, 2));
 
     (
         unique_house_count.to_string(),
@@ -31,37 +31,47 @@ fn is_desired_md5_hash(hash: &[u8; 16], leading_zero_count: usize) -> bool {
 
 // Returns the first number that results in an MD5 hash whose hexadecimal representation has leading_zero_count
 // leading zeroes when its string representation is appended to the given key string and the resulting string is
-// hashed.
-fn find_first_zero_prefixed_hash_number(key: &str, leading_zero_count: usize) -> usize {
+// hashed. Since the search is unbounded, it reports its progress to the given context, and it returns None if
+// the context is cancelled before a number is found.
+fn find_first_zero_prefixed_hash_number(
+    key: &str,
+    leading_zero_count: usize,
+    context: &crate::RunContext,
+) -> Option<usize> {
     let key_bytes = key.as_bytes();
 
-    // Iterate through chunks of numbers, hashing each number in parallel until a number is found that results
-    // in a hash with the desired number of leading zeroes.
+    // Iterate through chunks of numbers, hashing each number in parallel on the context's thread pool until a
+    // number is found that results in a hash with the desired number of leading zeroes.
     let chunk_size = 100000;
     let mut chunk_begin = 0;
-    loop {
+    while !context.is_cancelled() {
         let chunk_end = chunk_begin + chunk_size;
-        let chunk_result = (chunk_begin..chunk_end)
-            .into_par_iter()
-            .map(|n| (n, md5_hash_key_and_num(key_bytes, n)))
-            .find_first(|(_, hash)| is_desired_md5_hash(hash, leading_zero_count));
+        let chunk_result = context.install(|| {
+            (chunk_begin..chunk_end)
+                .into_par_iter()
+                .map(|n| (n, md5_hash_key_and_num(key_bytes, n)))
+                .find_first(|(_, hash)| is_desired_md5_hash(hash, leading_zero_count))
+        });
 
         if let Some((n, _)) = chunk_result {
-            return n;
+            return Some(n);
         }
 
         chunk_begin = chunk_end;
+        context.report_progress(chunk_begin as u64, None);
     }
+
+    None
 }
 
-fn solve(input: &str, _log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, _log_fn: Option<fn(&str)>, context: &crate::RunContext) -> (String, String) {
     // Part 1: Find the first number that results in a hash with five leading zeros when
     // its string representation is appended to the given key string.
-    let part1_result = find_first_zero_prefixed_hash_number(input, 5);
+    let part1_result = find_first_zero_prefixed_hash_number(input, 5, context).unwrap_or_default();
 
     // Part 2: Find the first number that results in a hash with six leading zeros when
     // its string representation is appended to the given key string.
-    let part2_result = find_first_zero_prefixed_hash_number(input, 6);
+    let part2_result = find_first_zero_prefixed_hash_number(input, 6, context).unwrap_or_default();
 
     (part1_result.to_string(), part2_result.to_string())
 }
@@ -33,7 +33,7 @@ fn is_nice_string1(string: &&str) -> bool {
             let last_string: &str = &[last_char, c].iter().collect::<String>();
             if DISALLOWED_SUBSTRINGS.contains(&last_string) {
                 return false;
-            };
+            }
         }
 
         last_char = c;
@@ -68,12 +68,12 @@ fn is_nice_string2(string: &&str) -> bool {
     found_matching_pair && found_repeated_letter
 }
 
-fn solve(input: &str, _log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, _log_fn: Option<fn(&str)>, context: &crate::RunContext) -> (String, String) {
     // Part 1: Find the number of "nice" strings in the input list according to the first set of rules.
-    let part1_result = input.par_lines().filter(is_nice_string1).collect::<Vec<&str>>().len();
+    let part1_result = context.install(|| input.par_lines().filter(is_nice_string1).collect::<Vec<&str>>().len());
 
     // Part 2: Find the number of "nice" strings in the input list according to the second set of rules.
-    let part2_result = input.par_lines().filter(is_nice_string2).collect::<Vec<&str>>().len();
+    let part2_result = context.install(|| input.par_lines().filter(is_nice_string2).collect::<Vec<&str>>().len());
 
     (part1_result.to_string(), part2_result.to_string())
 }
@@ -16,7 +16,7 @@ impl LightGrid {
 
     // Returns the total brightness of all lights in the grid together.
     pub fn get_total_brightness(&self) -> usize {
-        return self.light_status.iter().sum::<usize>();
+        self.light_status.iter().sum::<usize>()
     }
 
     // Toggles the given rectangular area of the grid on or off. If a light within the area is off
@@ -129,7 +129,7 @@ fn process_light_instructions<ToggleFn, OnFn, OffFn>(
     }
 }
 
-fn solve(input: &str, _log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, _log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
     // Note: Rc and RefCell are used below to allow the same LightGrid instance to be referenced by the multiple callbacks
     // (for each different instruction) and to allow each closure to dynamically borrow the instance to modify it. Just
     // trying to give the same instance to each closure will fail at compile time, because multiple mutable references to the
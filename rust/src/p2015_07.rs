@@ -1,10 +1,16 @@
 // Advent of Code 2015, Day 7: "Some Assembly Required"
 // https://adventofcode.com/2015/day/7
 
+mod compile;
+mod export;
+mod symbolic;
+mod validation;
+mod what_if;
+
 use phf::phf_map;
 
 // Represents the different operations a node in the circuit can perform.
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 enum NodeOperation {
     Set,
     And,
@@ -24,8 +30,9 @@ impl NodeOperation {
             Self::And => i1 & i2.unwrap(),
             Self::Or => i1 | i2.unwrap(),
             Self::Not => !i1,
-            Self::LeftShift => i1 << i2.unwrap(),
-            Self::RightShift => i1 >> i2.unwrap(),
+            // Shifting by the width of a value or more shifts out all of its bits.
+            Self::LeftShift => i1.checked_shl(u32::from(i2.unwrap())).unwrap_or(0),
+            Self::RightShift => i1.checked_shr(u32::from(i2.unwrap())).unwrap_or(0),
         }
     }
 }
@@ -55,9 +62,22 @@ struct CircuitNode {
     pub value: std::cell::RefCell<Option<u16>>,
 }
 
+// Returns the names of the nodes that the given node description reads from.
+fn get_input_node_names(description: &NodeDescription) -> impl Iterator<Item = &String> {
+    std::iter::once(&description.input1)
+        .chain(description.input2.as_ref())
+        .filter_map(|input| match input {
+            NodeInput::Node(name) => Some(name),
+            NodeInput::Immediate(_) => None,
+        })
+}
+
 // Represents a circuit of connected nodes.
 struct Circuit {
     node_list: std::collections::HashMap<String, CircuitNode>,
+    // Maps the name of each node to the names of the nodes that read from it, so that changes to a node can be
+    // propagated to only the nodes they affect.
+    dependent_node_names: std::collections::HashMap<String, Vec<String>>,
 }
 
 impl Circuit {
@@ -66,7 +86,7 @@ impl Circuit {
     where
         I: Iterator<Item = NodeDescription>,
     {
-        Circuit {
+        let mut circuit = Circuit {
             // Create circuit nodes from the given node descriptions.
             node_list: node_descriptions
                 .map(|d| {
@@ -78,11 +98,65 @@ impl Circuit {
                     (node_name, node)
                 })
                 .collect(),
+            dependent_node_names: std::collections::HashMap::new(),
+        };
+
+        let node_names: Vec<String> = circuit.node_list.keys().cloned().collect();
+        for node_name in node_names {
+            circuit.add_dependencies(&node_name);
         }
+        circuit
     }
 
-    // Returns the output value of the node with the given name.
-    pub fn get_node_value(&self, node_name: &str) -> u16 {
+    // Records that the node with the given name reads from each of its inputs.
+    fn add_dependencies(&mut self, node_name: &str) {
+        for input_name in get_input_node_names(&self.node_list[node_name].description) {
+            self.dependent_node_names
+                .entry(input_name.clone())
+                .or_default()
+                .push(node_name.to_string());
+        }
+    }
+
+    // Removes the records that the node with the given name reads from each of its inputs.
+    fn remove_dependencies(&mut self, node_name: &str) {
+        for input_name in get_input_node_names(&self.node_list[node_name].description) {
+            if let Some(dependent_node_names) = self.dependent_node_names.get_mut(input_name) {
+                if let Some(i) = dependent_node_names.iter().position(|name| name == node_name) {
+                    dependent_node_names.swap_remove(i);
+                }
+            }
+        }
+    }
+
+    // Returns the names of the nodes whose values depend on any of the nodes with the given names, including those
+    // nodes themselves. This is the fan-out cone of the given nodes.
+    pub fn get_fan_out_node_names<'a, I>(&self, node_names: I) -> std::collections::HashSet<String>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut fan_out_node_names = std::collections::HashSet::new();
+        let mut remaining_node_names: Vec<String> = node_names.into_iter().map(str::to_string).collect();
+        while let Some(node_name) = remaining_node_names.pop() {
+            if let Some(dependent_node_names) = self.dependent_node_names.get(&node_name) {
+                remaining_node_names.extend(
+                    dependent_node_names
+                        .iter()
+                        .filter(|name| !fan_out_node_names.contains(*name))
+                        .cloned(),
+                );
+            }
+            fan_out_node_names.insert(node_name);
+        }
+        fan_out_node_names
+    }
+
+    // Returns the output value of the node with the given name, using and filling in the given cache of node values.
+    fn evaluate_node<G, S>(&self, node_name: &str, get_cached_value: G, mut set_cached_value: S) -> u16
+    where
+        G: Fn(&str) -> Option<u16>,
+        S: FnMut(&str, u16),
+    {
         // Push the requested node node onto a stack of remaining node names to process,
         // and being processing the stack of node names. If the node specified by the name
         // on top of the stack has a value, pop it off. If the node doesn't have a value,
@@ -98,7 +172,7 @@ impl Circuit {
 
             // If the current node already has a value, pop it off the stack and continue;
             // it doesn't need to be processed any further.
-            if current_node.value.borrow().is_some() {
+            if get_cached_value(current_node_name).is_some() {
                 remaining_node_names.pop();
                 continue;
             }
@@ -113,9 +187,9 @@ impl Circuit {
             match &current_node.description.input1 {
                 NodeInput::Immediate(value) => input1 = Some(*value),
                 NodeInput::Node(name) => {
-                    input1 = *self.node_list[name].value.borrow();
+                    input1 = get_cached_value(name);
                     if input1.is_none() {
-                        remaining_node_names.push(name.to_string());
+                        remaining_node_names.push(name.clone());
                         continue;
                     }
                 }
@@ -124,13 +198,13 @@ impl Circuit {
             // Do the same thing for the second node input, but only if this node actually
             // has a second input.
             let mut input2: Option<u16> = None;
-            if current_node.description.input2.is_some() {
-                match current_node.description.input2.as_ref().unwrap() {
+            if let Some(description_input2) = &current_node.description.input2 {
+                match description_input2 {
                     NodeInput::Immediate(value) => input2 = Some(*value),
                     NodeInput::Node(name) => {
-                        input2 = *self.node_list[name].value.borrow();
+                        input2 = get_cached_value(name);
                         if input2.is_none() {
-                            remaining_node_names.push(name.to_string());
+                            remaining_node_names.push(name.clone());
                             continue;
                         }
                     }
@@ -140,11 +214,20 @@ impl Circuit {
             // Now that any necessary inputs for the current node have been determined, apply the current
             // node's operation to the inputs to determine the current node's value.
             let current_node_value = current_node.description.operation.apply(input1.unwrap(), input2);
-            *self.node_list[current_node_name].value.borrow_mut() = Some(current_node_value);
+            set_cached_value(current_node_name, current_node_value);
         }
 
         // Now that all relevant nodes have been processed, return the value associated with the requested node.
-        self.node_list[node_name].value.borrow().unwrap()
+        get_cached_value(node_name).unwrap()
+    }
+
+    // Returns the output value of the node with the given name.
+    pub fn get_node_value(&self, node_name: &str) -> u16 {
+        self.evaluate_node(
+            node_name,
+            |name| *self.node_list[name].value.borrow(),
+            |name, value| *self.node_list[name].value.borrow_mut() = Some(value),
+        )
     }
 
     // Returns the output values of all nodes in the circuit in the form of a mapping from
@@ -159,15 +242,43 @@ impl Circuit {
     // Updates the circuit with the given node description. If a node with the same name as the given node already
     // exists, it will be replaced.
     pub fn update_node(&mut self, new_node_description: &NodeDescription) {
-        *self.node_list.get_mut(&new_node_description.name).unwrap() = CircuitNode {
-            description: new_node_description.clone(),
-            value: std::cell::RefCell::new(None),
-        };
+        self.update_nodes(std::slice::from_ref(new_node_description));
+    }
 
-        for n in self.node_list.values() {
-            *n.value.borrow_mut() = None;
+    // Updates the circuit with all of the given node descriptions at once, replacing any existing nodes with the same
+    // names. Only the values of nodes downstream of the updated nodes are recalculated the next time they are needed.
+    pub fn update_nodes(&mut self, new_node_descriptions: &[NodeDescription]) {
+        for new_node_description in new_node_descriptions {
+            let node_name = &new_node_description.name;
+            if self.node_list.contains_key(node_name) {
+                self.remove_dependencies(node_name);
+            }
+            self.node_list.insert(
+                node_name.clone(),
+                CircuitNode {
+                    description: new_node_description.clone(),
+                    value: std::cell::RefCell::new(None),
+                },
+            );
+            self.add_dependencies(node_name);
+        }
+
+        // Since the updates are applied together, the fan-out cone of all of the updated nodes only needs to be
+        // found and invalidated once.
+        let updated_node_names = new_node_descriptions.iter().map(|d| d.name.as_str());
+        for node_name in self.get_fan_out_node_names(updated_node_names) {
+            *self.node_list[&node_name].value.borrow_mut() = None;
         }
     }
+
+    // Returns a view of this circuit in which the wires with the given names are forced to the given values, which
+    // can be used to see what would happen without changing this circuit.
+    pub fn what_if<'a, I>(&self, overrides: I) -> what_if::WhatIfCircuit<'_>
+    where
+        I: IntoIterator<Item = (&'a str, u16)>,
+    {
+        what_if::WhatIfCircuit::new(self, overrides)
+    }
 }
 
 // Maps the names of node operations to the actual operations.
@@ -251,19 +362,121 @@ where
     input_lines.into_iter().map(parse_node_line)
 }
 
-fn solve(input: &str, log_fn: Option<fn(&str)>) -> (String, String) {
-    // Create a circuit from the node list specified by the input.
-    let mut circuit = Circuit::new(parse_node_list(&mut input.lines()));
+// Logs the given circuit as a Graphviz DOT graph and as a Verilog module, both annotated with the given node values.
+fn log_circuit_exports(circuit: &Circuit, node_values: &std::collections::HashMap<String, u16>, log_fn: fn(&str)) {
+    let mut dot_output = Vec::new();
+    circuit
+        .write_dot(&mut dot_output, Some(node_values))
+        .expect("Writing to memory should not fail");
+    let mut verilog_output = Vec::new();
+    circuit
+        .write_verilog(&mut verilog_output, "circuit", Some(node_values))
+        .expect("Writing to memory should not fail");
+
+    for export_output in [dot_output, verilog_output] {
+        log_fn("------------------------------");
+        for line in String::from_utf8_lossy(&export_output).lines() {
+            log_fn(line);
+        }
+    }
+}
+
+// Logs the value of each node that would change if the wires with the given names were forced to the given values,
+// both as it is in the circuit and as it would be, without changing the circuit.
+fn log_what_if(circuit: &Circuit, overrides: &[(&str, u16)], log_fn: fn(&str)) {
+    let what_if_circuit = circuit.what_if(overrides.iter().copied());
+    let mut affected_node_names: Vec<String> = circuit
+        .get_fan_out_node_names(overrides.iter().map(|(name, _)| *name))
+        .into_iter()
+        .collect();
+    affected_node_names.sort_unstable();
+    for name in affected_node_names {
+        log_fn(&format!(
+            "What if {}: {name} = {} -> {}",
+            overrides
+                .iter()
+                .map(|(name, value)| format!("{name} = {value}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            circuit.get_node_value(&name),
+            what_if_circuit.get_node_value(&name)
+        ));
+    }
+}
+
+// Logs what node "a" computes in terms of the circuit's constant inputs, along with the values of node "b" that
+// make node "a" have the given value.
+fn log_symbolic_analysis(circuit: &Circuit, target_value_of_a: u16, log_fn: fn(&str)) {
+    // Treat every node that is set directly to an immediate value as an input.
+    let input_wire_names: Vec<&str> = circuit
+        .node_list
+        .values()
+        .filter(|node| {
+            matches!(
+                (node.description.operation, &node.description.input1),
+                (NodeOperation::Set, NodeInput::Immediate(_))
+            )
+        })
+        .map(|node| node.description.name.as_str())
+        .collect();
+    let symbolic_circuit = circuit.evaluate_symbolically(input_wire_names);
+    log_fn(&format!(
+        "Inputs that a depends on: {:?}",
+        symbolic_circuit.get_dependencies("a")
+    ));
+    for line in symbolic_circuit.format_wire("a").lines() {
+        log_fn(line);
+    }
+
+    let solutions = circuit
+        .evaluate_symbolically(["b"])
+        .solve_for_input("a", "b", target_value_of_a);
+    log_fn(&format!(
+        "Values of b that make a = {target_value_of_a}: {} (first few: {:?})",
+        solutions.len(),
+        &solutions[..solutions.len().min(8)]
+    ));
+}
 
-    // Print out the status of each node in the circuit.
-    for v in circuit.get_all_node_values() {
-        if let Some(log_fn) = log_fn {
+fn solve(input: &str, log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
+    // Check the node list specified by the input for problems that would keep it from being evaluated, and create a
+    // circuit from it if there aren't any. If there are, they are reported and no results are given. Errors are
+    // reported on stderr when logging isn't enabled, so that it is clear why there are no results.
+    let node_descriptions: Vec<NodeDescription> = parse_node_list(&mut input.lines()).collect();
+    let validation_issues = validation::validate_node_descriptions(&node_descriptions, &["a", "b"]);
+    for issue in &validation_issues {
+        let severity = if issue.is_error() { "Error" } else { "Warning" };
+        match log_fn {
+            Some(log_fn) => log_fn(&format!("{severity}: {issue}")),
+            None if issue.is_error() => eprintln!("2015 day 7: {severity}: {issue}"),
+            None => {}
+        }
+    }
+    if validation_issues.iter().any(validation::ValidationIssue::is_error) {
+        return (String::from("-"), String::from("-"));
+    }
+    let mut circuit = Circuit::new(node_descriptions.into_iter());
+
+    // If logging is enabled, print out the status of each node in the circuit, along with exports of the circuit
+    // annotated with those values, which can be used to check the wiring.
+    if let Some(log_fn) = log_fn {
+        let node_values = circuit.get_all_node_values();
+        for v in &node_values {
             log_fn(&format!("{}: {}", v.0, v.1));
         }
+        log_circuit_exports(&circuit, &node_values, log_fn);
     }
 
-    // Part 1: Find the value of node "a" in the circuit specified by the input.
-    let value_of_a = circuit.get_node_value("a");
+    // Part 1: Find the value of node "a" in the circuit specified by the input. This is found with the circuit
+    // compiled into a straight-line program.
+    let compiled_circuit = circuit.compile();
+    if let Some(log_fn) = log_fn {
+        log_fn("------------------------------");
+        for line in compiled_circuit.to_string().lines() {
+            log_fn(line);
+        }
+    }
+    let value_of_a = compiled_circuit.evaluate()[compiled_circuit.get_wire_id("a")];
     if let Some(log_fn) = log_fn {
         log_fn("------------------------------");
         log_fn(&format!("{value_of_a}"));
@@ -271,31 +484,130 @@ fn solve(input: &str, log_fn: Option<fn(&str)>) -> (String, String) {
     }
     let part1_result = value_of_a;
 
-    // Update the circuit with a new node "b".
-    circuit.update_node(&NodeDescription {
-        name: String::from("b"),
-        operation: NodeOperation::Set,
-        input1: NodeInput::Immediate(value_of_a),
-        input2: None,
-    });
-
-    // Print out the status of each node in the circuit.
-    for v in circuit.get_all_node_values() {
-        if let Some(log_fn) = log_fn {
+    // Part 2: Find the value of node "a" when node "b" is overridden with the value of node "a" found in part 1.
+    // The compiled program can evaluate this directly by treating node "b" as an input.
+    let part2_result = compiled_circuit.evaluate_batch(&["b"], &[vec![value_of_a]], &["a"])[0][0];
+
+    // If logging is enabled, show what overriding node "b" would change without touching the circuit, then update the
+    // circuit with a new node "b", which only invalidates the nodes downstream of it, and print out the status of
+    // each node in the updated circuit.
+    if let Some(log_fn) = log_fn {
+        log_what_if(&circuit, &[("b", value_of_a)], log_fn);
+        circuit.update_node(&NodeDescription {
+            name: String::from("b"),
+            operation: NodeOperation::Set,
+            input1: NodeInput::Immediate(value_of_a),
+            input2: None,
+        });
+        for v in circuit.get_all_node_values() {
             log_fn(&format!("{}: {}", v.0, v.1));
         }
-    }
 
-    // Part 2: Find the value of node "a" in the modified circuit.
-    if let Some(log_fn) = log_fn {
         log_fn("------------------------------");
-        log_fn(&format!("{}", circuit.get_node_value("a")));
+        log_fn(&format!("{part2_result}"));
         log_fn("------------------------------");
+
+        log_symbolic_analysis(&circuit, part2_result, log_fn);
     }
-    let part2_result = circuit.get_node_value("a");
 
     (part1_result.to_string(), part2_result.to_string())
 }
 
 #[linkme::distributed_slice(crate::SOLUTIONS)]
 static SOLUTION: crate::Solution = crate::Solution::new(2015, 7, solve);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The example circuit from the puzzle description, extended with a wire "b" that can be overridden and a wire
+    // "a" downstream of it.
+    const EXAMPLE_CIRCUIT: &str = "123 -> x
+456 -> y
+x AND y -> d
+x OR y -> e
+x LSHIFT 2 -> f
+y RSHIFT 2 -> g
+NOT x -> h
+NOT y -> i
+e -> b
+b OR f -> c
+c AND i -> a";
+
+    fn create_example_circuit() -> Circuit {
+        Circuit::new(parse_node_list(EXAMPLE_CIRCUIT.lines()))
+    }
+
+    #[test]
+    fn compiled_circuit_matches_interpreter() {
+        let circuit = create_example_circuit();
+        let compiled_circuit = circuit.compile();
+        let registers = compiled_circuit.evaluate();
+        let node_values = circuit.get_all_node_values();
+        assert_eq!(node_values.len(), compiled_circuit.wire_names().len());
+        for (name, value) in &node_values {
+            assert_eq!(registers[compiled_circuit.get_wire_id(name)], *value, "wire {name}");
+        }
+        assert_eq!(node_values["d"], 72);
+        assert_eq!(node_values["h"], 65412);
+        assert_eq!(node_values["i"], 65079);
+    }
+
+    #[test]
+    fn batch_evaluation_matches_what_if() {
+        let circuit = create_example_circuit();
+        let compiled_circuit = circuit.compile();
+        let input_vectors: Vec<Vec<u16>> = [0, 1, 507, 0x8000, 0xffff].into_iter().map(|b| vec![b]).collect();
+        let output_vectors = compiled_circuit.evaluate_batch(&["b"], &input_vectors, &["a", "c"]);
+        for (input_vector, output_vector) in input_vectors.iter().zip(&output_vectors) {
+            let what_if_circuit = circuit.what_if([("b", input_vector[0])]);
+            assert_eq!(
+                *output_vector,
+                [what_if_circuit.get_node_value("a"), what_if_circuit.get_node_value("c")]
+            );
+        }
+    }
+
+    #[test]
+    fn update_node_invalidates_only_fan_out() {
+        let mut circuit = create_example_circuit();
+        let node_values = circuit.get_all_node_values();
+        let value_of_b = 0x1234;
+        circuit.update_node(&NodeDescription {
+            name: String::from("b"),
+            operation: NodeOperation::Set,
+            input1: NodeInput::Immediate(value_of_b),
+            input2: None,
+        });
+
+        // Only the nodes downstream of "b" should have lost their cached values.
+        let fan_out_node_names = circuit.get_fan_out_node_names(["b"]);
+        assert_eq!(
+            fan_out_node_names,
+            ["a", "b", "c"].into_iter().map(String::from).collect()
+        );
+        for (name, node) in &circuit.node_list {
+            let cached_value = *node.value.borrow();
+            if fan_out_node_names.contains(name) {
+                assert_eq!(cached_value, None, "wire {name}");
+            } else {
+                assert_eq!(cached_value, Some(node_values[name]), "wire {name}");
+            }
+        }
+
+        // The re-evaluated nodes should agree with evaluating the override without changing the circuit.
+        let expected_value_of_a = create_example_circuit()
+            .what_if([("b", value_of_b)])
+            .get_node_value("a");
+        assert_eq!(circuit.get_node_value("a"), expected_value_of_a);
+        assert_eq!(circuit.get_node_value("b"), value_of_b);
+        assert_eq!(circuit.get_node_value("e"), node_values["e"]);
+    }
+
+    #[test]
+    fn invalid_circuit_gives_no_results() {
+        let context = crate::RunContext::new(crate::run_context::CancellationToken::new());
+        let results = solve("123 -> x\nx AND y -> a\nb -> b", None, &context);
+        assert_eq!(results, (String::from("-"), String::from("-")));
+    }
+}
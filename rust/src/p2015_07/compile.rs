@@ -0,0 +1,253 @@
+// Compiles a circuit into a straight-line program over a register file, with one register per wire and one
+// instruction per node, ordered so that every instruction's inputs are calculated before it. Evaluating every wire in
+// the circuit is then a single pass over the instructions, without any name lookups or caching.
+
+use super::{Circuit, NodeInput, NodeOperation};
+use std::collections::HashMap;
+
+// The number of input vectors evaluated together by evaluate_batch. Each register holds this many values, and each
+// instruction is applied to all of them in a simple loop that the compiler can vectorize.
+const BATCH_LANE_COUNT: usize = 64;
+
+// Represents an operand of an instruction.
+#[derive(Debug, Clone, Copy)]
+pub enum Operand {
+    Register(usize),
+    Immediate(u16),
+}
+
+// Represents an instruction that applies an operation to its operands and stores the result in a register.
+#[derive(Debug, Clone, Copy)]
+pub struct Instruction {
+    pub operation: NodeOperation,
+    pub destination: usize,
+    pub operand1: Operand,
+    pub operand2: Option<Operand>,
+}
+
+// A circuit compiled into a straight-line program.
+pub struct CompiledCircuit {
+    wire_names: Vec<String>,
+    wire_ids: HashMap<String, usize>,
+    instructions: Vec<Instruction>,
+}
+
+impl std::fmt::Display for CompiledCircuit {
+    // Formats the program as an assembly-like listing, with one instruction per line.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format_operand = |operand: Operand| match operand {
+            Operand::Register(register) => format!("r{register}"),
+            Operand::Immediate(value) => format!("#{value}"),
+        };
+        for instruction in &self.instructions {
+            write!(
+                f,
+                "{:>6}  r{} <- {}",
+                instruction.operation.name(),
+                instruction.destination,
+                format_operand(instruction.operand1)
+            )?;
+            if let Some(operand2) = instruction.operand2 {
+                write!(f, ", {}", format_operand(operand2))?;
+            }
+            writeln!(f, "  ; {}", self.wire_names[instruction.destination])?;
+        }
+        Ok(())
+    }
+}
+
+impl CompiledCircuit {
+    // Returns the names of the wires, indexed by the ids of the registers that hold their values.
+    pub fn wire_names(&self) -> &[String] {
+        &self.wire_names
+    }
+
+    // Returns the instructions of the program, in the order that they are run.
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    // Returns the id of the register that holds the value of the wire with the given name.
+    pub fn get_wire_id(&self, wire_name: &str) -> usize {
+        *self
+            .wire_ids
+            .get(wire_name)
+            .unwrap_or_else(|| panic!("The circuit has no wire named \"{wire_name}\""))
+    }
+
+    // Evaluates every wire in the circuit, and returns the register file, which holds the value of each wire at its
+    // id (see get_wire_id).
+    pub fn evaluate(&self) -> Vec<u16> {
+        let mut registers = vec![0; self.wire_names.len()];
+        for instruction in &self.instructions {
+            let read = |operand| match operand {
+                Operand::Register(register) => registers[register],
+                Operand::Immediate(value) => value,
+            };
+            let value = instruction
+                .operation
+                .apply(read(instruction.operand1), instruction.operand2.map(read));
+            registers[instruction.destination] = value;
+        }
+        registers
+    }
+
+    // Evaluates the circuit once for each of the given input vectors, and returns the values of the given output
+    // wires for each one. Each input vector holds a value for each of the given input wires, which replaces whatever
+    // drives that wire in the circuit. This makes it practical to test a circuit exhaustively.
+    pub fn evaluate_batch(
+        &self,
+        input_wires: &[&str],
+        input_vectors: &[Vec<u16>],
+        output_wires: &[&str],
+    ) -> Vec<Vec<u16>> {
+        let input_wire_ids: Vec<usize> = input_wires.iter().map(|name| self.get_wire_id(name)).collect();
+        let output_wire_ids: Vec<usize> = output_wires.iter().map(|name| self.get_wire_id(name)).collect();
+
+        // The instructions that drive the input wires are skipped, so that the input values are left in place.
+        let instructions: Vec<&Instruction> = self
+            .instructions
+            .iter()
+            .filter(|instruction| !input_wire_ids.contains(&instruction.destination))
+            .collect();
+
+        let mut registers = vec![[0u16; BATCH_LANE_COUNT]; self.wire_names.len()];
+        let mut output_vectors = Vec::with_capacity(input_vectors.len());
+        for input_vector_chunk in input_vectors.chunks(BATCH_LANE_COUNT) {
+            for (lane, input_vector) in input_vector_chunk.iter().enumerate() {
+                assert_eq!(
+                    input_vector.len(),
+                    input_wire_ids.len(),
+                    "Each input vector must have one value per input wire"
+                );
+                for (wire_id, value) in input_wire_ids.iter().zip(input_vector) {
+                    registers[*wire_id][lane] = *value;
+                }
+            }
+
+            for instruction in &instructions {
+                let operand1 = Self::read_lanes(&registers, instruction.operand1);
+                let operand2 = instruction
+                    .operand2
+                    .map(|operand| Self::read_lanes(&registers, operand));
+                registers[instruction.destination] =
+                    Self::apply_to_lanes(instruction.operation, &operand1, operand2.as_ref());
+            }
+
+            output_vectors.extend((0..input_vector_chunk.len()).map(|lane| {
+                output_wire_ids
+                    .iter()
+                    .map(|wire_id| registers[*wire_id][lane])
+                    .collect()
+            }));
+        }
+        output_vectors
+    }
+
+    // Returns the values of the given operand in each lane.
+    fn read_lanes(registers: &[[u16; BATCH_LANE_COUNT]], operand: Operand) -> [u16; BATCH_LANE_COUNT] {
+        match operand {
+            Operand::Register(register) => registers[register],
+            Operand::Immediate(value) => [value; BATCH_LANE_COUNT],
+        }
+    }
+
+    // Applies the given operation to the given operands in each lane. The operation is matched once for all lanes,
+    // so that each loop below is simple enough to be vectorized.
+    fn apply_to_lanes(
+        operation: NodeOperation,
+        operand1: &[u16; BATCH_LANE_COUNT],
+        operand2: Option<&[u16; BATCH_LANE_COUNT]>,
+    ) -> [u16; BATCH_LANE_COUNT] {
+        let mut result = [0u16; BATCH_LANE_COUNT];
+        match (operation, operand2) {
+            (NodeOperation::Set, _) => result = *operand1,
+            (NodeOperation::Not, _) => {
+                for (r, a) in result.iter_mut().zip(operand1) {
+                    *r = !a;
+                }
+            }
+            (NodeOperation::And, Some(operand2)) => {
+                for ((r, a), b) in result.iter_mut().zip(operand1).zip(operand2) {
+                    *r = a & b;
+                }
+            }
+            (NodeOperation::Or, Some(operand2)) => {
+                for ((r, a), b) in result.iter_mut().zip(operand1).zip(operand2) {
+                    *r = a | b;
+                }
+            }
+            (NodeOperation::LeftShift | NodeOperation::RightShift, Some(operand2)) => {
+                for ((r, a), b) in result.iter_mut().zip(operand1).zip(operand2) {
+                    *r = operation.apply(*a, Some(*b));
+                }
+            }
+            (_, None) => panic!("The {} instruction has no second operand", operation.name()),
+        }
+        result
+    }
+}
+
+impl Circuit {
+    // Compiles this circuit into a straight-line program. The circuit must not contain any loops, which validation
+    // checks for.
+    pub fn compile(&self) -> CompiledCircuit {
+        // Intern the wire names, in sorted order so that the program is deterministic.
+        let mut wire_names: Vec<String> = self.node_list.keys().cloned().collect();
+        wire_names.sort_unstable();
+        let wire_ids: HashMap<String, usize> = wire_names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (name.clone(), id))
+            .collect();
+
+        // Sort the nodes topologically with Kahn's algorithm: repeatedly emit a node whose inputs have all been
+        // emitted already, starting with the nodes that have no node inputs.
+        let mut pending_input_counts: Vec<usize> = wire_names
+            .iter()
+            .map(|name| super::get_input_node_names(&self.node_list[name].description).count())
+            .collect();
+        let mut ready_wire_ids: Vec<usize> = (0..wire_names.len())
+            .filter(|id| pending_input_counts[*id] == 0)
+            .rev()
+            .collect();
+        let mut instructions = Vec::with_capacity(wire_names.len());
+        while let Some(wire_id) = ready_wire_ids.pop() {
+            let description = &self.node_list[&wire_names[wire_id]].description;
+            let to_operand = |input: &NodeInput| match input {
+                NodeInput::Immediate(value) => Operand::Immediate(*value),
+                NodeInput::Node(name) => Operand::Register(wire_ids[name]),
+            };
+            instructions.push(Instruction {
+                operation: description.operation,
+                destination: wire_id,
+                operand1: to_operand(&description.input1),
+                operand2: description.input2.as_ref().map(to_operand),
+            });
+
+            for dependent_name in self
+                .dependent_node_names
+                .get(&wire_names[wire_id])
+                .into_iter()
+                .flatten()
+            {
+                let dependent_id = wire_ids[dependent_name];
+                pending_input_counts[dependent_id] -= 1;
+                if pending_input_counts[dependent_id] == 0 {
+                    ready_wire_ids.push(dependent_id);
+                }
+            }
+        }
+        assert_eq!(
+            instructions.len(),
+            wire_names.len(),
+            "The circuit contains a loop, so it can't be compiled"
+        );
+
+        CompiledCircuit {
+            wire_names,
+            wire_ids,
+            instructions,
+        }
+    }
+}
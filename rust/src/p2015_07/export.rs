@@ -0,0 +1,159 @@
+// Exports a circuit to formats that other tools understand: Graphviz DOT graphs, for visually checking the wiring,
+// and Verilog netlists, for checking the circuit's behavior against an external simulator.
+
+use super::{Circuit, NodeInput, NodeOperation};
+use std::collections::HashMap;
+use std::io::Write;
+
+impl NodeOperation {
+    // Returns the name of this operation as it appears in the puzzle input.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Set => "SET",
+            Self::And => "AND",
+            Self::Or => "OR",
+            Self::Not => "NOT",
+            Self::LeftShift => "LSHIFT",
+            Self::RightShift => "RSHIFT",
+        }
+    }
+
+    // Returns the Verilog operator that implements this operation.
+    fn verilog_operator(self) -> &'static str {
+        match self {
+            Self::Set => "",
+            Self::And => "&",
+            Self::Or => "|",
+            Self::Not => "~",
+            Self::LeftShift => "<<",
+            Self::RightShift => ">>",
+        }
+    }
+}
+
+// Returns the given node name as a Verilog identifier. Node names are prefixed, since some of them (e.g. "if" or
+// "or") are Verilog keywords.
+fn get_verilog_wire_name(node_name: &str) -> String {
+    format!("n_{node_name}")
+}
+
+// Returns the given node input as a Verilog expression.
+fn get_verilog_input(input: &NodeInput) -> String {
+    match input {
+        NodeInput::Immediate(value) => format!("16'd{value}"),
+        NodeInput::Node(name) => get_verilog_wire_name(name),
+    }
+}
+
+// Returns the given string escaped for use within a quoted DOT identifier.
+fn escape_dot_string(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl Circuit {
+    // Returns the names of the nodes in the circuit in sorted order, so that exports are deterministic.
+    fn get_sorted_node_names(&self) -> Vec<&String> {
+        let mut node_names: Vec<&String> = self.node_list.keys().collect();
+        node_names.sort_unstable();
+        node_names
+    }
+
+    // Writes the circuit to the given output as a Graphviz DOT graph, with an edge from each node to the nodes that
+    // use its value. Immediate inputs are drawn as separate constant nodes. If node values are given, each node is
+    // labeled with its value.
+    pub fn write_dot(
+        &self,
+        output: &mut impl Write,
+        node_values: Option<&HashMap<String, u16>>,
+    ) -> std::io::Result<()> {
+        writeln!(output, "digraph circuit {{")?;
+        writeln!(output, "    rankdir=LR;")?;
+        writeln!(output, "    node [shape=box, fontname=\"monospace\"];")?;
+
+        for node_name in self.get_sorted_node_names() {
+            let description = &self.node_list[node_name].description;
+            let escaped_name = escape_dot_string(node_name);
+            let operation_name = description.operation.name();
+            let label = match node_values.and_then(|values| values.get(node_name)) {
+                Some(value) => format!("{escaped_name}\\n{operation_name}\\n= {value}"),
+                None => format!("{escaped_name}\\n{operation_name}"),
+            };
+            writeln!(output, "    \"{escaped_name}\" [label=\"{label}\"];")?;
+
+            // The inputs of binary operations are labeled, since the order of a shift's inputs matters.
+            let inputs = std::iter::once(&description.input1).chain(description.input2.as_ref());
+            let is_binary = description.input2.is_some();
+            for (input_index, input) in inputs.enumerate() {
+                let source_name = match input {
+                    NodeInput::Immediate(value) => {
+                        let constant_name = format!("{escaped_name}#{input_index}");
+                        writeln!(output, "    \"{constant_name}\" [label=\"{value}\", shape=plaintext];")?;
+                        constant_name
+                    }
+                    NodeInput::Node(name) => escape_dot_string(name),
+                };
+                if is_binary {
+                    writeln!(
+                        output,
+                        "    \"{source_name}\" -> \"{escaped_name}\" [label=\"{}\"];",
+                        input_index + 1
+                    )?;
+                } else {
+                    writeln!(output, "    \"{source_name}\" -> \"{escaped_name}\";")?;
+                }
+            }
+        }
+
+        writeln!(output, "}}")
+    }
+
+    // Writes the circuit to the given output as a synthesizable Verilog module with the given name. Every node
+    // becomes a 16-bit output wire named after the node with an "n_" prefix, so that all of them can be observed by
+    // a simulator. If node values are given, each wire's assignment is annotated with its expected value.
+    pub fn write_verilog(
+        &self,
+        output: &mut impl Write,
+        module_name: &str,
+        node_values: Option<&HashMap<String, u16>>,
+    ) -> std::io::Result<()> {
+        let node_names = self.get_sorted_node_names();
+
+        writeln!(output, "module {module_name} (")?;
+        for (i, node_name) in node_names.iter().enumerate() {
+            let separator = if i + 1 < node_names.len() { "," } else { "" };
+            writeln!(
+                output,
+                "    output wire [15:0] {}{separator}",
+                get_verilog_wire_name(node_name)
+            )?;
+        }
+        writeln!(output, ");")?;
+
+        for node_name in node_names {
+            let description = &self.node_list[node_name].description;
+            let input1 = get_verilog_input(&description.input1);
+            let expression = match (description.operation, &description.input2) {
+                (operation @ (NodeOperation::Set | NodeOperation::Not), _) => {
+                    format!("{}{input1}", operation.verilog_operator())
+                }
+                (operation, Some(input2)) => format!(
+                    "{input1} {} {}",
+                    operation.verilog_operator(),
+                    get_verilog_input(input2)
+                ),
+                (operation, None) => panic!("The {} node {node_name} has no second input", operation.name()),
+            };
+            write!(
+                output,
+                "    assign {} = {expression};",
+                get_verilog_wire_name(node_name)
+            )?;
+            if let Some(value) = node_values.and_then(|values| values.get(node_name)) {
+                write!(output, " // = {value}")?;
+            }
+            writeln!(output)?;
+        }
+
+        writeln!(output, "endmodule")
+    }
+}
@@ -0,0 +1,298 @@
+// Evaluates a circuit symbolically, treating some of its wires as unknown inputs. Each wire's value is built up as an
+// expression over those inputs, which is simplified as it is built, so that it is possible to see what a wire really
+// computes and which inputs it really depends on.
+
+use super::compile::Operand;
+use super::{Circuit, NodeOperation};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+// Identifies an expression within a SymbolicCircuit.
+pub type ExpressionId = usize;
+
+// Represents an expression over the inputs of a circuit. Subexpressions are referred to by id, so that identical
+// subexpressions are only stored once.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expression {
+    Constant(u16),
+    Input(String),
+    Not(ExpressionId),
+    Binary(NodeOperation, ExpressionId, ExpressionId),
+}
+
+// A circuit in which every wire's value is a simplified expression over the circuit's input wires.
+pub struct SymbolicCircuit {
+    // All expressions that have been built. An expression only refers to expressions that come before it.
+    expressions: Vec<Expression>,
+    expression_ids: HashMap<Expression, ExpressionId>,
+    wire_expression_ids: HashMap<String, ExpressionId>,
+}
+
+impl SymbolicCircuit {
+    // Returns the id of the given expression, adding it if it hasn't been seen before.
+    fn intern(&mut self, expression: Expression) -> ExpressionId {
+        if let Some(id) = self.expression_ids.get(&expression) {
+            return *id;
+        }
+        let id = self.expressions.len();
+        self.expressions.push(expression.clone());
+        self.expression_ids.insert(expression, id);
+        id
+    }
+
+    // Returns the value of the given expression if it is a constant.
+    fn get_constant(&self, id: ExpressionId) -> Option<u16> {
+        match self.expressions[id] {
+            Expression::Constant(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    // Returns whether the given expressions are always the bitwise complement of each other.
+    fn are_complements(&self, id1: ExpressionId, id2: ExpressionId) -> bool {
+        self.expressions[id1] == Expression::Not(id2) || self.expressions[id2] == Expression::Not(id1)
+    }
+
+    // Returns the id of the bitwise complement of the given expression, simplified.
+    fn make_not(&mut self, id: ExpressionId) -> ExpressionId {
+        match self.expressions[id] {
+            Expression::Constant(value) => self.intern(Expression::Constant(!value)),
+            Expression::Not(inner_id) => inner_id,
+            _ => self.intern(Expression::Not(id)),
+        }
+    }
+
+    // Returns the id of the given binary operation applied to the given expressions, simplified.
+    fn make_binary(&mut self, operation: NodeOperation, id1: ExpressionId, id2: ExpressionId) -> ExpressionId {
+        // Fold operations on constants.
+        if let (Some(value1), Some(value2)) = (self.get_constant(id1), self.get_constant(id2)) {
+            return self.intern(Expression::Constant(operation.apply(value1, Some(value2))));
+        }
+
+        match operation {
+            NodeOperation::And | NodeOperation::Or => {
+                // Put the operands of commutative operations in a canonical order, with any constant second, so that
+                // equivalent expressions are identical.
+                let (id1, id2) = if self.get_constant(id1).is_some() || (id1 > id2 && self.get_constant(id2).is_none())
+                {
+                    (id2, id1)
+                } else {
+                    (id1, id2)
+                };
+                let is_and = operation == NodeOperation::And;
+                let (absorbing_value, identity_value) = if is_and { (0, u16::MAX) } else { (u16::MAX, 0) };
+
+                if id1 == id2 {
+                    return id1;
+                }
+                if self.are_complements(id1, id2) {
+                    return self.intern(Expression::Constant(absorbing_value));
+                }
+                if let Some(value2) = self.get_constant(id2) {
+                    if value2 == absorbing_value {
+                        return id2;
+                    }
+                    if value2 == identity_value {
+                        return id1;
+                    }
+
+                    // Combine constants across nested operations, e.g. (x AND 12) AND 10 becomes x AND 8.
+                    if let Expression::Binary(inner_operation, inner_id1, inner_id2) = self.expressions[id1] {
+                        if let Some(inner_value2) = self.get_constant(inner_id2) {
+                            if inner_operation == operation {
+                                let combined_id =
+                                    self.intern(Expression::Constant(operation.apply(inner_value2, Some(value2))));
+                                return self.make_binary(operation, inner_id1, combined_id);
+                            }
+                        }
+                    }
+                }
+                self.intern(Expression::Binary(operation, id1, id2))
+            }
+            NodeOperation::LeftShift | NodeOperation::RightShift => {
+                if self.get_constant(id1) == Some(0) {
+                    return id1;
+                }
+                if let Some(shift) = self.get_constant(id2) {
+                    if shift == 0 {
+                        return id1;
+                    }
+                    if shift >= 16 {
+                        return self.intern(Expression::Constant(0));
+                    }
+
+                    // Combine nested shifts in the same direction, e.g. (x LSHIFT 2) LSHIFT 3 becomes x LSHIFT 5.
+                    if let Expression::Binary(inner_operation, inner_id1, inner_id2) = self.expressions[id1] {
+                        if let Some(inner_shift) = self.get_constant(inner_id2) {
+                            if inner_operation == operation {
+                                let combined_id = self.intern(Expression::Constant(inner_shift.saturating_add(shift)));
+                                return self.make_binary(operation, inner_id1, combined_id);
+                            }
+                        }
+                    }
+                }
+                self.intern(Expression::Binary(operation, id1, id2))
+            }
+            NodeOperation::Set | NodeOperation::Not => {
+                panic!("{} is not a binary operation", operation.name())
+            }
+        }
+    }
+
+    // Returns the id of the simplified expression for the value of the wire with the given name.
+    pub fn get_wire_expression_id(&self, wire_name: &str) -> ExpressionId {
+        *self
+            .wire_expression_ids
+            .get(wire_name)
+            .unwrap_or_else(|| panic!("The circuit has no wire named \"{wire_name}\""))
+    }
+
+    // Returns the names of the input wires that the value of the wire with the given name depends on, after
+    // simplification.
+    pub fn get_dependencies(&self, wire_name: &str) -> BTreeSet<String> {
+        let mut dependencies = BTreeSet::new();
+        let mut visited_ids = HashSet::new();
+        let mut remaining_ids = vec![self.get_wire_expression_id(wire_name)];
+        while let Some(id) = remaining_ids.pop() {
+            if !visited_ids.insert(id) {
+                continue;
+            }
+            match &self.expressions[id] {
+                Expression::Constant(_) => {}
+                Expression::Input(name) => {
+                    dependencies.insert(name.clone());
+                }
+                Expression::Not(inner_id) => remaining_ids.push(*inner_id),
+                Expression::Binary(_, id1, id2) => remaining_ids.extend([*id1, *id2]),
+            }
+        }
+        dependencies
+    }
+
+    // Returns the simplified formula for the value of the wire with the given name. Subexpressions that are used more
+    // than once are given names (t0, t1, ...) and defined on lines of their own, so that the formula doesn't grow
+    // exponentially with the depth of the circuit.
+    pub fn format_wire(&self, wire_name: &str) -> String {
+        let root_id = self.get_wire_expression_id(wire_name);
+
+        // Count the uses of each subexpression reachable from the root. Since expressions only refer to ones that
+        // come before them, walking backwards from the root visits every user of an expression before it.
+        let mut use_counts: HashMap<ExpressionId, usize> = HashMap::from([(root_id, 1)]);
+        for id in (0..=root_id).rev() {
+            if !use_counts.contains_key(&id) {
+                continue;
+            }
+            let children = match self.expressions[id] {
+                Expression::Not(inner_id) => vec![inner_id],
+                Expression::Binary(_, id1, id2) => vec![id1, id2],
+                Expression::Constant(_) | Expression::Input(_) => Vec::new(),
+            };
+            for child_id in children {
+                *use_counts.entry(child_id).or_default() += 1;
+            }
+        }
+
+        // Format the expressions in order, so that each named subexpression is defined before it is used.
+        let mut formatted_expressions: HashMap<ExpressionId, String> = HashMap::new();
+        let mut lines = Vec::new();
+        for id in 0..=root_id {
+            let Some(use_count) = use_counts.get(&id) else {
+                continue;
+            };
+            let formatted_expression = match &self.expressions[id] {
+                Expression::Constant(value) => value.to_string(),
+                Expression::Input(name) => name.clone(),
+                Expression::Not(inner_id) => format!("NOT {}", formatted_expressions[inner_id]),
+                Expression::Binary(operation, id1, id2) => format!(
+                    "({} {} {})",
+                    formatted_expressions[id1],
+                    operation.name(),
+                    formatted_expressions[id2]
+                ),
+            };
+            let is_shared =
+                *use_count > 1 && matches!(self.expressions[id], Expression::Not(_) | Expression::Binary(..));
+            if is_shared && id != root_id {
+                let name = format!("t{}", lines.len());
+                lines.push(format!("{name} = {formatted_expression}"));
+                formatted_expressions.insert(id, name);
+            } else {
+                formatted_expressions.insert(id, formatted_expression);
+            }
+        }
+        lines.push(format!("{wire_name} = {}", formatted_expressions[&root_id]));
+        lines.join("\n")
+    }
+
+    // Finds every value of the given input wire that makes the given output wire have the given target value. The
+    // output wire must not depend on any other input wire. All possible values of the input are tried, using the
+    // simplified expression for the output, which is usually far smaller than the circuit.
+    pub fn solve_for_input(&self, output_wire_name: &str, input_wire_name: &str, target_value: u16) -> Vec<u16> {
+        let dependencies = self.get_dependencies(output_wire_name);
+        assert!(
+            dependencies.iter().all(|name| name == input_wire_name),
+            "Wire \"{output_wire_name}\" depends on inputs other than \"{input_wire_name}\": {dependencies:?}"
+        );
+
+        let root_id = self.get_wire_expression_id(output_wire_name);
+        let mut values = vec![0u16; root_id + 1];
+        (0..=u16::MAX)
+            .filter(|input_value| {
+                for id in 0..=root_id {
+                    values[id] = match self.expressions[id] {
+                        Expression::Constant(value) => value,
+                        Expression::Input(_) => *input_value,
+                        Expression::Not(inner_id) => !values[inner_id],
+                        Expression::Binary(operation, id1, id2) => operation.apply(values[id1], Some(values[id2])),
+                    };
+                }
+                values[root_id] == target_value
+            })
+            .collect()
+    }
+}
+
+impl Circuit {
+    // Evaluates this circuit symbolically, treating the wires with the given names as unknown inputs. Every other
+    // wire's value is built from the inputs and from constants. The circuit must not contain any loops, which
+    // validation checks for.
+    pub fn evaluate_symbolically<'a, I>(&self, input_wire_names: I) -> SymbolicCircuit
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let input_wire_names: HashSet<&str> = input_wire_names.into_iter().collect();
+        let mut symbolic_circuit = SymbolicCircuit {
+            expressions: Vec::new(),
+            expression_ids: HashMap::new(),
+            wire_expression_ids: HashMap::new(),
+        };
+
+        // The compiled circuit's instructions are in an order in which each wire is calculated after its inputs, so
+        // each wire's expression can be built from the expressions of its inputs in a single pass.
+        let compiled_circuit = self.compile();
+        let wire_names = compiled_circuit.wire_names();
+        let mut wire_expression_ids = vec![0; wire_names.len()];
+        for instruction in compiled_circuit.instructions() {
+            let wire_name = &wire_names[instruction.destination];
+            let expression_id = if input_wire_names.contains(wire_name.as_str()) {
+                symbolic_circuit.intern(Expression::Input(wire_name.clone()))
+            } else {
+                let mut get_operand_id = |operand| match operand {
+                    Operand::Register(register) => wire_expression_ids[register],
+                    Operand::Immediate(value) => symbolic_circuit.intern(Expression::Constant(value)),
+                };
+                let id1 = get_operand_id(instruction.operand1);
+                let id2 = instruction.operand2.map(get_operand_id);
+                match (instruction.operation, id2) {
+                    (NodeOperation::Set, _) => id1,
+                    (NodeOperation::Not, _) => symbolic_circuit.make_not(id1),
+                    (operation, Some(id2)) => symbolic_circuit.make_binary(operation, id1, id2),
+                    (operation, None) => panic!("The {} node {wire_name} has no second input", operation.name()),
+                }
+            };
+            wire_expression_ids[instruction.destination] = expression_id;
+        }
+
+        symbolic_circuit.wire_expression_ids = wire_names.iter().cloned().zip(wire_expression_ids).collect();
+        symbolic_circuit
+    }
+}
@@ -0,0 +1,362 @@
+// Checks a list of node descriptions for problems that would keep the circuit they describe from being evaluated,
+// such as wires that are never driven or combinational loops, before the circuit is built from them.
+
+use super::{get_input_node_names, NodeDescription};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// Describes a problem found in a circuit's node descriptions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    // A wire is used but no node drives it. If the wire is used by a node, that node's name is given; otherwise the
+    // wire is one of the wires that the circuit is required to have.
+    UndefinedWire {
+        wire: String,
+        used_by: Option<String>,
+    },
+    // A wire is driven by more than one node.
+    MultipleDrivers {
+        wire: String,
+        driver_count: usize,
+    },
+    // The given wires form a combinational loop, listed in the order that signals flow through them. The first wire
+    // is repeated at the end to close the loop. If several loops are tangled together, only one of them is given,
+    // and the number of other wires tangled up with it is given as well.
+    Cycle {
+        wires: Vec<String>,
+        other_wire_count: usize,
+    },
+    // The node driving the given wire doesn't contribute to any of the wires that the circuit is required to have.
+    UnreachableNode {
+        wire: String,
+    },
+}
+
+impl ValidationIssue {
+    // Returns whether this issue keeps the circuit from being evaluated, as opposed to just being suspicious.
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Self::UnreachableNode { .. })
+    }
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UndefinedWire {
+                wire,
+                used_by: Some(used_by),
+            } => write!(f, "Wire \"{wire}\" is used by \"{used_by}\" but is never driven"),
+            Self::UndefinedWire { wire, used_by: None } => write!(f, "Required wire \"{wire}\" is never driven"),
+            Self::MultipleDrivers { wire, driver_count } => {
+                write!(f, "Wire \"{wire}\" is driven by {driver_count} nodes")
+            }
+            Self::Cycle {
+                wires,
+                other_wire_count,
+            } => {
+                write!(f, "Combinational loop: {}", wires.join(" -> "))?;
+                if *other_wire_count > 0 {
+                    let noun = if *other_wire_count == 1 { "wire is" } else { "wires are" };
+                    write!(f, " ({other_wire_count} other {noun} tangled up in this loop)")?;
+                }
+                Ok(())
+            }
+            Self::UnreachableNode { wire } => write!(f, "Wire \"{wire}\" doesn't affect any required wire"),
+        }
+    }
+}
+
+// Finds the strongly connected components of the given graph with Tarjan's algorithm, and returns the components
+// that contain a cycle. The graph is given as a list of edges out of each vertex. The search is iterative, so that
+// long chains of wires can't overflow the stack.
+fn find_cyclic_components(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+
+    let vertex_count = edges.len();
+    let mut indices = vec![UNVISITED; vertex_count];
+    let mut low_links = vec![0; vertex_count];
+    let mut is_on_stack = vec![false; vertex_count];
+    let mut component_stack = Vec::new();
+    let mut next_index = 0;
+    let mut components = Vec::new();
+
+    for root in 0..vertex_count {
+        if indices[root] != UNVISITED {
+            continue;
+        }
+
+        // Each entry on the search stack is a vertex along with the position of the next edge out of it to follow.
+        let mut search_stack = vec![(root, 0)];
+        indices[root] = next_index;
+        low_links[root] = next_index;
+        next_index += 1;
+        component_stack.push(root);
+        is_on_stack[root] = true;
+
+        while let Some((vertex, edge_position)) = search_stack.last_mut() {
+            let vertex = *vertex;
+            if let Some(&next_vertex) = edges[vertex].get(*edge_position) {
+                *edge_position += 1;
+                if indices[next_vertex] == UNVISITED {
+                    indices[next_vertex] = next_index;
+                    low_links[next_vertex] = next_index;
+                    next_index += 1;
+                    component_stack.push(next_vertex);
+                    is_on_stack[next_vertex] = true;
+                    search_stack.push((next_vertex, 0));
+                } else if is_on_stack[next_vertex] {
+                    low_links[vertex] = low_links[vertex].min(indices[next_vertex]);
+                }
+                continue;
+            }
+
+            // All edges out of the vertex have been followed, so it is finished. If it is the root of a component,
+            // then the component is everything above it on the component stack.
+            search_stack.pop();
+            if let Some((parent, _)) = search_stack.last() {
+                low_links[*parent] = low_links[*parent].min(low_links[vertex]);
+            }
+            if low_links[vertex] == indices[vertex] {
+                let mut component = Vec::new();
+                loop {
+                    let member = component_stack.pop().unwrap();
+                    is_on_stack[member] = false;
+                    component.push(member);
+                    if member == vertex {
+                        break;
+                    }
+                }
+                if component.len() > 1 || edges[vertex].contains(&vertex) {
+                    components.push(component);
+                }
+            }
+        }
+    }
+
+    components
+}
+
+// Returns a shortest cycle through the given vertex that stays within the given component, as a list of vertices
+// that starts and ends with the given vertex.
+fn find_cycle_in_component(edges: &[Vec<usize>], component: &HashSet<usize>, start: usize) -> Vec<usize> {
+    let mut predecessors = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(vertex) = queue.pop_front() {
+        for &next_vertex in &edges[vertex] {
+            if !component.contains(&next_vertex) || predecessors.contains_key(&next_vertex) {
+                continue;
+            }
+            predecessors.insert(next_vertex, vertex);
+            if next_vertex == start {
+                // Walk back from the start to itself, which lists the cycle in reverse.
+                let mut cycle = vec![start];
+                let mut current = vertex;
+                while current != start {
+                    cycle.push(current);
+                    current = predecessors[&current];
+                }
+                cycle.push(start);
+                cycle.reverse();
+                return cycle;
+            }
+            queue.push_back(next_vertex);
+        }
+    }
+    unreachable!("Every vertex in a cyclic component is part of a cycle")
+}
+
+// Validates the given node descriptions, and returns all problems found with them. The names of the wires that the
+// circuit is required to have (e.g. the ones whose values are needed) are used to find nodes that don't matter.
+pub fn validate_node_descriptions(descriptions: &[NodeDescription], required_wires: &[&str]) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    // Find the node driving each wire, and any wires that are driven by more than one node. Only the first driver
+    // of each wire is considered in the checks below.
+    let mut driver_counts: HashMap<&str, usize> = HashMap::new();
+    let mut node_indices: HashMap<&str, usize> = HashMap::new();
+    for (i, description) in descriptions.iter().enumerate() {
+        *driver_counts.entry(&description.name).or_default() += 1;
+        node_indices.entry(&description.name).or_insert(i);
+    }
+    let mut multiply_driven_wires: Vec<(&str, usize)> =
+        driver_counts.into_iter().filter(|(_, count)| *count > 1).collect();
+    multiply_driven_wires.sort_unstable();
+    for (wire, driver_count) in multiply_driven_wires {
+        issues.push(ValidationIssue::MultipleDrivers {
+            wire: wire.to_string(),
+            driver_count,
+        });
+    }
+
+    // Build the graph of which node drives which, flagging any wires used without being driven. Edges run in the
+    // direction that signals flow, from a node to the nodes that read from it.
+    let mut edges = vec![Vec::new(); descriptions.len()];
+    for (i, description) in descriptions.iter().enumerate() {
+        if node_indices[description.name.as_str()] != i {
+            continue;
+        }
+        for input_name in get_input_node_names(description) {
+            match node_indices.get(input_name.as_str()) {
+                Some(input_index) => edges[*input_index].push(i),
+                None => issues.push(ValidationIssue::UndefinedWire {
+                    wire: input_name.clone(),
+                    used_by: Some(description.name.clone()),
+                }),
+            }
+        }
+    }
+    for required_wire in required_wires {
+        if !node_indices.contains_key(required_wire) {
+            issues.push(ValidationIssue::UndefinedWire {
+                wire: (*required_wire).to_string(),
+                used_by: None,
+            });
+        }
+    }
+
+    // Report one loop for each tangle of wires that loop back on themselves, starting from the wire with the
+    // lowest name so that the report is stable.
+    let mut cycles = Vec::new();
+    for component in find_cyclic_components(&edges) {
+        let start = *component.iter().min_by_key(|i| &descriptions[**i].name).unwrap();
+        let component_set: HashSet<usize> = component.iter().copied().collect();
+        let cycle = find_cycle_in_component(&edges, &component_set, start);
+        let other_wire_count = component.len() - (cycle.len() - 1);
+        let wires: Vec<String> = cycle.into_iter().map(|i| descriptions[i].name.clone()).collect();
+        cycles.push((wires, other_wire_count));
+    }
+    cycles.sort_unstable();
+    issues.extend(
+        cycles
+            .into_iter()
+            .map(|(wires, other_wire_count)| ValidationIssue::Cycle {
+                wires,
+                other_wire_count,
+            }),
+    );
+
+    // Find the nodes that contribute to the required wires by walking backwards from them. Without any required
+    // wires, there is nothing for the nodes to contribute to, so this check is skipped.
+    if required_wires.is_empty() {
+        return issues;
+    }
+    let mut is_reachable = vec![false; descriptions.len()];
+    let mut remaining_nodes: Vec<usize> = required_wires
+        .iter()
+        .filter_map(|wire| node_indices.get(wire).copied())
+        .collect();
+    while let Some(i) = remaining_nodes.pop() {
+        if is_reachable[i] {
+            continue;
+        }
+        is_reachable[i] = true;
+        remaining_nodes.extend(
+            get_input_node_names(&descriptions[i])
+                .filter_map(|input_name| node_indices.get(input_name.as_str()).copied()),
+        );
+    }
+    let mut unreachable_wires: Vec<&str> = node_indices
+        .iter()
+        .filter(|(_, i)| !is_reachable[**i])
+        .map(|(wire, _)| *wire)
+        .collect();
+    unreachable_wires.sort_unstable();
+    issues.extend(
+        unreachable_wires
+            .into_iter()
+            .map(|wire| ValidationIssue::UnreachableNode { wire: wire.to_string() }),
+    );
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Validates the circuit with the given text, which must have the given required wires.
+    fn validate(text: &str, required_wires: &[&str]) -> Vec<ValidationIssue> {
+        let descriptions: Vec<NodeDescription> = super::super::parse_node_list(text.lines()).collect();
+        validate_node_descriptions(&descriptions, required_wires)
+    }
+
+    // Returns the given wire names as owned strings.
+    fn wires(names: &[&str]) -> Vec<String> {
+        names.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn reports_cycles_with_their_wires() {
+        assert_eq!(
+            validate("c -> a\na -> b\nb -> c", &["a"]),
+            [ValidationIssue::Cycle {
+                wires: wires(&["a", "b", "c", "a"]),
+                other_wire_count: 0,
+            }]
+        );
+
+        // Only the shortest loop through the lowest wire is given, with the rest of the tangle counted.
+        let issues = validate("b OR c -> a\na -> b\nb -> c", &["a"]);
+        assert_eq!(
+            issues,
+            [ValidationIssue::Cycle {
+                wires: wires(&["a", "b", "a"]),
+                other_wire_count: 1,
+            }]
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            "Combinational loop: a -> b -> a (1 other wire is tangled up in this loop)"
+        );
+    }
+
+    #[test]
+    fn reports_multiple_drivers() {
+        assert_eq!(
+            validate("1 -> a\n2 -> a\n3 -> b\n4 -> b\n5 -> b", &["a", "b"]),
+            [
+                ValidationIssue::MultipleDrivers {
+                    wire: String::from("a"),
+                    driver_count: 2,
+                },
+                ValidationIssue::MultipleDrivers {
+                    wire: String::from("b"),
+                    driver_count: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_undefined_wires() {
+        assert_eq!(
+            validate("x AND y -> a\n1 -> x", &["a", "b"]),
+            [
+                ValidationIssue::UndefinedWire {
+                    wire: String::from("y"),
+                    used_by: Some(String::from("a")),
+                },
+                ValidationIssue::UndefinedWire {
+                    wire: String::from("b"),
+                    used_by: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unreachable_nodes_as_warnings() {
+        let issues = validate("1 -> a\na -> z\n2 -> y", &["a"]);
+        assert_eq!(
+            issues,
+            [
+                ValidationIssue::UnreachableNode {
+                    wire: String::from("y")
+                },
+                ValidationIssue::UnreachableNode {
+                    wire: String::from("z")
+                },
+            ]
+        );
+        assert!(!issues.iter().any(ValidationIssue::is_error));
+    }
+}
@@ -0,0 +1,62 @@
+// Evaluates a circuit with some of its wires forced to given values, without changing the circuit itself. This
+// allows trying out many changes to a circuit interactively, without rebuilding it or undoing changes afterwards.
+
+use super::Circuit;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+// A view of a circuit in which some wires are forced to given values.
+//
+// Values of nodes outside the fan-out cone of the overridden wires are the same as in the base circuit, so they are
+// taken from the base circuit's cache when it has them. Values of the other nodes are calculated and cached within
+// the view, so the base circuit is never changed.
+pub struct WhatIfCircuit<'a> {
+    circuit: &'a Circuit,
+    overrides: HashMap<String, u16>,
+    affected_node_names: HashSet<String>,
+    values: RefCell<HashMap<String, u16>>,
+}
+
+impl<'a> WhatIfCircuit<'a> {
+    // Creates a view of the given circuit with the given wires forced to the given values.
+    pub fn new<'b, I>(circuit: &'a Circuit, overrides: I) -> Self
+    where
+        I: IntoIterator<Item = (&'b str, u16)>,
+    {
+        let overrides: HashMap<String, u16> = overrides
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+        let affected_node_names = circuit.get_fan_out_node_names(overrides.keys().map(String::as_str));
+        WhatIfCircuit {
+            circuit,
+            overrides,
+            affected_node_names,
+            values: RefCell::new(HashMap::new()),
+        }
+    }
+
+    // Returns the value that has already been determined for the node with the given name, if any.
+    fn get_cached_value(&self, node_name: &str) -> Option<u16> {
+        if let Some(value) = self.overrides.get(node_name) {
+            return Some(*value);
+        }
+        if !self.affected_node_names.contains(node_name) {
+            if let Some(value) = *self.circuit.node_list[node_name].value.borrow() {
+                return Some(value);
+            }
+        }
+        self.values.borrow().get(node_name).copied()
+    }
+
+    // Returns the output value of the node with the given name.
+    pub fn get_node_value(&self, node_name: &str) -> u16 {
+        self.circuit.evaluate_node(
+            node_name,
+            |name| self.get_cached_value(name),
+            |name, value| {
+                self.values.borrow_mut().insert(name.to_string(), value);
+            },
+        )
+    }
+}
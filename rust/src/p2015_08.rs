@@ -20,20 +20,16 @@ fn parse_list_string_literal(list_string_literal: &str) -> String {
     for c in list_string_literal.chars() {
         match mode {
             // Looking for the opening quote of the string literal.
-            ParserMode::OutsideString => {
-                match c {
-                    '"' => mode = ParserMode::InsideString,
-                    _ => panic!(),
-                };
-            }
+            ParserMode::OutsideString => match c {
+                '"' => mode = ParserMode::InsideString,
+                _ => panic!(),
+            },
             // Processing the characters of the string literal.
-            ParserMode::InsideString => {
-                match c {
-                    '\\' => mode = ParserMode::ProcessingEscape,
-                    '"' => mode = ParserMode::OutsideString,
-                    _ => parsed_string.push(c),
-                };
-            }
+            ParserMode::InsideString => match c {
+                '\\' => mode = ParserMode::ProcessingEscape,
+                '"' => mode = ParserMode::OutsideString,
+                _ => parsed_string.push(c),
+            },
             // Processing an escape sequence.
             ParserMode::ProcessingEscape => {
                 match c {
@@ -48,7 +44,7 @@ fn parse_list_string_literal(list_string_literal: &str) -> String {
                         mode = ParserMode::ProcessingAsciiHexEscape;
                     }
                     _ => panic!(),
-                };
+                }
             }
             // Processing an ASCII hex escape sequence.
             ParserMode::ProcessingAsciiHexEscape => {
@@ -90,7 +86,7 @@ fn encode_list_string(list_string: &str) -> String {
     encoded_string
 }
 
-fn solve(input: &str, _log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, _log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
     // Part 1: Find the total number of characters of code for string literals minus the total number of characters in memory
     // for the values of the strings in the input.
     let mut total_char_diff: isize = 0;
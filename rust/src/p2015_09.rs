@@ -1,14 +1,18 @@
 // Advent of Code 2015, Day 9: "All in a Single Night"
 // https://adventofcode.com/2015/day/9
 
-use std::collections::HashMap;
+use crate::tour::{find_best_tour, Graph, Objective, Tour, TourOptions};
 
+// Represents a set of cities and the distances between them. Distances are directed, so the distance from one city
+// to another may differ from the distance back, and some pairs of cities may have no known distance between them,
+// in which case a route can't go directly between them.
 struct CityGraph {
     cities: Vec<String>,
-    distance_matrix: Vec<Option<u32>>,
+    graph: Graph,
 }
 
 impl CityGraph {
+    // Creates a graph from the given list of distances, each of which is from the first city to the second.
     fn new(distance_list: &[(String, String, u32)]) -> Self {
         fn ensure_city_id(cities: &mut Vec<String>, city_name: &str) -> usize {
             if let Some(city_id) = cities.iter().position(|c| c == city_name) {
@@ -19,115 +23,40 @@ impl CityGraph {
             }
         }
 
-        let mut graph = CityGraph {
-            cities: Vec::new(),
-            distance_matrix: Vec::new(),
-        };
-
-        for (city_name1, city_name2, distance) in distance_list {
-            let city_id1 = ensure_city_id(&mut graph.cities, city_name1);
-            let city_id2 = ensure_city_id(&mut graph.cities, city_name2);
-            let index = graph.get_distance_matrix_index(city_id1, city_id2);
-            graph.distance_matrix.resize(index + 1, None);
-            graph.distance_matrix[index] = Some(*distance);
+        // Find all the cities first, so that the size of the graph is known before it is filled in.
+        let mut cities = Vec::new();
+        let city_id_pairs: Vec<(usize, usize, u32)> = distance_list
+            .iter()
+            .map(|(city_name1, city_name2, distance)| {
+                (
+                    ensure_city_id(&mut cities, city_name1),
+                    ensure_city_id(&mut cities, city_name2),
+                    *distance,
+                )
+            })
+            .collect();
+
+        let mut graph = Graph::new(cities.len());
+        for (city_id1, city_id2, distance) in city_id_pairs {
+            graph.set_weight(city_id1, city_id2, i64::from(distance));
         }
-        graph
+        CityGraph { cities, graph }
     }
 
-    fn get_distance_by_id(&self, city_id1: usize, city_id2: usize) -> Option<u32> {
-        let index = self.get_distance_matrix_index(city_id1, city_id2);
-        self.distance_matrix[index]
+    // Finds the best route of the given kind that visits every city exactly once, or returns None if there is no
+    // such route because some cities aren't connected.
+    fn find_best_route(&self, options: &TourOptions) -> Option<Tour> {
+        find_best_tour(&self.graph, options)
     }
 
-    fn get_distance_matrix_index(&self, city_id1: usize, city_id2: usize) -> usize {
-        let min_id = std::cmp::min(city_id1, city_id2);
-        let max_id = std::cmp::max(city_id1, city_id2);
-        (self.cities.len() * min_id) + max_id
-    }
-}
-
-struct PathSolver<'a> {
-    graph: &'a CityGraph,
-    known_distances: HashMap<(Vec<usize>, usize), u32>,
-}
-
-impl<'a> PathSolver<'a> {
-    pub fn new(graph: &'a CityGraph) -> Self {
-        Self {
-            graph,
-            known_distances: HashMap::new(),
+    // Returns a description of the given route using the names of the cities, e.g. "London -> Dublin -> Belfast =
+    // 605". A closed route is shown returning to its first city.
+    fn describe_route(&self, route: &Tour, is_closed: bool) -> String {
+        let mut city_names: Vec<&str> = route.vertex_ids.iter().map(|id| self.cities[*id].as_str()).collect();
+        if is_closed {
+            city_names.push(city_names[0]);
         }
-    }
-
-    pub fn find_shortest_hamiltonian_path_distance(&mut self) -> Option<u32> {
-        let target_city_id = 0;
-        let through_city_ids = (1..=self.graph.cities.len()).collect::<Vec<_>>();
-        self.find_optimized_path_distance_memoize(&through_city_ids, target_city_id, true)
-    }
-
-    pub fn find_longest_hamiltonian_path_distance(&mut self) -> Option<u32> {
-        let target_city_id = 0;
-        let through_city_ids = (1..=self.graph.cities.len()).collect::<Vec<_>>();
-        self.find_optimized_path_distance_memoize(&through_city_ids, target_city_id, false)
-    }
-
-    fn find_optimized_path_distance_memoize(
-        &mut self,
-        through_city_ids: &[usize],
-        target_city_id: usize,
-        find_shortest: bool,
-    ) -> Option<u32> {
-        let key = (through_city_ids.to_vec(), target_city_id);
-        if let Some(distance) = self.known_distances.get(&key) {
-            return Some(*distance);
-        }
-
-        let distance = self.find_optimized_path_distance(through_city_ids, target_city_id, find_shortest);
-        if let Some(distance) = distance {
-            self.known_distances.insert(key, distance);
-        }
-        distance
-    }
-
-    fn find_optimized_path_distance(
-        &mut self,
-        through_city_ids: &[usize],
-        target_city_id: usize,
-        find_shortest: bool,
-    ) -> Option<u32> {
-        if through_city_ids.is_empty() {
-            return Some(0);
-        }
-
-        if through_city_ids.len() == 1 {
-            return self.get_city_distance(through_city_ids[0], target_city_id);
-        }
-
-        let mut shortest_distance = None;
-        for id in through_city_ids {
-            let mut remaining_city_ids = through_city_ids.to_vec();
-            remaining_city_ids.retain(|c| c != id);
-            let distance = self.find_optimized_path_distance_memoize(&remaining_city_ids, *id, find_shortest);
-            if let Some(distance) = distance {
-                let total_distance = self.get_city_distance(*id, target_city_id).unwrap() + distance;
-                if shortest_distance.is_none()
-                    || (find_shortest && total_distance < shortest_distance.unwrap())
-                    || (!find_shortest && total_distance > shortest_distance.unwrap())
-                {
-                    shortest_distance = Some(total_distance);
-                }
-            }
-        }
-        shortest_distance
-    }
-
-    // Returns the distance between the two cities with the given IDs or None if no path exists between them,
-    // where 0 is the virtual starting city.
-    fn get_city_distance(&self, city_id1: usize, city_id2: usize) -> Option<u32> {
-        if city_id1 == 0 || city_id2 == 0 {
-            return Some(0);
-        }
-        self.graph.get_distance_by_id(city_id1 - 1, city_id2 - 1)
+        format!("{} = {}", city_names.join(" -> "), route.weight)
     }
 }
 
@@ -138,27 +67,62 @@ fn parse_city_graph(input: &str) -> CityGraph {
         let mut parts = line.split(" = ");
         let mut cities = parts.next().unwrap().split(" to ");
         let distance = parts.next().unwrap().parse::<u32>().unwrap();
-        distance_list.push((
-            cities.next().unwrap().to_string(),
-            cities.next().unwrap().to_string(),
-            distance,
-        ));
+        let (city_name1, city_name2) = (cities.next().unwrap().to_string(), cities.next().unwrap().to_string());
+
+        // Distances are the same in both directions.
+        distance_list.push((city_name1.clone(), city_name2.clone(), distance));
+        distance_list.push((city_name2, city_name1, distance));
     }
     CityGraph::new(&distance_list)
 }
 
-fn solve(input: &str, _log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
     let graph = parse_city_graph(input);
+    let route_options = |objective, is_closed, start_id| TourOptions {
+        objective,
+        is_closed,
+        start_id,
+    };
 
     // Part 1: Find the shortest Hamiltonian path through the given cities.
-    let mut shortest_path_solver = PathSolver::new(&graph);
-    let part1_result = shortest_path_solver.find_shortest_hamiltonian_path_distance().unwrap();
+    let shortest_route = graph
+        .find_best_route(&route_options(Objective::Minimize, false, None))
+        .expect("There is no route that visits every city exactly once");
 
     // Part 2: Find the longest Hamiltonian path through the given cities.
-    let mut longest_path_solver = PathSolver::new(&graph);
-    let part2_result = longest_path_solver.find_longest_hamiltonian_path_distance().unwrap();
+    let longest_route = graph
+        .find_best_route(&route_options(Objective::Maximize, false, None))
+        .expect("There is no route that visits every city exactly once");
+
+    // If logging is enabled, print the routes that were found, along with the shortest route starting from the first
+    // city in the input and the shortest round trip, where they exist.
+    if let Some(log_fn) = log_fn {
+        log_fn(&format!(
+            "Shortest route: {}",
+            graph.describe_route(&shortest_route, false)
+        ));
+        log_fn(&format!(
+            "Longest route: {}",
+            graph.describe_route(&longest_route, false)
+        ));
+        for (description, options) in [
+            (
+                "Shortest route from the first city",
+                route_options(Objective::Minimize, false, Some(0)),
+            ),
+            ("Shortest round trip", route_options(Objective::Minimize, true, None)),
+        ] {
+            match graph.find_best_route(&options) {
+                Some(route) => log_fn(&format!(
+                    "{description}: {}",
+                    graph.describe_route(&route, options.is_closed)
+                )),
+                None => log_fn(&format!("{description}: none")),
+            }
+        }
+    }
 
-    (part1_result.to_string(), part2_result.to_string())
+    (shortest_route.weight.to_string(), longest_route.weight.to_string())
 }
 
 #[linkme::distributed_slice(crate::SOLUTIONS)]
@@ -1,6 +1,12 @@
 // Advent of Code 2015, Day 10: "Elves Look, Elves Say"
 // https://adventofcode.com/2015/day/10
 
+use std::collections::{HashMap, HashSet};
+
+// The maximum number of leading digits tracked when working out how the start of a string evolves. The start of a
+// string only depends on a short prefix, so this bounds the work without limiting which strings can be split.
+const MAX_TRACKED_PREFIX_LENGTH: usize = 32;
+
 // Applies the look-and-say algorithm to the given input buffer and stores the result in the given output buffer.
 // The details of the look-and-say algorithm are documented on the apply_look_and_say function.
 fn apply_look_and_say_to_buffer(input: &[char], output: &mut Vec<char>) {
@@ -53,41 +59,215 @@ fn apply_look_and_say(input: &str, iterations: usize) -> String {
     input_buffer.iter().collect()
 }
 
-fn solve(input: &str, log_fn: Option<fn(&str)>) -> (String, String) {
-    // Part 1: Find the length of the string after applying the look-and-say algorithm 40 times.
-    let mut string_after_40_iterations;
-    // If logging is enabled, log intermediate results. Otherwise, just apply the look-and-say
-    // algorithm 40 times.
+// Returns every digit that the given string starts with, now or after any number of applications of the look-and-say
+// algorithm, or None if they can't all be determined.
+//
+// The start of the string only depends on a prefix of it, so only a prefix is evolved. All runs of digits in the
+// prefix except the last are complete, since a different digit follows them, so applying look-and-say to those runs
+// gives a correct prefix of the next string. Since the tracked prefix has a bounded length, it eventually repeats, and
+// then every leading digit has been seen.
+fn get_future_leading_digits(string: &str) -> Option<HashSet<char>> {
+    // Whether the prefix is the whole string, in which case its last run is also complete.
+    let mut is_whole_string = true;
+    let mut prefix: Vec<char> = string.chars().collect();
+    let mut seen_prefixes = HashSet::new();
+    let mut leading_digits = HashSet::new();
+    loop {
+        leading_digits.insert(*prefix.first()?);
+        if !seen_prefixes.insert((prefix.clone(), is_whole_string)) {
+            return Some(leading_digits);
+        }
+
+        if !is_whole_string {
+            let last_digit = *prefix.last().unwrap();
+            while prefix.last() == Some(&last_digit) {
+                prefix.pop();
+            }
+            if prefix.is_empty() {
+                return None;
+            }
+        }
+        let mut next_prefix = Vec::with_capacity(prefix.len() * 2);
+        apply_look_and_say_to_buffer(&prefix, &mut next_prefix);
+        if next_prefix.len() > MAX_TRACKED_PREFIX_LENGTH {
+            next_prefix.truncate(MAX_TRACKED_PREFIX_LENGTH);
+            is_whole_string = false;
+        }
+        prefix = next_prefix;
+    }
+}
+
+// Splits the given string into elements: substrings that evolve independently of each other under the look-and-say
+// algorithm, so that the string after any number of iterations is the concatenation of what each element becomes.
+//
+// Applying look-and-say to two adjacent strings gives the same result as applying it to each of them separately
+// exactly when the last digit of the first differs from the first digit of the second, so that no run of digits
+// spans them. The last digit of a string never changes under look-and-say, so two strings evolve independently
+// forever if the last digit of the first is never a leading digit of the second. Strings are only split where this
+// can be shown, so that the split is always exact. For strings made of the digits 1 to 3, this gives Conway's 92
+// audioactive elements.
+fn split_into_elements(string: &str) -> Vec<&str> {
+    let digits: Vec<char> = string.chars().collect();
+    let mut elements = Vec::new();
+    let mut element_start = 0;
+    for i in 1..digits.len() {
+        if digits[i - 1] != digits[i]
+            && get_future_leading_digits(&string[i..])
+                .is_some_and(|leading_digits| !leading_digits.contains(&digits[i - 1]))
+        {
+            elements.push(&string[element_start..i]);
+            element_start = i;
+        }
+    }
+    elements.push(&string[element_start..]);
+    elements
+}
+
+// Keeps track of the elements that have been found, and what each one decays into after an application of the
+// look-and-say algorithm.
+struct ElementTable {
+    elements: Vec<String>,
+    element_ids: HashMap<String, usize>,
+
+    // The IDs of the elements that each element decays into, if that has been worked out.
+    decay_products: Vec<Option<Vec<usize>>>,
+}
+
+impl ElementTable {
+    fn new() -> Self {
+        Self {
+            elements: Vec::new(),
+            element_ids: HashMap::new(),
+            decay_products: Vec::new(),
+        }
+    }
+
+    // Returns the number of elements that have been found.
+    fn element_count(&self) -> usize {
+        self.elements.len()
+    }
+
+    // Returns the ID of the given element, adding it to the table if it hasn't been seen before.
+    fn get_element_id(&mut self, element: &str) -> usize {
+        if let Some(element_id) = self.element_ids.get(element) {
+            return *element_id;
+        }
+        let element_id = self.elements.len();
+        self.elements.push(element.to_string());
+        self.element_ids.insert(element.to_string(), element_id);
+        self.decay_products.push(None);
+        element_id
+    }
+
+    // Returns the IDs of the elements that the element with the given ID decays into.
+    fn get_decay_products(&mut self, element_id: usize) -> Vec<usize> {
+        if let Some(decay_products) = &self.decay_products[element_id] {
+            return decay_products.clone();
+        }
+        let decayed_element = apply_look_and_say(&self.elements[element_id], 1);
+        let decay_products: Vec<usize> = split_into_elements(&decayed_element)
+            .into_iter()
+            .map(|element| self.get_element_id(element))
+            .collect();
+        self.decay_products[element_id] = Some(decay_products.clone());
+        decay_products
+    }
+}
+
+// Returns the lengths of the strings generated by applying the look-and-say algorithm to the given input string
+// from zero up to the given number of times, without building the strings.
+//
+// The input is split into elements, and only the number of each element is tracked. Each iteration replaces every
+// element with the elements that it decays into, and the length of the string is the total length of its elements.
+// The lengths grow by about 30% per iteration, so they are calculated exactly with 128-bit integers, which is enough
+// for a few hundred iterations.
+fn get_look_and_say_lengths(element_table: &mut ElementTable, input: &str, iterations: usize) -> Vec<u128> {
+    let mut element_counts: HashMap<usize, u128> = HashMap::new();
+    for element in split_into_elements(input) {
+        *element_counts.entry(element_table.get_element_id(element)).or_default() += 1;
+    }
+
+    let mut lengths = Vec::with_capacity(iterations + 1);
+    for i in 0..=iterations {
+        let length = element_counts
+            .iter()
+            .map(|(element_id, count)| count.checked_mul(element_table.elements[*element_id].len() as u128))
+            .try_fold(0u128, |total, length| total.checked_add(length?));
+        lengths.push(length.unwrap_or_else(|| panic!("The length after {i} iterations is too large to represent")));
+        if i == iterations {
+            break;
+        }
+
+        let mut next_element_counts: HashMap<usize, u128> = HashMap::new();
+        for (element_id, count) in element_counts {
+            for decay_product_id in element_table.get_decay_products(element_id) {
+                *next_element_counts.entry(decay_product_id).or_default() += count;
+            }
+        }
+        element_counts = next_element_counts;
+    }
+    lengths
+}
+
+fn solve(input: &str, log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
+    // Calculate the lengths of the strings from the counts of the elements that make them up.
+    let mut element_table = ElementTable::new();
+    let lengths = get_look_and_say_lengths(&mut element_table, input, 50);
+
+    // If logging is enabled, log the first few strings built by the look-and-say algorithm.
     if let Some(log_fn) = log_fn {
-        string_after_40_iterations = input.to_string();
-        for i in 0..10 {
-            string_after_40_iterations = apply_look_and_say(&string_after_40_iterations, 1);
-            log_fn(&format!(
-                "String after {} iterations: {}",
-                i + 1,
-                string_after_40_iterations
-            ));
+        let mut string = input.to_string();
+        for i in 1..=10 {
+            string = apply_look_and_say(&string, 1);
+            log_fn(&format!("String after {i} iterations: {string}"));
         }
+    }
 
-        for i in 10..40 {
-            string_after_40_iterations = apply_look_and_say(&string_after_40_iterations, 1);
-            log_fn(&format!(
-                "Length of string after {} iterations: {}",
-                i + 1,
-                string_after_40_iterations.len()
-            ));
+    // If logging is enabled, log the lengths of the later strings, and the length of a string far longer than could
+    // ever be built.
+    if let Some(log_fn) = log_fn {
+        for (i, length) in lengths.iter().enumerate().skip(11) {
+            log_fn(&format!("Length of string after {i} iterations: {length}"));
         }
-    } else {
-        string_after_40_iterations = apply_look_and_say(input, 40);
+        let length = get_look_and_say_lengths(&mut element_table, input, 100)[100];
+        log_fn(&format!("Length of string after 100 iterations: {length}"));
+        log_fn(&format!("Number of elements found: {}", element_table.element_count()));
     }
-    let part1_result = string_after_40_iterations.len();
+
+    // Part 1: Find the length of the string after applying the look-and-say algorithm 40 times.
+    let part1_result = lengths[40];
 
     // Part 2: Find the length of the string after applying the look-and-say algorithm 50 times.
-    let string_after_50_iterations = apply_look_and_say(&string_after_40_iterations, 10);
-    let part2_result = string_after_50_iterations.len();
+    let part2_result = lengths[50];
 
     (part1_result.to_string(), part2_result.to_string())
 }
 
 #[linkme::distributed_slice(crate::SOLUTIONS)]
 static SOLUTION: crate::Solution = crate::Solution::new(2015, 10, solve);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The number of iterations for which the lengths calculated from element counts are checked against the lengths
+    // of the strings actually built by the look-and-say algorithm.
+    const CROSS_CHECK_ITERATION_COUNT: usize = 30;
+
+    #[test]
+    fn lengths_from_element_counts_match_built_strings() {
+        for input in ["1", "1113222113", "1321131112", "3113322113"] {
+            let mut element_table = ElementTable::new();
+            let lengths = get_look_and_say_lengths(&mut element_table, input, CROSS_CHECK_ITERATION_COUNT);
+            let mut string = input.to_string();
+            for (i, length) in lengths.iter().enumerate() {
+                assert_eq!(
+                    string.len() as u128,
+                    *length,
+                    "The length calculated from element counts for {input} after {i} iterations is wrong"
+                );
+                string = apply_look_and_say(&string, 1);
+            }
+        }
+    }
+}
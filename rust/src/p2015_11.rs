@@ -1,135 +1,308 @@
 // Advent of Code 2015, Day 11: "Corporate Policy"
 // https://adventofcode.com/2015/day/11
 
-// This struct implements an iterator over valid passwords, given a starting password and a set of invalid characters.
-struct PasswordIterator {
-    password: Vec<char>,
-    invalid_characters: Vec<char>,
+// The smallest and largest characters that passwords may contain.
+const FIRST_CHARACTER: char = 'a';
+const LAST_CHARACTER: char = 'z';
+
+// Returns the password that follows the given one, incrementing it like a number whose digits are the characters
+// from 'a' to 'z', or None if the given password is the last of its length.
+fn increment_password(password: &[char]) -> Option<Vec<char>> {
+    let mut next_password = password.to_vec();
+    for c in next_password.iter_mut().rev() {
+        if *c < LAST_CHARACTER {
+            *c = (*c as u8 + 1) as char;
+            return Some(next_password);
+        }
+        *c = FIRST_CHARACTER;
+    }
+    None
 }
 
-impl PasswordIterator {
-    // Creates a new PasswordIterator instance with the given starting password and invalid characters.
-    pub fn new(password: &str, invalid_characters: &[char]) -> Self {
-        Self {
-            password: password.chars().collect(),
-            invalid_characters: invalid_characters.to_vec(),
+// Returns the smallest password of the same length that is greater than or equal to the given one and can be
+// completed according to the given function, or None if there is no such password. The function is given the start
+// of a password and the number of characters still to be chosen, and must return exactly whether those characters
+// can be chosen so that the password satisfies a rule.
+//
+// The result keeps as much of the start of the given password as possible: it finds the rightmost character that
+// can be increased while keeping the password completable, then fills in the characters after it with the smallest
+// ones that keep it completable. This jumps straight past every password that can't satisfy the rule.
+fn find_next_completable_password(
+    password: &[char],
+    can_be_completed: impl Fn(&[char], usize) -> bool,
+) -> Option<Vec<char>> {
+    let length = password.len();
+    if can_be_completed(password, 0) {
+        return Some(password.to_vec());
+    }
+
+    let mut next_password = (0..length).rev().find_map(|i| {
+        let mut prefix = password[..i].to_vec();
+        if !can_be_completed(&prefix, length - i) {
+            return None;
         }
+        let next_character = ((password[i] as u8 + 1) as char..=LAST_CHARACTER).find(|c| {
+            prefix.push(*c);
+            let is_completable = can_be_completed(&prefix, length - i - 1);
+            prefix.pop();
+            is_completable
+        })?;
+        prefix.push(next_character);
+        Some(prefix)
+    })?;
+
+    while next_password.len() < length {
+        let next_character = (FIRST_CHARACTER..=LAST_CHARACTER)
+            .find(|c| {
+                next_password.push(*c);
+                let is_completable = can_be_completed(&next_password, length - next_password.len());
+                next_password.pop();
+                is_completable
+            })
+            .expect("A completable password can be extended by at least one character");
+        next_password.push(next_character);
     }
+    Some(next_password)
+}
 
-    // Given a character, return the next valid password character and whether a
-    // carry/wraparound occurred.
-    fn get_next_character(&mut self, c: char) -> (char, bool) {
-        let mut next_character = c;
-        let mut carry = false;
-        loop {
-            if next_character == 'z' {
-                next_character = 'a';
-                carry = true;
-            } else {
-                next_character = (next_character as u8 + 1) as char;
-            }
+// Defines a rule that passwords must satisfy. Rules can be combined into a PasswordPolicy, which is itself a rule,
+// and any function from a password to whether it is valid can be used as a rule.
+trait PasswordRule {
+    // Returns true if the given password satisfies the rule and false otherwise.
+    fn is_satisfied_by(&self, password: &[char]) -> bool;
 
-            if !self.invalid_characters.contains(&next_character) {
-                break;
-            }
+    // Returns the smallest password of the same length that is greater than or equal to the given one and satisfies
+    // the rule, or None if there is no such password. This tries each password in turn; rules that can work out the
+    // next satisfying password directly should override it.
+    fn get_next_satisfying_password(&self, password: &[char]) -> Option<Vec<char>> {
+        let mut password = password.to_vec();
+        while !self.is_satisfied_by(&password) {
+            password = increment_password(&password)?;
         }
-        (next_character, carry)
+        Some(password)
     }
 }
 
-// Returns true if the given password is valid according to the rule set and false otherwise.
-// In addition to the built-in rule set, the password may not contain any of the given invalid characters.
-fn is_valid_password(password: &[char], invalid_characters: &[char]) -> bool {
-    let mut has_increasing_straight = false;
-    let mut has_two_pairs = false;
+// Implement PasswordRule for functions, so that rules can be defined without declaring a type for them.
+impl<F: Fn(&[char]) -> bool> PasswordRule for F {
+    fn is_satisfied_by(&self, password: &[char]) -> bool {
+        self(password)
+    }
+}
 
-    // Iterate over the characters in the given password, keeping track of the last two characters seen.
-    let mut last_char = '\0';
-    let mut last_last_char = '\0';
-    let mut first_pair_char = '\0';
-    for (i, c_ref) in password.iter().enumerate() {
-        let c = *c_ref;
+// A rule that passwords may not contain any of the given characters.
+struct ForbiddenCharacters(Vec<char>);
 
-        // Check whether the given string contains any invalid characters.
-        if invalid_characters.contains(&c) {
-            return false;
-        }
+impl ForbiddenCharacters {
+    // Returns whether a password starting with the given characters can be completed with the given number of
+    // additional characters so that it satisfies this rule.
+    fn can_be_completed(&self, prefix: &[char], remaining_length: usize) -> bool {
+        !prefix.iter().any(|c| self.0.contains(c))
+            && (remaining_length == 0 || (FIRST_CHARACTER..=LAST_CHARACTER).any(|c| !self.0.contains(&c)))
+    }
+}
 
-        // Check whether the given string contains a straight of three increasing characters.
-        if i > 1 && (c as u8) == ((last_char as u8) + 1) && ((last_char as u8) == ((last_last_char as u8) + 1)) {
-            has_increasing_straight = true;
-        }
+impl PasswordRule for ForbiddenCharacters {
+    fn is_satisfied_by(&self, password: &[char]) -> bool {
+        self.can_be_completed(password, 0)
+    }
+
+    // When the password contains a forbidden character, the first one is replaced by the next allowed character and
+    // every character after it by the smallest allowed character.
+    fn get_next_satisfying_password(&self, password: &[char]) -> Option<Vec<char>> {
+        find_next_completable_password(password, |prefix, remaining_length| {
+            self.can_be_completed(prefix, remaining_length)
+        })
+    }
+}
 
-        // Check whether the given string contains two non-overlapping pairs of characters.
-        if i > 0 && c == last_char {
-            if first_pair_char == '\0' {
-                first_pair_char = c;
-            } else if c != first_pair_char {
-                has_two_pairs = true;
+// A rule that passwords must contain a straight of at least the given number of increasing consecutive characters,
+// such as "abc" for a length of three.
+struct IncreasingStraight(usize);
+
+impl IncreasingStraight {
+    // Returns whether a password starting with the given characters can be completed with the given number of
+    // additional characters so that it satisfies this rule.
+    fn can_be_completed(&self, prefix: &[char], remaining_length: usize) -> bool {
+        let straight_length = self.0;
+        let alphabet_length = (LAST_CHARACTER as usize) - (FIRST_CHARACTER as usize) + 1;
+
+        // Find the longest straight in the prefix and the straight that it ends with.
+        let mut longest_length = 0;
+        let mut current_length = 0;
+        for (i, c) in prefix.iter().enumerate() {
+            if i > 0 && *c as u32 == prefix[i - 1] as u32 + 1 {
+                current_length += 1;
+            } else {
+                current_length = 1;
             }
+            longest_length = usize::max(longest_length, current_length);
         }
+        if longest_length >= straight_length {
+            return true;
+        }
+
+        // Otherwise, either the straight at the end of the prefix can be continued, or a new straight can be started.
+        let extension_length = straight_length - current_length;
+        let can_extend = current_length > 0
+            && remaining_length >= extension_length
+            && (*prefix.last().unwrap() as usize) + extension_length <= LAST_CHARACTER as usize;
+        can_extend || (remaining_length >= straight_length && straight_length <= alphabet_length)
+    }
+}
 
-        last_last_char = last_char;
-        last_char = c;
+impl PasswordRule for IncreasingStraight {
+    fn is_satisfied_by(&self, password: &[char]) -> bool {
+        self.can_be_completed(password, 0)
     }
 
-    has_increasing_straight && has_two_pairs
+    fn get_next_satisfying_password(&self, password: &[char]) -> Option<Vec<char>> {
+        find_next_completable_password(password, |prefix, remaining_length| {
+            self.can_be_completed(prefix, remaining_length)
+        })
+    }
 }
 
-// Implement the standard Iterator trait for PasswordIterator.
-impl Iterator for PasswordIterator {
-    type Item = String;
+// A rule that passwords must contain pairs of at least the given number of different characters, such as "aa" and
+// "bb" for two pairs. Pairs of different characters can never overlap.
+struct DifferentPairs(usize);
 
-    // Returns the next password after the current password, skipping any
-    // passwords that contain invalid characters.
-    fn next(&mut self) -> Option<Self::Item> {
-        let mut password = self.password.clone();
+impl DifferentPairs {
+    // Returns whether a password starting with the given characters can be completed with the given number of
+    // additional characters so that it satisfies this rule.
+    fn can_be_completed(&self, prefix: &[char], remaining_length: usize) -> bool {
+        let alphabet_length = (LAST_CHARACTER as usize) - (FIRST_CHARACTER as usize) + 1;
+        let mut paired_characters: Vec<char> = prefix.windows(2).filter(|w| w[0] == w[1]).map(|w| w[0]).collect();
+        paired_characters.sort_unstable();
+        paired_characters.dedup();
+        let missing_pair_count = self.0.saturating_sub(paired_characters.len());
+        if missing_pair_count == 0 {
+            return true;
+        }
 
-        loop {
-            // Increment the least significant character until it is valid.
-            // If a carry occurs, increment the next most significant character.
-            // Continue until no carry occurs or a carry occurs on the most significant character.
-            let mut i = password.len() - 1;
-            loop {
-                // Get the next valid password character and whether a carry occurred.
-                // Update the current password character to the next valid password character,
-                // and check whether the next most significant character should be incremented.
-                let (next_character, carry) = self.get_next_character(password[i]);
-                password[i] = next_character;
-                if !carry {
-                    break;
-                }
+        // The last character of the prefix can be paired with a single additional character, if it isn't already
+        // paired. Every other new pair takes two additional characters.
+        let possible_pair_count = match prefix.last() {
+            Some(c) if remaining_length > 0 && !paired_characters.contains(c) => 1 + (remaining_length - 1) / 2,
+            _ => remaining_length / 2,
+        };
+        possible_pair_count >= missing_pair_count && paired_characters.len() + missing_pair_count <= alphabet_length
+    }
+}
+
+impl PasswordRule for DifferentPairs {
+    fn is_satisfied_by(&self, password: &[char]) -> bool {
+        self.can_be_completed(password, 0)
+    }
+
+    fn get_next_satisfying_password(&self, password: &[char]) -> Option<Vec<char>> {
+        find_next_completable_password(password, |prefix, remaining_length| {
+            self.can_be_completed(prefix, remaining_length)
+        })
+    }
+}
+
+// A set of rules that passwords must all satisfy.
+struct PasswordPolicy {
+    rules: Vec<Box<dyn PasswordRule>>,
+}
+
+impl PasswordPolicy {
+    // Creates a new PasswordPolicy instance with the given rules.
+    fn new(rules: Vec<Box<dyn PasswordRule>>) -> Self {
+        Self { rules }
+    }
+
+    // Creates the policy of Santa's corporation: passwords must contain a straight of three increasing characters and
+    // two different pairs, and may not contain the letters 'i', 'o' or 'l'.
+    fn corporate() -> Self {
+        Self::new(vec![
+            Box::new(ForbiddenCharacters(vec!['i', 'o', 'l'])),
+            Box::new(IncreasingStraight(3)),
+            Box::new(DifferentPairs(2)),
+        ])
+    }
+}
+
+impl PasswordRule for PasswordPolicy {
+    fn is_satisfied_by(&self, password: &[char]) -> bool {
+        self.rules.iter().all(|rule| rule.is_satisfied_by(password))
+    }
 
-                // If a carry occurred on the most significant character, return None,
-                // since there is no next valid password.
-                if i == 0 {
-                    return None;
+    // Each rule in turn moves the password forward to the next one that satisfies it, until the password satisfies
+    // every rule. No rule moves past a password that satisfies it, so no password that satisfies every rule is missed.
+    fn get_next_satisfying_password(&self, password: &[char]) -> Option<Vec<char>> {
+        let mut password = password.to_vec();
+        loop {
+            let mut is_unchanged = true;
+            for rule in &self.rules {
+                let next_password = rule.get_next_satisfying_password(&password)?;
+                if next_password != password {
+                    password = next_password;
+                    is_unchanged = false;
                 }
-                // Otherwise, continue to the next most significant character.
-                i -= 1;
             }
-
-            // If the current password is valid, return it.
-            if is_valid_password(&password, &self.invalid_characters) {
-                break;
+            if is_unchanged {
+                return Some(password);
             }
         }
+    }
+}
+
+// This struct implements an iterator over the passwords that satisfy a policy, given a starting password.
+struct PasswordIterator {
+    password: Vec<char>,
+    policy: PasswordPolicy,
+}
+
+impl PasswordIterator {
+    // Creates a new PasswordIterator instance with the given starting password and policy.
+    pub fn new(password: &str, policy: PasswordPolicy) -> Self {
+        assert!(
+            password
+                .chars()
+                .all(|c| (FIRST_CHARACTER..=LAST_CHARACTER).contains(&c)),
+            "Passwords may only contain the characters '{FIRST_CHARACTER}' to '{LAST_CHARACTER}'"
+        );
+        Self {
+            password: password.chars().collect(),
+            policy,
+        }
+    }
+}
+
+// Implement the standard Iterator trait for PasswordIterator.
+impl Iterator for PasswordIterator {
+    type Item = String;
 
-        self.password = password;
+    // Returns the next password after the current password that satisfies the policy, or None if there is no such
+    // password of the same length.
+    fn next(&mut self) -> Option<Self::Item> {
+        let password = increment_password(&self.password)?;
+        self.password = self.policy.get_next_satisfying_password(&password)?;
         Some(self.password.iter().collect())
     }
 }
 
-fn solve(input: &str, _log_fn: Option<fn(&str)>) -> (String, String) {
-    const INVALID_CHARACTERS: [char; 3] = ['i', 'o', 'l'];
-
+fn solve(input: &str, log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
     // Part 1: Find the next valid password after the input password.
-    let mut password_iterator = PasswordIterator::new(input, &INVALID_CHARACTERS);
+    let mut password_iterator = PasswordIterator::new(input, PasswordPolicy::corporate());
     let part1_result = password_iterator.next().unwrap();
 
     // Part 2: Find the next valid password after the part 1 result.
     let part2_result = password_iterator.next().unwrap();
 
+    // If logging is enabled, also find the next password under a stricter policy, which adds a rule of its own to
+    // the corporate policy: no character may appear three times in a row.
+    if let Some(log_fn) = log_fn {
+        let has_no_triples = |password: &[char]| password.windows(3).all(|w| w[0] != w[1] || w[1] != w[2]);
+        let strict_policy = PasswordPolicy::new(vec![Box::new(PasswordPolicy::corporate()), Box::new(has_no_triples)]);
+        match PasswordIterator::new(input, strict_policy).next() {
+            Some(password) => log_fn(&format!("Next password under the strict policy: {password}")),
+            None => log_fn("There is no next password under the strict policy"),
+        }
+    }
+
     (part1_result, part2_result)
 }
 
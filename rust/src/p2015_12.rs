@@ -1,40 +1,641 @@
 // Advent of Code 2015, Day 12: "JSAbacusFramework.io"
 // https://adventofcode.com/2015/day/12
 
-use serde_json::Value;
-
-// Given a JSON value, returns the sum of all numbers in the JSON, excluding any object that has a value
-// with the given name.
-fn get_sum_of_numbers(value: &Value, exclude_name: Option<&str>) -> i64 {
-    match value {
-        Value::Number(number) => number.as_i64().unwrap(),
-        Value::Array(array) => array.iter().fold(0, |sum, array_value| {
-            sum + get_sum_of_numbers(array_value, exclude_name)
-        }),
-        Value::Object(map) => {
-            if exclude_name.is_some() && map.values().any(|value| value == exclude_name.unwrap()) {
-                return 0;
+mod filter;
+
+use filter::{ExcludedObjects, Filter, NumberStatistics, PathSegment, PathStatus};
+use std::io::{self, BufRead, Write};
+use std::ops::Range;
+use std::path::Path;
+
+// Returns an error for JSON that can't be parsed, with the given message.
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// Returns whether the given text is a number in JSON syntax, e.g. "-12", "0.5" or "1e+30".
+fn is_json_number(text: &str) -> bool {
+    let is_digits = |text: &str| !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit());
+    let text = text.strip_prefix('-').unwrap_or(text);
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (text, None),
+    };
+    let (integer_part, fraction_part) = match mantissa.split_once('.') {
+        Some((integer_part, fraction_part)) => (integer_part, Some(fraction_part)),
+        None => (mantissa, None),
+    };
+    is_digits(integer_part)
+        && (integer_part == "0" || !integer_part.starts_with('0'))
+        && fraction_part.is_none_or(is_digits)
+        && exponent.is_none_or(|exponent| is_digits(exponent.strip_prefix(['+', '-']).unwrap_or(exponent)))
+}
+
+// Converts the given integer to the nearest floating-point number, which loses precision for large integers.
+#[allow(clippy::cast_precision_loss)]
+fn to_float(value: i128) -> f64 {
+    value as f64
+}
+
+// Represents a number read from a JSON document. Integers are kept exactly as long as they fit in 128 bits; any other
+// numbers are kept approximately as floating-point numbers.
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Integer(i128),
+    Float(f64),
+}
+
+impl Number {
+    // Parses the given text in JSON syntax into a number.
+    fn parse(text: &str) -> io::Result<Self> {
+        if !is_json_number(text) {
+            return Err(invalid_data(format!("Invalid number \"{text}\"")));
+        }
+        Ok(match text.parse::<i128>() {
+            Ok(value) => Number::Integer(value),
+            // The text is valid JSON, so it can always be parsed as a floating-point number, though it may be
+            // rounded or become infinite.
+            Err(_) => Number::Float(text.parse().unwrap()),
+        })
+    }
+
+    // Returns the nearest floating-point number to this number.
+    fn to_float(self) -> f64 {
+        match self {
+            Number::Integer(value) => to_float(value),
+            Number::Float(value) => value,
+        }
+    }
+}
+
+// Represents the sum of a set of numbers. Integers are added exactly as long as the sum fits in 128 bits; any other
+// numbers, such as fractions or integers too large to fit, are added approximately as floating-point numbers.
+#[derive(Debug, Default, Clone, Copy)]
+struct NumberSum {
+    integer_sum: i128,
+    float_sum: f64,
+    is_approximate: bool,
+}
+
+impl NumberSum {
+    // Adds the given integer to the sum, falling back to floating point if the exact sum would overflow.
+    fn add_integer(&mut self, value: i128) {
+        if let Some(integer_sum) = self.integer_sum.checked_add(value) {
+            self.integer_sum = integer_sum;
+        } else {
+            self.add_float(to_float(value));
+        }
+    }
+
+    // Adds the given floating-point number to the sum.
+    fn add_float(&mut self, value: f64) {
+        self.float_sum += value;
+        self.is_approximate = true;
+    }
+
+    // Adds the given number to the sum.
+    fn add_number(&mut self, number: Number) {
+        match number {
+            Number::Integer(value) => self.add_integer(value),
+            Number::Float(value) => self.add_float(value),
+        }
+    }
+
+    // Adds the given sum to this one.
+    fn add_sum(&mut self, other: &NumberSum) {
+        self.add_integer(other.integer_sum);
+        if other.is_approximate {
+            self.add_float(other.float_sum);
+        }
+    }
+}
+
+// Implement the Display trait for NumberSum, showing exact sums as integers.
+impl std::fmt::Display for NumberSum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_approximate {
+            write!(f, "{}", to_float(self.integer_sum) + self.float_sum)
+        } else {
+            write!(f, "{}", self.integer_sum)
+        }
+    }
+}
+
+// Describes what may come next in a JSON document, given what has been read so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expecting {
+    Value,
+    ValueOrEnd,
+    Key,
+    KeyOrEnd,
+    Colon,
+    CommaOrEnd,
+    Nothing,
+}
+
+// The kinds of containers that values can be in. The whole document is treated as a container of a single value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ContainerKind {
+    Document,
+    Array,
+    Object,
+}
+
+// Represents a container that is being read, with the sums of the numbers read so far inside it. Containers are
+// pushed and popped for every array and object in the document, so anything that needs memory of its own is kept by
+// the NumberSummer instead, and only referred to here.
+#[derive(Debug)]
+struct Container {
+    kind: ContainerKind,
+    expecting: Expecting,
+    // How the container relates to the filter. The whole document is treated as being partially selected, so that
+    // its value is checked against the filter's paths.
+    status: PathStatus,
+    // The range of NumberSummer::keys that holds the key of the value being read, for objects, and the index of the
+    // value being read, for arrays.
+    key: Range<usize>,
+    index: usize,
+
+    // The sum of all the numbers in the container.
+    sum: NumberSum,
+    // The statistics of the numbers in the container that the filter keeps, not counting those inside excluded
+    // objects.
+    included: NumberStatistics,
+    // The mark of NumberSummer::excluded_objects from when the container was started, so that the objects that it
+    // excludes can be removed if it is excluded itself.
+    excluded_objects_mark: (usize, usize),
+    // Whether the container is an object that the filter excludes, so that its numbers don't count towards the
+    // included statistics of the containers around it.
+    is_excluded: bool,
+}
+
+impl Container {
+    // Creates a new, empty container of the given kind, which relates to the filter as given. Its keys are stored from
+    // the given offset of NumberSummer::keys, and the excluded objects in it are added after the given mark.
+    fn new(kind: ContainerKind, status: PathStatus, key_offset: usize, excluded_objects_mark: (usize, usize)) -> Self {
+        Self {
+            kind,
+            expecting: match kind {
+                ContainerKind::Document => Expecting::Value,
+                ContainerKind::Array => Expecting::ValueOrEnd,
+                ContainerKind::Object => Expecting::KeyOrEnd,
+            },
+            status,
+            key: key_offset..key_offset,
+            index: 0,
+            sum: NumberSum::default(),
+            included: NumberStatistics::default(),
+            excluded_objects_mark,
+            is_excluded: false,
+        }
+    }
+
+    // Returns the segment of the location of the value being read in this container, which must be an array or an
+    // object, given NumberSummer::keys.
+    fn get_value_segment(&self, keys: &str) -> PathSegment {
+        match self.kind {
+            ContainerKind::Object => PathSegment::Key(keys[self.key.clone()].to_string()),
+            _ => PathSegment::Index(self.index),
+        }
+    }
+}
+
+// The sums of the numbers in a JSON document.
+pub struct DocumentSums {
+    // The sum of all the numbers in the document.
+    total: NumberSum,
+    // The statistics of the numbers in the document that the filter keeps.
+    included: NumberStatistics,
+    // The objects in the document that the filter excludes.
+    excluded_objects: ExcludedObjects,
+}
+
+// Sums the numbers in a JSON document in a single pass as it is read, without building the document in memory. The
+// numbers are summed both in total and as the given filter (see filter::Filter) keeps them. The memory used only
+// depends on how deeply the document is nested and the length of its longest string or number, so documents of any
+// size can be summed.
+//
+// Whether an object is excluded by the filter isn't known until the object has been read completely, so each
+// container that is being read keeps its own partial sums. When an object ends, its included statistics are only
+// added to the container around it if the object turned out not to be excluded.
+struct NumberSummer<'a, R: BufRead> {
+    reader: R,
+    // The number of bytes read so far, for error messages.
+    offset: usize,
+    // The filter that decides which numbers are included.
+    filter: &'a Filter,
+    // Whether the filter compares values with numbers, so that numbers need to be parsed into values.
+    compares_numbers: bool,
+    // The memory used for reading strings that are values, which is reused for each one.
+    string: String,
+    // The keys of the values being read in the containers, one after the other.
+    keys: String,
+    // The objects read so far that the filter excludes, not counting those inside other excluded objects.
+    excluded_objects: ExcludedObjects,
+    // The containers that are being read, from the document itself to the innermost one.
+    containers: Vec<Container>,
+}
+
+impl<'a, R: BufRead> NumberSummer<'a, R> {
+    // Creates a new NumberSummer instance that reads a document from the given reader and includes numbers with the
+    // given filter.
+    fn new(reader: R, filter: &'a Filter) -> Self {
+        Self {
+            reader,
+            offset: 0,
+            filter,
+            compares_numbers: filter.compares_numbers(),
+            string: String::new(),
+            keys: String::new(),
+            excluded_objects: ExcludedObjects::default(),
+            containers: vec![Container::new(ContainerKind::Document, PathStatus::Partial, 0, (0, 0))],
+        }
+    }
+
+    // Returns the next byte of the document without consuming it, or None at the end of the document.
+    fn peek_byte(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.reader.fill_buf()?.first().copied())
+    }
+
+    // Consumes and returns the next byte of the document, or returns an error at the end of the document.
+    fn next_byte(&mut self) -> io::Result<u8> {
+        let byte = self
+            .peek_byte()?
+            .ok_or_else(|| invalid_data("Unexpected end of JSON".to_string()))?;
+        self.reader.consume(1);
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    // Returns the innermost container that is being read.
+    fn container(&mut self) -> &mut Container {
+        self.containers.last_mut().unwrap()
+    }
+
+    // Returns the location of the value being read in the innermost container.
+    fn get_value_location(&self) -> Vec<PathSegment> {
+        self.containers[1..]
+            .iter()
+            .map(|container| container.get_value_segment(&self.keys))
+            .collect()
+    }
+
+    // Returns how the value being read in the innermost container relates to the filter.
+    fn get_value_status(&self) -> PathStatus {
+        let container = self.containers.last().unwrap();
+        self.filter
+            .get_value_status(container.status, self.containers.len() - 1, || {
+                self.get_value_location()
+            })
+    }
+
+    // Notes whether the value being read in the innermost container excludes the container, if the container is an
+    // object that the filter doesn't drop. The value is checked against the values that the filter excludes with the
+    // given function.
+    fn check_exclusion(&mut self, is_value: impl Fn(&serde_json::Value) -> bool) {
+        let container = self.containers.last_mut().unwrap();
+        if container.kind == ContainerKind::Object
+            && container.status != PathStatus::Outside
+            && self.filter.excludes_entry(&self.keys[container.key.clone()], is_value)
+        {
+            container.is_excluded = true;
+        }
+    }
+
+    // Notes that a complete value has been read in the innermost container.
+    fn finish_value(&mut self) {
+        let container = self.container();
+        container.expecting = match container.kind {
+            ContainerKind::Document => Expecting::Nothing,
+            ContainerKind::Array | ContainerKind::Object => Expecting::CommaOrEnd,
+        };
+        container.index += 1;
+    }
+
+    // Ends the innermost container, adding its sums to the container around it.
+    fn end_container(&mut self) {
+        let container = self.containers.pop().unwrap();
+        self.keys.truncate(container.key.start);
+        if container.is_excluded {
+            self.excluded_objects.remove_since(container.excluded_objects_mark);
+            let location = self.get_value_location();
+            self.excluded_objects.add(location);
+        }
+        let parent = self.container();
+        parent.sum.add_sum(&container.sum);
+        if !container.is_excluded && container.included.count() > 0 {
+            parent.included.add_statistics(&container.included);
+        }
+        self.finish_value();
+    }
+
+    // Reads a string after its opening quote, appending it to the given string.
+    fn read_string_into(&mut self, string: &mut String) -> io::Result<()> {
+        let mut bytes = std::mem::take(string).into_bytes();
+        loop {
+            // Copy the bytes up to the next one that needs to be looked at on its own straight from the reader's
+            // buffer, since most strings don't have any.
+            let buffer = self.reader.fill_buf()?;
+            let plain_length = buffer
+                .iter()
+                .position(|byte| matches!(byte, b'"' | b'\\' | 0..0x20))
+                .unwrap_or(buffer.len());
+            bytes.extend_from_slice(&buffer[..plain_length]);
+            self.reader.consume(plain_length);
+            self.offset += plain_length;
+
+            match self.next_byte()? {
+                b'"' => {
+                    *string = String::from_utf8(bytes)
+                        .map_err(|_| invalid_data(format!("Invalid UTF-8 in string before offset {}", self.offset)))?;
+                    return Ok(());
+                }
+                b'\\' => {
+                    let c = match self.next_byte()? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.read_unicode_escape()?,
+                        byte => return Err(invalid_data(format!("Invalid escape '\\{}'", byte as char))),
+                    };
+                    bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                byte if byte < 0x20 => {
+                    return Err(invalid_data(format!(
+                        "Unescaped control character in string at offset {}",
+                        self.offset - 1
+                    )));
+                }
+                byte => bytes.push(byte),
+            }
+        }
+    }
+
+    // Reads the four hexadecimal digits of a Unicode escape, returning the UTF-16 code unit that they encode.
+    fn read_code_unit(&mut self) -> io::Result<u32> {
+        let mut code_unit = 0;
+        for _ in 0..4 {
+            let digit = char::from(self.next_byte()?)
+                .to_digit(16)
+                .ok_or_else(|| invalid_data("Invalid Unicode escape".to_string()))?;
+            code_unit = code_unit * 16 + digit;
+        }
+        Ok(code_unit)
+    }
+
+    // Reads a Unicode escape after its "\u", along with the escaped low surrogate that follows if it is a high
+    // surrogate, and returns the character that it encodes.
+    fn read_unicode_escape(&mut self) -> io::Result<char> {
+        let code_unit = self.read_code_unit()?;
+        let code_point = if (0xd800..0xdc00).contains(&code_unit) {
+            if self.next_byte()? != b'\\' || self.next_byte()? != b'u' {
+                return Err(invalid_data("Unpaired surrogate in Unicode escape".to_string()));
+            }
+            let low_code_unit = self.read_code_unit()?;
+            if !(0xdc00..0xe000).contains(&low_code_unit) {
+                return Err(invalid_data("Unpaired surrogate in Unicode escape".to_string()));
+            }
+            0x10000 + ((code_unit - 0xd800) << 10) + (low_code_unit - 0xdc00)
+        } else {
+            code_unit
+        };
+        char::from_u32(code_point).ok_or_else(|| invalid_data("Unpaired surrogate in Unicode escape".to_string()))
+    }
+
+    // Reads a number, adding it to the sums of the innermost container.
+    fn read_number(&mut self) -> io::Result<()> {
+        let mut text = String::new();
+        while let Some(byte) = self.peek_byte()? {
+            if !matches!(byte, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') {
+                break;
+            }
+            text.push(char::from(byte));
+            self.next_byte()?;
+        }
+        let number = Number::parse(&text)?;
+        let is_included = self.get_value_status() == PathStatus::Selected;
+        let container = self.container();
+        container.sum.add_number(number);
+        if is_included {
+            container.included.add_number(number);
+        }
+        // Numbers are only parsed into values if they need to be compared. Numbers too large for serde_json to
+        // represent can't be equal to any number in a filter.
+        let value: Option<serde_json::Value> = if self.compares_numbers {
+            serde_json::from_str(&text).ok()
+        } else {
+            None
+        };
+        self.check_exclusion(|excluded_value| Some(excluded_value) == value.as_ref());
+        Ok(())
+    }
+
+    // Reads one of the literals "true", "false" or "null", and returns its value.
+    fn read_literal(&mut self) -> io::Result<serde_json::Value> {
+        let offset = self.offset;
+        let mut text = String::new();
+        while let Some(byte) = self.peek_byte()? {
+            if !byte.is_ascii_lowercase() || text.len() == 5 {
+                break;
             }
+            text.push(char::from(byte));
+            self.next_byte()?;
+        }
+        match text.as_str() {
+            "true" => Ok(serde_json::Value::Bool(true)),
+            "false" => Ok(serde_json::Value::Bool(false)),
+            "null" => Ok(serde_json::Value::Null),
+            _ => Err(invalid_data(format!("Invalid literal \"{text}\" at offset {offset}"))),
+        }
+    }
 
-            map.values()
-                .fold(0, |sum, map_value| sum + get_sum_of_numbers(map_value, exclude_name))
+    // Reads the whole document and returns the sums of its numbers.
+    fn run(mut self) -> io::Result<DocumentSums> {
+        loop {
+            let Some(byte) = self.peek_byte()? else {
+                if self.containers.len() == 1 && self.container().expecting == Expecting::Nothing {
+                    let document = self.containers.pop().unwrap();
+                    return Ok(DocumentSums {
+                        total: document.sum,
+                        included: document.included,
+                        excluded_objects: self.excluded_objects,
+                    });
+                }
+                return Err(invalid_data("Unexpected end of JSON".to_string()));
+            };
+            if byte.is_ascii_whitespace() {
+                self.next_byte()?;
+                continue;
+            }
+
+            let container = self.container();
+            let (kind, expecting) = (container.kind, container.expecting);
+            let is_expecting_value = matches!(expecting, Expecting::Value | Expecting::ValueOrEnd);
+            match byte {
+                b'[' | b'{' if is_expecting_value => {
+                    self.next_byte()?;
+                    let kind = if byte == b'[' {
+                        ContainerKind::Array
+                    } else {
+                        ContainerKind::Object
+                    };
+                    let status = self.get_value_status();
+                    self.check_exclusion(|_| false);
+                    let container = Container::new(kind, status, self.keys.len(), self.excluded_objects.mark());
+                    self.containers.push(container);
+                }
+                b']' if kind == ContainerKind::Array
+                    && matches!(expecting, Expecting::ValueOrEnd | Expecting::CommaOrEnd) =>
+                {
+                    self.next_byte()?;
+                    self.end_container();
+                }
+                b'}' if kind == ContainerKind::Object
+                    && matches!(expecting, Expecting::KeyOrEnd | Expecting::CommaOrEnd) =>
+                {
+                    self.next_byte()?;
+                    self.end_container();
+                }
+                b'"' if matches!(expecting, Expecting::Key | Expecting::KeyOrEnd) => {
+                    self.next_byte()?;
+                    let key_start = self.container().key.start;
+                    let mut keys = std::mem::take(&mut self.keys);
+                    keys.truncate(key_start);
+                    self.read_string_into(&mut keys)?;
+                    let container = self.container();
+                    container.key.end = keys.len();
+                    container.expecting = Expecting::Colon;
+                    self.keys = keys;
+                }
+                b'"' if is_expecting_value => {
+                    self.next_byte()?;
+                    let mut string = std::mem::take(&mut self.string);
+                    string.clear();
+                    self.read_string_into(&mut string)?;
+                    self.check_exclusion(|excluded_value| *excluded_value == *string);
+                    self.string = string;
+                    self.finish_value();
+                }
+                b':' if expecting == Expecting::Colon => {
+                    self.next_byte()?;
+                    self.container().expecting = Expecting::Value;
+                }
+                b',' if expecting == Expecting::CommaOrEnd => {
+                    self.next_byte()?;
+                    self.container().expecting = if kind == ContainerKind::Object {
+                        Expecting::Key
+                    } else {
+                        Expecting::Value
+                    };
+                }
+                b'-' | b'0'..=b'9' if is_expecting_value => {
+                    self.read_number()?;
+                    self.finish_value();
+                }
+                b't' | b'f' | b'n' if is_expecting_value => {
+                    let value = self.read_literal()?;
+                    self.check_exclusion(|excluded_value| *excluded_value == value);
+                    self.finish_value();
+                }
+                _ => {
+                    return Err(invalid_data(format!(
+                        "Unexpected character '{}' at offset {}",
+                        char::from(byte).escape_default(),
+                        self.offset
+                    )));
+                }
+            }
         }
-        _ => 0,
     }
 }
 
-fn solve(input: &str, _log_fn: Option<fn(&str)>) -> (String, String) {
-    let input_json: Value = serde_json::from_str(input).expect("Unable to parse JSON.");
+// Sums the numbers in the JSON document read from the given reader, both in total and as the given filter keeps them.
+fn sum_numbers<R: BufRead>(reader: R, filter: &Filter) -> io::Result<DocumentSums> {
+    NumberSummer::new(reader, filter).run()
+}
+
+// Sums the numbers in the JSON document in the file at the given path in the same way as sum_numbers. The file is read
+// as it is summed, so it doesn't need to fit in memory.
+pub fn sum_numbers_in_file(path: &Path, filter: &Filter) -> io::Result<DocumentSums> {
+    sum_numbers(io::BufReader::new(std::fs::File::open(path)?), filter)
+}
+
+// Solves the puzzle for the JSON document in the file at the given path, writing the results to the given output. If
+// any arguments are given, they are treated as a filter (see filter::Filter), and the aggregate of the numbers that it
+// keeps is written instead, along with the objects it excludes. If the only argument is "explore", then filters are
+// tried out interactively on the document instead (see filter::explore), with commands read from the standard input.
+// Exploring reads the whole document into memory, so that it can be pruned.
+fn run_on_file(path: &Path, arguments: &[&str], output: &mut dyn Write) -> io::Result<()> {
+    match arguments {
+        [] => {}
+        ["explore"] => return filter::explore(&std::fs::read_to_string(path)?, &mut io::stdin().lock(), output),
+        _ => return filter::evaluate_file(&arguments.join(" "), path, output),
+    }
+    let sums = sum_numbers_in_file(path, &Filter::excluding_value("red"))?;
+    writeln!(output, "Part 1: {}", sums.total)?;
+    writeln!(output, "Part 2: {}", sums.included.sum())
+}
+
+fn solve(input: &str, _log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
+    let sums = sum_numbers(input.as_bytes(), &Filter::excluding_value("red")).expect("Unable to parse JSON.");
 
     // Part 1: Find the sum of all numbers in the JSON.
-    let part1_result = get_sum_of_numbers(&input_json, None);
+    let part1_result = sums.total;
 
     // Part 2: Find the sum of all numbers in the JSON, excluding any object that has a value
     // with the name "red".
-    let part2_result = get_sum_of_numbers(&input_json, Some("red"));
+    let part2_result = sums.included.sum();
 
     (part1_result.to_string(), part2_result.to_string())
 }
 
 #[linkme::distributed_slice(crate::SOLUTIONS)]
-static SOLUTION: crate::Solution = crate::Solution::new(2015, 12, solve);
+static SOLUTION: crate::Solution = crate::Solution::new(2015, 12, solve).with_file_runner(run_on_file);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sums_numbers_in_file() {
+        // The document is made large enough that it is read through several fills of the reader's buffer.
+        let object_count = 5000;
+        let objects: Vec<String> = (0..object_count)
+            .map(|i| {
+                let color = if i % 3 == 0 { "red" } else { "blue" };
+                format!("{{\"id\":{i},\"color\":\"{color}\",\"values\":[1,-2,{{\"x\":3}}]}}")
+            })
+            .collect();
+        let document = format!("[{}]", objects.join(",\n"));
+        let path = std::env::temp_dir().join(format!("p2015_12_sums_numbers_in_file_{}.json", std::process::id()));
+        std::fs::write(&path, &document).unwrap();
+        let sums = sum_numbers_in_file(&path, &Filter::excluding_value("red"));
+        std::fs::remove_file(&path).unwrap();
+        let sums = sums.unwrap();
+
+        let expected_total: i128 = (0..object_count).map(|i| i + 2).sum();
+        let expected_included_total: i128 = (0..object_count).filter(|i| i % 3 != 0).map(|i| i + 2).sum();
+        assert!(document.len() > 10 * 8192);
+        assert_eq!(sums.total.to_string(), expected_total.to_string());
+        assert_eq!(sums.included.sum().to_string(), expected_included_total.to_string());
+    }
+
+    #[test]
+    fn evaluates_filter_on_file() {
+        let document = r#"[{"a":1,"b":"red"},{"a":2,"c":{"b":"red","a":3}},[{"b":"red"}]]"#;
+        let path = std::env::temp_dir().join(format!("p2015_12_evaluates_filter_on_file_{}.json", std::process::id()));
+        std::fs::write(&path, document).unwrap();
+        let mut output = Vec::new();
+        let result = run_on_file(&path, &["exclude", "*=\"red\";", "aggregate", "count"], &mut output);
+        std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        let expected_output = "1\nExcluded objects: 3\n  $[0]\n  $[1].c\n  $[2][0]\n";
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+}
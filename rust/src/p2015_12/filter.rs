@@ -0,0 +1,604 @@
+// Implements a small filter language for JSON documents, which generalizes the puzzle's rule of ignoring objects with
+// a "red" value. A filter can exclude objects that match predicates on their keys and values, limit how deeply the
+// document is read, restrict it to given paths and choose how its numbers are aggregated. Filters can be tried out
+// interactively, showing either the aggregate or the pruned document, so that it is easy to see what was excluded.
+// Aggregates are found by the streaming reader that solves the puzzle, so they can also be found for files that are
+// too large to hold in memory.
+
+use super::{Number, NumberSum};
+use serde_json::{Map, Value};
+use std::io::{self, BufRead, Write};
+
+// The maximum number of excluded objects whose paths are listed after evaluating a filter.
+const MAX_LISTED_EXCLUSIONS: usize = 10;
+
+// Describes the help text shown for the filter commands.
+const HELP_TEXT: &str = "\
+Commands:
+  e, eval [<filter>]       Aggregate the numbers left by the given filter, and list the excluded objects
+  p, prune [<filter>]      Show the document that is left by the given filter
+  q, quit                  Exit
+
+A filter is a list of clauses separated by semicolons:
+  exclude <key>=<value>    Exclude objects where the given key has the given string, number, boolean or null value;
+                           a key of * matches any key
+  exclude <key>            Exclude objects that have the given key
+  depth <count>            Drop values nested more than the given number of levels deep
+  path <path>              Only keep values at or under the given path, e.g. $.a[2].*; may be given more than once
+  aggregate <kind>         Aggregate numbers by sum (the default), count, min or max
+
+Keys can be quoted like strings, e.g. \"a=b\", so that they can contain = or be a key named *. Semicolons inside
+strings don't separate clauses.
+
+For example, the puzzle's second part is: exclude *=\"red\"";
+
+// Returns an error of kind InvalidInput with the given message, for reporting a filter that can't be understood.
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+// Represents a segment of a path through a document. Paths given in filters may contain wildcards, which match any
+// key or index, but the locations of values in a document never do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+impl PathSegment {
+    // Returns whether this segment of a filter's path matches the given segment of a value's location.
+    fn matches(&self, location_segment: &PathSegment) -> bool {
+        *self == PathSegment::Wildcard || self == location_segment
+    }
+}
+
+// Parses the given path, such as "$.a[2].*", into its segments.
+fn parse_path(text: &str) -> io::Result<Vec<PathSegment>> {
+    let invalid_path = || invalid_input(format!("Invalid path \"{text}\""));
+    let mut remaining = text.strip_prefix('$').ok_or_else(invalid_path)?;
+    let mut path = Vec::new();
+    while !remaining.is_empty() {
+        if let Some(rest) = remaining.strip_prefix('[') {
+            let (index, rest) = rest.split_once(']').ok_or_else(invalid_path)?;
+            path.push(match index {
+                "*" => PathSegment::Wildcard,
+                _ => PathSegment::Index(index.parse().map_err(|_| invalid_path())?),
+            });
+            remaining = rest;
+        } else if let Some(rest) = remaining.strip_prefix('.') {
+            let key_length = rest.find(['.', '[']).unwrap_or(rest.len());
+            path.push(match &rest[..key_length] {
+                "" => return Err(invalid_path()),
+                "*" => PathSegment::Wildcard,
+                key => PathSegment::Key(key.to_string()),
+            });
+            remaining = &rest[key_length..];
+        } else {
+            return Err(invalid_path());
+        }
+    }
+    Ok(path)
+}
+
+// Splits the given filter into its clauses at the semicolons that aren't inside strings, so that strings in clauses
+// can contain semicolons.
+fn split_clauses(text: &str) -> Vec<&str> {
+    let mut clauses = Vec::new();
+    let mut clause_start = 0;
+    let mut is_in_string = false;
+    let mut is_escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if is_escaped => is_escaped = false,
+            '\\' if is_in_string => is_escaped = true,
+            '"' => is_in_string = !is_in_string,
+            ';' if !is_in_string => {
+                clauses.push(&text[clause_start..i]);
+                clause_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    clauses.push(&text[clause_start..]);
+    clauses
+}
+
+// Implement the Display trait for PathSegment, showing it in the syntax of a path.
+impl std::fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathSegment::Key(key) => write!(f, ".{key}"),
+            PathSegment::Index(index) => write!(f, "[{index}]"),
+            PathSegment::Wildcard => write!(f, "[*]"),
+        }
+    }
+}
+
+// Formats the given location in a document in the syntax of a path.
+fn format_location(location: &[PathSegment]) -> String {
+    format!("${}", location.iter().map(ToString::to_string).collect::<String>())
+}
+
+// Represents a predicate that excludes the objects matching it.
+#[derive(Debug, PartialEq)]
+enum Exclusion {
+    // Matches objects that have the given key.
+    HasKey(String),
+    // Matches objects where the given key, or any key if there isn't one, has the given value. The value is never an
+    // array or an object, so that it can be compared as soon as it is read.
+    HasValue(Option<String>, Value),
+}
+
+impl Exclusion {
+    // Parses the argument of an exclude clause, which is a key optionally followed by "=" and a value. The key may be
+    // quoted as a JSON string, so that it can contain "=" or be a literal "*".
+    fn parse(argument: &str) -> io::Result<Self> {
+        let (key, is_quoted, rest) = if argument.starts_with('"') {
+            let mut keys = serde_json::Deserializer::from_str(argument).into_iter::<String>();
+            let key = keys
+                .next()
+                .and_then(Result::ok)
+                .ok_or_else(|| invalid_input(format!("Invalid key in \"{argument}\"")))?;
+            (key, true, &argument[keys.byte_offset()..])
+        } else {
+            let key_length = argument.find('=').unwrap_or(argument.len());
+            (
+                argument[..key_length].trim().to_string(),
+                false,
+                &argument[key_length..],
+            )
+        };
+        if key.is_empty() && !is_quoted {
+            return Err(invalid_input("Expected a key to exclude".to_string()));
+        }
+
+        let rest = rest.trim();
+        if rest.is_empty() {
+            return Ok(Exclusion::HasKey(key));
+        }
+        let value_text = rest
+            .strip_prefix('=')
+            .ok_or_else(|| invalid_input(format!("Expected \"=\" before \"{rest}\"")))?
+            .trim();
+        let value = serde_json::from_str(value_text)
+            .map_err(|_| invalid_input(format!("Invalid JSON value \"{value_text}\"")))?;
+        if matches!(value, Value::Array(_) | Value::Object(_)) {
+            return Err(invalid_input(format!(
+                "Objects can't be excluded by array or object values like {value}"
+            )));
+        }
+        Ok(Exclusion::HasValue((is_quoted || key != "*").then_some(key), value))
+    }
+
+    // Returns whether an object with the given key matches this predicate because of that key's value, which is
+    // checked with the given function. This lets values be checked without building them.
+    fn matches_entry(&self, key: &str, is_value: impl Fn(&Value) -> bool) -> bool {
+        match self {
+            Exclusion::HasKey(excluded_key) => excluded_key == key,
+            Exclusion::HasValue(None, excluded_value) => is_value(excluded_value),
+            Exclusion::HasValue(Some(excluded_key), excluded_value) => excluded_key == key && is_value(excluded_value),
+        }
+    }
+
+    // Returns whether the given object matches this predicate.
+    fn matches(&self, object: &Map<String, Value>) -> bool {
+        object
+            .iter()
+            .any(|(key, value)| self.matches_entry(key, |excluded_value| excluded_value == value))
+    }
+}
+
+// The ways in which the numbers left by a filter can be aggregated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Aggregation {
+    Sum,
+    Count,
+    Min,
+    Max,
+}
+
+// Keeps track of the numbers that a filter leaves, for every kind of aggregation at once.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NumberStatistics {
+    sum: NumberSum,
+    count: u64,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl NumberStatistics {
+    // Adds the given number to the statistics.
+    pub fn add_number(&mut self, number: Number) {
+        self.sum.add_number(number);
+        self.add_min_max(number.to_float());
+        self.count += 1;
+    }
+
+    // Adds the given statistics to these ones.
+    pub fn add_statistics(&mut self, other: &NumberStatistics) {
+        self.sum.add_sum(&other.sum);
+        self.count += other.count;
+        for value in other.min.into_iter().chain(other.max) {
+            self.add_min_max(value);
+        }
+    }
+
+    // Updates the minimum and maximum with the given value.
+    fn add_min_max(&mut self, value: f64) {
+        self.min = Some(self.min.map_or(value, |min| min.min(value)));
+        self.max = Some(self.max.map_or(value, |max| max.max(value)));
+    }
+
+    // Returns how many numbers there are.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    // Returns the sum of the numbers.
+    pub fn sum(&self) -> NumberSum {
+        self.sum
+    }
+
+    // Returns the given aggregate of the numbers, or "none" for the minimum or maximum of no numbers.
+    fn get_aggregate(&self, aggregation: Aggregation) -> String {
+        match aggregation {
+            Aggregation::Sum => self.sum.to_string(),
+            Aggregation::Count => self.count.to_string(),
+            Aggregation::Min => self.min.map_or_else(|| "none".to_string(), |min| min.to_string()),
+            Aggregation::Max => self.max.map_or_else(|| "none".to_string(), |max| max.to_string()),
+        }
+    }
+}
+
+// Keeps track of the objects that a filter excludes. Only the locations of the first few are kept, so that the
+// memory used doesn't depend on how many objects are excluded.
+#[derive(Debug, Default)]
+pub struct ExcludedObjects {
+    count: usize,
+    listed_locations: Vec<Vec<PathSegment>>,
+}
+
+impl ExcludedObjects {
+    // Adds an excluded object at the given location.
+    pub fn add(&mut self, location: Vec<PathSegment>) {
+        self.count += 1;
+        if self.listed_locations.len() < MAX_LISTED_EXCLUSIONS {
+            self.listed_locations.push(location);
+        }
+    }
+
+    // Returns a mark that the objects added after this point can be removed back to (see remove_since).
+    pub fn mark(&self) -> (usize, usize) {
+        (self.count, self.listed_locations.len())
+    }
+
+    // Removes the objects that were added after the given mark, for when an object around them is excluded itself.
+    pub fn remove_since(&mut self, (count, listed_count): (usize, usize)) {
+        self.count = count;
+        self.listed_locations.truncate(listed_count);
+    }
+}
+
+// Describes how a value's location relates to the paths that a filter restricts the document to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathStatus {
+    // The value is at or under one of the paths, so it is kept.
+    Selected,
+    // The value is on the way to one of the paths, so only the parts of it that lead there are kept.
+    Partial,
+    // The value isn't on the way to any of the paths, or is nested too deeply, so it is dropped.
+    Outside,
+}
+
+// Represents a parsed filter.
+#[derive(Debug)]
+pub struct Filter {
+    exclusions: Vec<Exclusion>,
+    max_depth: Option<usize>,
+    paths: Vec<Vec<PathSegment>>,
+    aggregation: Aggregation,
+}
+
+impl Filter {
+    // Returns a filter that excludes objects with any key that has the given string as its value, which is the rule
+    // from the puzzle's second part.
+    pub fn excluding_value(value: &str) -> Self {
+        Filter {
+            exclusions: vec![Exclusion::HasValue(None, Value::String(value.to_string()))],
+            max_depth: None,
+            paths: Vec::new(),
+            aggregation: Aggregation::Sum,
+        }
+    }
+
+    // Parses the given filter, which is a list of clauses separated by semicolons outside of strings. An empty filter
+    // keeps the whole document and sums its numbers.
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut filter = Filter {
+            exclusions: Vec::new(),
+            max_depth: None,
+            paths: Vec::new(),
+            aggregation: Aggregation::Sum,
+        };
+        for clause in split_clauses(text)
+            .into_iter()
+            .map(str::trim)
+            .filter(|clause| !clause.is_empty())
+        {
+            let (keyword, argument) = clause.split_once(char::is_whitespace).unwrap_or((clause, ""));
+            let argument = argument.trim();
+            match keyword {
+                "exclude" => filter.exclusions.push(Exclusion::parse(argument)?),
+                "depth" => {
+                    let depth = argument
+                        .parse()
+                        .map_err(|_| invalid_input(format!("Invalid depth \"{argument}\"")))?;
+                    filter.max_depth = Some(depth);
+                }
+                "path" => filter.paths.push(parse_path(argument)?),
+                "aggregate" => {
+                    filter.aggregation = match argument {
+                        "sum" => Aggregation::Sum,
+                        "count" => Aggregation::Count,
+                        "min" => Aggregation::Min,
+                        "max" => Aggregation::Max,
+                        _ => return Err(invalid_input(format!("Unknown aggregation \"{argument}\""))),
+                    };
+                }
+                _ => return Err(invalid_input(format!("Unknown clause \"{clause}\""))),
+            }
+        }
+        Ok(filter)
+    }
+
+    // Returns how the given location relates to the filter's paths.
+    fn get_path_status(&self, location: &[PathSegment]) -> PathStatus {
+        if self.paths.is_empty() {
+            return PathStatus::Selected;
+        }
+        let mut status = PathStatus::Outside;
+        for path in &self.paths {
+            let common_length = usize::min(path.len(), location.len());
+            if path[..common_length]
+                .iter()
+                .zip(&location[..common_length])
+                .all(|(path_segment, location_segment)| path_segment.matches(location_segment))
+            {
+                if path.len() <= location.len() {
+                    return PathStatus::Selected;
+                }
+                status = PathStatus::Partial;
+            }
+        }
+        status
+    }
+
+    // Returns how a value at the given depth relates to the filter, given how the value around it does, or Partial
+    // for the whole document. The value's location is only found with the given function if it is needed.
+    pub fn get_value_status(
+        &self,
+        parent_status: PathStatus,
+        depth: usize,
+        get_location: impl FnOnce() -> Vec<PathSegment>,
+    ) -> PathStatus {
+        if self.max_depth.is_some_and(|max_depth| depth > max_depth) {
+            return PathStatus::Outside;
+        }
+        match parent_status {
+            PathStatus::Selected | PathStatus::Outside => parent_status,
+            PathStatus::Partial => self.get_path_status(&get_location()),
+        }
+    }
+
+    // Returns whether any of the filter's exclusions compare values with numbers, so that numbers don't need to be
+    // parsed into values otherwise.
+    pub fn compares_numbers(&self) -> bool {
+        self.exclusions
+            .iter()
+            .any(|exclusion| matches!(exclusion, Exclusion::HasValue(_, Value::Number(_))))
+    }
+
+    // Returns whether an object is excluded because of the given key and its value, which is checked against the
+    // values that the filter excludes with the given function.
+    pub fn excludes_entry(&self, key: &str, is_value: impl Fn(&Value) -> bool) -> bool {
+        self.exclusions
+            .iter()
+            .any(|exclusion| exclusion.matches_entry(key, &is_value))
+    }
+
+    // Returns the part of the given document that the filter keeps, or None if it drops the whole document.
+    fn prune_document(&self, document: &Value) -> Option<Value> {
+        self.prune(document, &mut Vec::new(), PathStatus::Partial)
+    }
+
+    // Returns the part of the given value at the given location that the filter keeps, or None if it drops the whole
+    // value, given how the value around it relates to the filter.
+    fn prune(&self, value: &Value, location: &mut Vec<PathSegment>, parent_status: PathStatus) -> Option<Value> {
+        let status = self.get_value_status(parent_status, location.len(), || location.clone());
+        let is_selected = match status {
+            PathStatus::Selected => true,
+            PathStatus::Partial => false,
+            PathStatus::Outside => return None,
+        };
+
+        // Values that are only on the way to the filter's paths are kept as long as something in them is.
+        match value {
+            Value::Object(object) => {
+                if self.exclusions.iter().any(|exclusion| exclusion.matches(object)) {
+                    return None;
+                }
+                let mut pruned_object = Map::new();
+                for (key, child) in object {
+                    location.push(PathSegment::Key(key.clone()));
+                    if let Some(pruned_child) = self.prune(child, location, status) {
+                        pruned_object.insert(key.clone(), pruned_child);
+                    }
+                    location.pop();
+                }
+                (is_selected || !pruned_object.is_empty()).then_some(Value::Object(pruned_object))
+            }
+            Value::Array(array) => {
+                let mut pruned_array = Vec::new();
+                for (index, child) in array.iter().enumerate() {
+                    location.push(PathSegment::Index(index));
+                    pruned_array.extend(self.prune(child, location, status));
+                    location.pop();
+                }
+                (is_selected || !pruned_array.is_empty()).then_some(Value::Array(pruned_array))
+            }
+            _ => is_selected.then(|| value.clone()),
+        }
+    }
+}
+
+// Writes the aggregate of the numbers that the given filter left in a document, given the sums found for it, along
+// with the objects that it excluded.
+fn write_evaluation(filter: &Filter, sums: &super::DocumentSums, output: &mut dyn Write) -> io::Result<()> {
+    writeln!(output, "{}", sums.included.get_aggregate(filter.aggregation))?;
+    let excluded_objects = &sums.excluded_objects;
+    writeln!(output, "Excluded objects: {}", excluded_objects.count)?;
+    for location in &excluded_objects.listed_locations {
+        writeln!(output, "  {}", format_location(location))?;
+    }
+    if excluded_objects.count > excluded_objects.listed_locations.len() {
+        writeln!(
+            output,
+            "  ... and {} more",
+            excluded_objects.count - excluded_objects.listed_locations.len()
+        )?;
+    }
+    Ok(())
+}
+
+// Evaluates the given filter on the JSON document in the file at the given path, writing the aggregate and the
+// excluded objects to the given output. The file is read as it is evaluated, so it doesn't need to fit in memory.
+pub fn evaluate_file(filter: &str, path: &std::path::Path, output: &mut dyn Write) -> io::Result<()> {
+    let filter = Filter::parse(filter)?;
+    let sums = super::sum_numbers_in_file(path, &filter)?;
+    write_evaluation(&filter, &sums, output)
+}
+
+// Runs the given filter command with the given filter on the given problem input, writing the result to the given
+// output. The problem input is only parsed into a document, which is kept in the given cache, when it needs to be
+// pruned. Invalid commands and filters are returned as errors of kind InvalidInput.
+fn run_command(
+    command: &str,
+    filter: &str,
+    problem_input: &str,
+    document: &mut Option<Value>,
+    output: &mut dyn Write,
+) -> io::Result<()> {
+    match command {
+        "e" | "eval" => {
+            let filter = Filter::parse(filter)?;
+            let sums = super::sum_numbers(problem_input.as_bytes(), &filter)?;
+            write_evaluation(&filter, &sums, output)
+        }
+        "p" | "prune" => {
+            let filter = Filter::parse(filter)?;
+            if document.is_none() {
+                *document = Some(serde_json::from_str(problem_input)?);
+            }
+            let pruned_document = document
+                .as_ref()
+                .and_then(|document| filter.prune_document(document))
+                .unwrap_or(Value::Null);
+            writeln!(output, "{}", serde_json::to_string_pretty(&pruned_document)?)
+        }
+        "h" | "help" => writeln!(output, "{HELP_TEXT}"),
+        _ => Err(invalid_input(format!("Unknown command \"{command}\""))),
+    }
+}
+
+// Runs an interactive session for trying out filters on the given JSON document, reading commands from the given input
+// and writing to the given output until the input ends or the session is told to quit.
+pub fn explore(problem_input: &str, input: &mut dyn BufRead, output: &mut dyn Write) -> io::Result<()> {
+    writeln!(output, "Type \"help\" for a list of commands.")?;
+
+    let mut document = None;
+    let mut command_line = String::new();
+    loop {
+        write!(output, "(filter) ")?;
+        output.flush()?;
+        command_line.clear();
+        if input.read_line(&mut command_line)? == 0 {
+            return Ok(());
+        }
+        let command_line = command_line.trim();
+        let (command, filter) = command_line
+            .split_once(char::is_whitespace)
+            .unwrap_or((command_line, ""));
+        match command {
+            "" => {}
+            "q" | "quit" => return Ok(()),
+            _ => match run_command(command, filter, problem_input, &mut document, output) {
+                Err(e) if e.kind() == io::ErrorKind::InvalidInput => writeln!(output, "{e}")?,
+                result => result?,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::sum_numbers;
+    use super::*;
+
+    const EXAMPLE_DOCUMENT: &str = r#"{"a":[1,{"c":"red","b":2},3],"d":{"e":[4,{"f":5}],"g":"blue"},
+        "h":[[6,[7]],{"i":8,"j":true,"k":{"l":"red"}}],"m":-9.5,"n":[{"o":null,"p":[10]}]}"#;
+
+    #[test]
+    fn evaluation_matches_pruning() {
+        let document = serde_json::from_str(EXAMPLE_DOCUMENT).unwrap();
+        let no_filter = Filter::parse("").unwrap();
+        for text in [
+            "",
+            "exclude *=\"red\"",
+            "exclude j=true; exclude g",
+            "exclude o=null; exclude l",
+            "depth 2",
+            "path $.h[*]; path $.a",
+            "path $.*[0]; exclude f",
+            "depth 3; exclude c=\"red\"; path $.d.e",
+        ] {
+            let filter = Filter::parse(text).unwrap();
+            let included = sum_numbers(EXAMPLE_DOCUMENT.as_bytes(), &filter).unwrap().included;
+            let pruned_document = filter.prune_document(&document).unwrap_or(Value::Null);
+            let pruned = sum_numbers(pruned_document.to_string().as_bytes(), &no_filter)
+                .unwrap()
+                .included;
+            assert_eq!(included.sum().to_string(), pruned.sum().to_string(), "{text}");
+            assert_eq!(included.count(), pruned.count(), "{text}");
+        }
+    }
+
+    #[test]
+    fn parses_quoted_strings_in_clauses() {
+        let string = |text: &str| Value::String(text.to_string());
+        let filter = Filter::parse(r#"exclude k="a;b"; exclude k = "x=y" ; depth 3"#).unwrap();
+        assert_eq!(
+            filter.exclusions,
+            [
+                Exclusion::HasValue(Some("k".to_string()), string("a;b")),
+                Exclusion::HasValue(Some("k".to_string()), string("x=y")),
+            ]
+        );
+        assert_eq!(filter.max_depth, Some(3));
+
+        let filter = Filter::parse(r#"exclude *="a\";b"; exclude "a=b"=1; exclude "*"; exclude "*"=null"#).unwrap();
+        assert_eq!(
+            filter.exclusions,
+            [
+                Exclusion::HasValue(None, string("a\";b")),
+                Exclusion::HasValue(Some("a=b".to_string()), Value::from(1)),
+                Exclusion::HasKey("*".to_string()),
+                Exclusion::HasValue(Some("*".to_string()), Value::Null),
+            ]
+        );
+
+        for text in [r#"exclude k="a;b"#, r#"exclude "k"x"#, "exclude =1", r#"exclude "k=1"#] {
+            assert!(Filter::parse(text).is_err(), "{text}");
+        }
+    }
+}
@@ -1,9 +1,15 @@
 // Advent of Code 2015, Day 13: "Knights of the Dinner Table"
 // https://adventofcode.com/2015/day/13
 
+use crate::tour::{find_best_tour, Graph, Objective, Tour, TourOptions};
+
 struct DinnerDescription {
     guests: Vec<String>,
     happiness_rule_matrix: Vec<i32>,
+
+    // The seating arrangement chosen for the guests, if one has been chosen. The guests are listed in order around
+    // the table, so the last guest sits next to the first.
+    seating_arrangement: Option<Tour>,
 }
 
 impl DinnerDescription {
@@ -13,6 +19,7 @@ impl DinnerDescription {
         let mut description = Self {
             guests,
             happiness_rule_matrix,
+            seating_arrangement: None,
         };
 
         for ((subject_name, object_name), happiness) in rules {
@@ -27,16 +34,24 @@ impl DinnerDescription {
         self.happiness_rule_matrix[index]
     }
 
+    // Returns the ID of the guest with the given name, adding the guest if they aren't already at the dinner. A new
+    // guest feels nothing about sitting next to anyone, and nobody feels anything about sitting next to them. Adding
+    // a guest clears any chosen seating arrangement, since it doesn't seat them.
     pub fn ensure_guest(&mut self, guest_name: &str) -> usize {
         if let Some(guest_id) = self.guests.iter().position(|g| g == guest_name) {
             guest_id
         } else {
+            let old_guest_count = self.guests.len();
             self.guests.push(guest_name.to_string());
-            for i in 0..self.guests.len() {
-                self.happiness_rule_matrix
-                    .insert(self.get_happiness_rule_matrix_index(i, self.guests.len() - 1), 0);
+            let mut happiness_rule_matrix = vec![0; self.guests.len() * self.guests.len()];
+            for subject_id in 0..old_guest_count {
+                for object_id in 0..old_guest_count {
+                    happiness_rule_matrix[self.get_happiness_rule_matrix_index(subject_id, object_id)] =
+                        self.happiness_rule_matrix[(subject_id * old_guest_count) + object_id];
+                }
             }
-            self.happiness_rule_matrix.append(&mut vec![0; self.guests.len()]);
+            self.happiness_rule_matrix = happiness_rule_matrix;
+            self.seating_arrangement = None;
             self.guests.len() - 1
         }
     }
@@ -72,55 +87,53 @@ impl std::fmt::Display for DinnerDescription {
             }
             writeln!(f)?;
         }
-        Ok(())
-    }
-}
 
-fn find_optimal_seating_arrangement_happiness(dinner_description: &DinnerDescription) -> i32 {
-    fn get_seating_arrangement_total_happiness(
-        dinner_description: &DinnerDescription,
-        seating_arrangement: &[usize],
-    ) -> i32 {
-        let mut total_happiness = 0;
-        for i in 0..seating_arrangement.len() {
-            let guest1_id = seating_arrangement[i];
-            let guest2_id = seating_arrangement[(i + 1) % seating_arrangement.len()];
-            total_happiness += dinner_description.get_happiness_change(guest1_id, guest2_id);
-            total_happiness += dinner_description.get_happiness_change(guest2_id, guest1_id);
+        // If a seating arrangement has been chosen, show each pair of neighbours around the table along with how
+        // each of them feels about the other.
+        if let Some(seating_arrangement) = &self.seating_arrangement {
+            writeln!(f, "Seating chart (total happiness: {})", seating_arrangement.weight)?;
+            let guest_ids = &seating_arrangement.vertex_ids;
+            for (i, guest1_id) in guest_ids.iter().enumerate() {
+                let guest2_id = guest_ids[(i + 1) % guest_ids.len()];
+                writeln!(
+                    f,
+                    "  {} ({:+}) <-> {} ({:+})",
+                    self.guests[*guest1_id],
+                    self.get_happiness_change(*guest1_id, guest2_id),
+                    self.guests[guest2_id],
+                    self.get_happiness_change(guest2_id, *guest1_id)
+                )?;
+            }
         }
-        total_happiness
+        Ok(())
     }
+}
 
-    fn find_optimal_seating_arrangement_happiness_recursive(
-        dinner_description: &DinnerDescription,
-        remaining_guests: &[usize],
-        seating_arrangement: &[usize],
-    ) -> i32 {
-        if remaining_guests.is_empty() {
-            get_seating_arrangement_total_happiness(dinner_description, seating_arrangement)
-        } else {
-            let mut max_happiness = 0;
-            for i in 0..remaining_guests.len() {
-                let mut remaining_guests = remaining_guests.to_vec();
-                let mut seating_arrangement = seating_arrangement.to_vec();
-                let guest_id = remaining_guests.remove(i);
-                seating_arrangement.push(guest_id);
-                let happiness = find_optimal_seating_arrangement_happiness_recursive(
-                    dinner_description,
-                    &remaining_guests,
-                    &seating_arrangement,
-                );
-                if happiness > max_happiness {
-                    max_happiness = happiness;
-                }
-            }
-            max_happiness
+// Finds the seating arrangement around a circular table that gives the greatest total change in happiness, which may
+// be negative. Returns None if there are no guests.
+//
+// Seating the guests is a closed tour through them, where going from one guest to the next gains the happiness that
+// the two neighbours feel about each other. Since closed tours start at a fixed guest, arrangements that are just
+// rotations of each other are only considered once.
+fn find_optimal_seating_arrangement(dinner_description: &DinnerDescription) -> Option<Tour> {
+    let guest_count = dinner_description.guests.len();
+    let mut graph = Graph::new(guest_count);
+    for guest1_id in 0..guest_count {
+        for guest2_id in (0..guest_count).filter(|id| *id != guest1_id) {
+            let happiness = dinner_description.get_happiness_change(guest1_id, guest2_id)
+                + dinner_description.get_happiness_change(guest2_id, guest1_id);
+            graph.set_weight(guest1_id, guest2_id, i64::from(happiness));
         }
     }
 
-    let remaining_guests: Vec<usize> = (0..dinner_description.guests.len()).collect();
-    let seating_arrangement = Vec::new();
-    find_optimal_seating_arrangement_happiness_recursive(dinner_description, &remaining_guests, &seating_arrangement)
+    find_best_tour(
+        &graph,
+        &TourOptions {
+            objective: Objective::Maximize,
+            is_closed: true,
+            start_id: None,
+        },
+    )
 }
 
 fn parse_happiness_rule(line: &str) -> ((String, String), i32) {
@@ -139,7 +152,7 @@ fn parse_dinner_description(input: &str) -> DinnerDescription {
     DinnerDescription::new(&input.lines().map(parse_happiness_rule).collect::<Vec<_>>())
 }
 
-fn solve(input: &str, log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
     let mut dinner_description = parse_dinner_description(input);
     if let Some(log_fn) = log_fn {
         log_fn(&format!("{dinner_description}"));
@@ -147,23 +160,29 @@ fn solve(input: &str, log_fn: Option<fn(&str)>) -> (String, String) {
     }
 
     // Part 1: Find the optimal seating arrangement for the given happiness rules.
+    let seating_arrangement =
+        find_optimal_seating_arrangement(&dinner_description).expect("There are no guests to seat");
+    let part1_result = seating_arrangement.weight;
+    dinner_description.seating_arrangement = Some(seating_arrangement);
     if let Some(log_fn) = log_fn {
         log_fn("Part 1");
         log_fn("----------");
         log_fn(&format!("{dinner_description}"));
         log_fn("-----");
     }
-    let part1_result = find_optimal_seating_arrangement_happiness(&dinner_description);
 
     // Part 2: Find the optimal seating arrangement for the given happiness rules, with yourself added.
     dinner_description.ensure_guest("You");
+    let seating_arrangement =
+        find_optimal_seating_arrangement(&dinner_description).expect("There are no guests to seat");
+    let part2_result = seating_arrangement.weight;
+    dinner_description.seating_arrangement = Some(seating_arrangement);
     if let Some(log_fn) = log_fn {
         log_fn("Part 2");
         log_fn("----------");
         log_fn(&format!("{dinner_description}"));
         log_fn("-----");
     }
-    let part2_result = find_optimal_seating_arrangement_happiness(&dinner_description);
 
     (part1_result.to_string(), part2_result.to_string())
 }
@@ -1,10 +1,12 @@
 // Advent of Code 2015, Day 14: "Reindeer Olympics"
 // https://adventofcode.com/2015/day/14
 
+use std::io::{self, Write};
+use std::path::Path;
+
 // Represents the desecription of a reindeer's characteristics.
 #[derive(Clone, Debug)]
 struct ReindeerDescription {
-    #[allow(dead_code)]
     name: String,
     speed: u32,
     fly_time: u32,
@@ -28,170 +30,473 @@ fn parse_reindeer_descriptions(input: &str) -> Vec<ReindeerDescription> {
     input.lines().map(parse_reindeer_description).collect()
 }
 
-// Identifies the state of a reindeer's movement.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum ReindeerMovementState {
-    Flying,
-    Resting,
+// Returns the greatest common divisor of the given numbers.
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
 }
 
-// Represents a reindeer, including its capabilities and current state.
+// Represents a reindeer. A reindeer's movement repeats in cycles of flying and resting, so where it is at any time can
+// be calculated directly, without simulating the race up to that time.
 #[derive(Clone, Debug)]
 struct Reindeer {
     description: ReindeerDescription,
-    distance_traveled: u32,
-    state: ReindeerMovementState,
-    state_ticks_remaining: u32,
 }
 
 impl Reindeer {
     // Creates a new Reindeer with the given description.
     fn new(description: ReindeerDescription) -> Reindeer {
-        let state_ticks_remaining = description.fly_time;
-        Reindeer {
-            description,
-            distance_traveled: 0,
-            state: ReindeerMovementState::Flying,
-            state_ticks_remaining,
+        assert!(
+            description.fly_time + description.rest_time > 0,
+            "Reindeer {} must fly or rest for some time",
+            description.name
+        );
+        Reindeer { description }
+    }
+
+    // Returns the length of a cycle of flying and then resting, in seconds.
+    fn cycle_time(&self) -> u64 {
+        u64::from(self.description.fly_time) + u64::from(self.description.rest_time)
+    }
+
+    // Returns the distance that the reindeer has traveled after the given number of seconds: the distance flown in
+    // each complete cycle, plus the distance flown in the current one.
+    fn distance_at(&self, time: u64) -> u64 {
+        let fly_time = u64::from(self.description.fly_time);
+        let cycle_count = time / self.cycle_time();
+        let flying_time = cycle_count * fly_time + u64::min(time % self.cycle_time(), fly_time);
+        flying_time * u64::from(self.description.speed)
+    }
+
+    // Returns the first time after the given one at which the reindeer starts or stops flying. Between such times,
+    // the distance that the reindeer has traveled changes at a constant rate.
+    fn get_next_state_change(&self, time: u64) -> u64 {
+        let phase = time % self.cycle_time();
+        let fly_time = u64::from(self.description.fly_time);
+        if phase < fly_time {
+            time - phase + fly_time
+        } else {
+            time - phase + self.cycle_time()
         }
     }
+}
 
-    // Advances the reindeer's state for the given number of ticks.
-    fn advance(&mut self, ticks: u32) {
-        // Continue advancing the reindeer's state until the given number of ticks has been used up.
-        let mut ticks_remaining = ticks;
-        while ticks_remaining > 0 {
-            // Determine the number of ticks to advance by the minimum of the number of ticks remaining
-            // in the current advancement interval and the number of ticks remaining for the reindeer's
-            // current state. This ensures that only a single state will be processed in each iteration
-            // and that no more than the given number of ticks will be advanced.
-            let ticks_to_advance = ticks_remaining.min(self.state_ticks_remaining);
-
-            // If the reindeer is flying, advance its distance traveled by the distance it would travel
-            // in the current advancement interval.
-            if self.state == ReindeerMovementState::Flying {
-                self.distance_traveled += ticks_to_advance * self.description.speed;
+// Returns the first second after the given one at which the first given reindeer has traveled at least the given
+// margin further than the second, or None if that never happens.
+//
+// The difference between the reindeer's distances changes at a constant rate between the times that either of them
+// starts or stops flying, so the crossing can be found by checking each of those intervals in turn. Both reindeer
+// are back in the same phases after the least common multiple of their cycle times, at which point the difference
+// has changed by a fixed amount. If the crossing isn't within the first such period, that amount shows how many
+// periods to skip to reach the one that it is in, or that it never happens.
+fn find_first_second_ahead(reindeer1: &Reindeer, reindeer2: &Reindeer, after: u64, margin: i64) -> Option<u64> {
+    let period = reindeer1.cycle_time() / gcd(reindeer1.cycle_time(), reindeer2.cycle_time()) * reindeer2.cycle_time();
+    let get_lead =
+        |time| i128::from(reindeer1.distance_at(time)) - i128::from(reindeer2.distance_at(time)) - i128::from(margin);
+
+    // Returns the first second in the period after the given time at which the lead is at least zero, or the
+    // largest lead in that period if there is no such second.
+    let search_period = |start: u64| -> Result<u64, i128> {
+        let end = start + period;
+        let (mut time, mut lead) = (start, get_lead(start));
+        let mut max_lead = i128::MIN;
+        while time < end {
+            let next_time = reindeer1
+                .get_next_state_change(time)
+                .min(reindeer2.get_next_state_change(time))
+                .min(end);
+            let next_lead = get_lead(next_time);
+            let rate = (next_lead - lead) / i128::from(next_time - time);
+            if lead + rate >= 0 {
+                return Ok(time + 1);
+            }
+            if next_lead >= 0 {
+                // The lead is rising, so it reaches zero somewhere in this interval.
+                let seconds = u64::try_from((-lead + rate - 1) / rate).unwrap();
+                return Ok(time + seconds);
             }
+            max_lead = max_lead.max(next_lead);
+            (time, lead) = (next_time, next_lead);
+        }
+        Err(max_lead)
+    };
 
-            // Reduce the number of ticks remaining for the reindeer's current state, and if the state
-            // has finished, switch to the next state.
-            self.state_ticks_remaining -= ticks_to_advance;
-            if self.state_ticks_remaining == 0 {
-                match self.state {
-                    ReindeerMovementState::Flying => {
-                        self.state = ReindeerMovementState::Resting;
-                        self.state_ticks_remaining = self.description.rest_time;
-                    }
-                    ReindeerMovementState::Resting => {
-                        self.state = ReindeerMovementState::Flying;
-                        self.state_ticks_remaining = self.description.fly_time;
-                    }
-                }
+    match search_period(after) {
+        Ok(second) => Some(second),
+        Err(max_lead) => {
+            let period_change = get_lead(after + period) - get_lead(after);
+            if period_change <= 0 {
+                return None;
             }
+            let period_count = u64::try_from((-max_lead + period_change - 1) / period_change).ok()?;
+            search_period(after.checked_add(period_count.checked_mul(period)?)?).ok()
+        }
+    }
+}
 
-            ticks_remaining -= ticks_to_advance;
+// Returns the first second after the given one at which the order of the given reindeer differs from their order
+// at that second: one overtakes the other, or they draw level, or they stop being level. Returns None if their order
+// never changes.
+fn find_next_order_change(reindeer1: &Reindeer, reindeer2: &Reindeer, second: u64) -> Option<u64> {
+    match reindeer1.distance_at(second).cmp(&reindeer2.distance_at(second)) {
+        std::cmp::Ordering::Less => find_first_second_ahead(reindeer1, reindeer2, second, 0),
+        std::cmp::Ordering::Greater => find_first_second_ahead(reindeer2, reindeer1, second, 0),
+        std::cmp::Ordering::Equal => {
+            let second1 = find_first_second_ahead(reindeer1, reindeer2, second, 1);
+            let second2 = find_first_second_ahead(reindeer2, reindeer1, second, 1);
+            second1.into_iter().chain(second2).min()
         }
     }
 }
 
-// Represents a single reindeer race.
+// Represents a stretch of a race during which the same reindeer are in the lead, from the first given second to the
+// last, inclusive.
+#[derive(Clone, Debug)]
+struct LeadInterval {
+    first_second: u64,
+    last_second: u64,
+    leader_ids: Vec<usize>,
+}
+
+// Represents the start of a stretch of a race during which only the reindeer that are fastest on average have been in
+// the lead, with the points and the number of changes of the lead at that second.
+#[derive(Clone, Debug)]
+struct LeadPeriodStart {
+    second: u64,
+    points: Vec<u64>,
+    lead_change_count: u64,
+}
+
+// Finds where the lead of a race starts repeating. After the least common multiple of the reindeer's cycle times, each
+// reindeer is back in the same phase of its cycle and has gained a fixed distance. If only the reindeer that gain the
+// most have been in the lead for a whole period, then the others are behind by at least as much in the next period
+// and the leaders are level with each other in the same way, so the lead repeats in every period after that.
+#[derive(Clone, Debug)]
+struct LeadPeriodFinder {
+    length: u64,
+    is_fastest: Vec<bool>,
+    start: Option<LeadPeriodStart>,
+}
+
+impl LeadPeriodFinder {
+    // Creates a new LeadPeriodFinder for a race of the given duration between the given reindeer, or None if the
+    // race is shorter than two of its periods, so that no periods could be skipped.
+    fn new(reindeer: &[Reindeer], duration: u64) -> Option<LeadPeriodFinder> {
+        let length = reindeer.iter().try_fold(1, |length: u64, r| {
+            (length / gcd(length, r.cycle_time())).checked_mul(r.cycle_time())
+        })?;
+        if length > duration / 2 {
+            return None;
+        }
+        let gains: Vec<u64> = reindeer.iter().map(|r| r.distance_at(length)).collect();
+        let max_gain = *gains.iter().max()?;
+        Some(LeadPeriodFinder {
+            length,
+            is_fastest: gains.iter().map(|gain| *gain == max_gain).collect(),
+            start: None,
+        })
+    }
+}
+
+// Represents a reindeer race of a given duration, with the points that each reindeer scored by being in the lead.
 #[derive(Clone, Debug)]
 struct ReindeerRace {
-    reindeer_race_states: Vec<(Reindeer, u32)>,
-    time_elapsed: u32,
+    reindeer: Vec<Reindeer>,
+    duration: u64,
+    points: Vec<u64>,
+    lead_change_count: u64,
+    lead_intervals: Option<Vec<LeadInterval>>,
 }
 
 impl ReindeerRace {
-    // Creates a new ReindeerRace for reindeer with the given descriptions.
-    fn new(descriptions: &[ReindeerDescription]) -> ReindeerRace {
-        let reindeer_race_states = descriptions.iter().map(|d| (Reindeer::new(d.clone()), 0)).collect();
-        ReindeerRace {
-            reindeer_race_states,
-            time_elapsed: 0,
-        }
+    // Runs a race of the given duration for reindeer with the given descriptions.
+    fn new(descriptions: &[ReindeerDescription], duration: u64) -> ReindeerRace {
+        Self::run(descriptions, duration, false)
+    }
+
+    // Runs a race in the same way as new, and also records each stretch of the race during which the same reindeer
+    // were in the lead, so that its timeline can be written.
+    fn with_timeline(descriptions: &[ReindeerDescription], duration: u64) -> ReindeerRace {
+        Self::run(descriptions, duration, true)
     }
 
-    // Advances the race (and all reindeer) by the given number of ticks.
-    fn advance(&mut self, ticks: u32) {
-        let mut ticks_remaining = ticks;
-        while ticks_remaining > 0 {
-            // Advance each reindeer by 1 tick.
-            for r in &mut self.reindeer_race_states {
-                r.0.advance(1);
+    // Runs a race of the given duration for reindeer with the given descriptions, recording its stretches of the same
+    // leaders if requested.
+    //
+    // Rather than checking who is in the lead every second, this jumps from one change of the lead to the next. The
+    // lead only changes when some reindeer changes its order relative to one of the leaders, so the next change is
+    // the earliest of those, and every second until then scores a point for each of the current leaders. Once the
+    // lead starts repeating (see LeadPeriodFinder), the points and changes of the lead in one period are multiplied
+    // to skip the rest of the whole periods, unless the stretches are being recorded.
+    fn run(descriptions: &[ReindeerDescription], duration: u64, record_lead_intervals: bool) -> ReindeerRace {
+        let reindeer: Vec<Reindeer> = descriptions.iter().cloned().map(Reindeer::new).collect();
+        let mut points = vec![0; reindeer.len()];
+        let mut lead_change_count = 0;
+        let mut lead_intervals = record_lead_intervals.then(Vec::new);
+        let mut lead_period_finder = if record_lead_intervals {
+            None
+        } else {
+            LeadPeriodFinder::new(&reindeer, duration)
+        };
+
+        let mut previous_leader_ids = Vec::new();
+        let mut second = 1;
+        while second <= duration {
+            let distances: Vec<u64> = reindeer.iter().map(|r| r.distance_at(second)).collect();
+            let max_distance = *distances.iter().max().unwrap();
+            let leader_ids: Vec<usize> = (0..reindeer.len())
+                .filter(|id| distances[*id] == max_distance)
+                .collect();
+            let is_lead_change = second > 1 && leader_ids != previous_leader_ids;
+            if is_lead_change {
+                lead_change_count += 1;
             }
 
-            // Award a point to each reindeer that is in the lead.
-            let max_distance = self.get_max_distance_traveled();
-            for r in &mut self.reindeer_race_states {
-                if r.0.distance_traveled == max_distance {
-                    r.1 += 1;
+            let mut period_end = duration;
+            if let Some(finder) = &mut lead_period_finder {
+                if !leader_ids.iter().all(|id| finder.is_fastest[*id]) {
+                    finder.start = None;
+                } else if let Some(start) = &finder.start {
+                    if second == start.second + finder.length {
+                        // The last period has the same leaders at each second as every whole period after it. Its
+                        // changes of the lead include the one at this second, which is counted again at the second
+                        // after the skipped periods, where the lead is the same as at this one.
+                        let period_count = (duration - second + 1) / finder.length;
+                        for (reindeer_points, start_points) in points.iter_mut().zip(&start.points) {
+                            *reindeer_points += (*reindeer_points - start_points) * period_count;
+                        }
+                        let period_lead_change_count = lead_change_count - start.lead_change_count;
+                        lead_change_count += period_lead_change_count * period_count;
+                        let skipped_seconds = period_count * finder.length;
+                        lead_period_finder = None;
+                        if skipped_seconds > 0 {
+                            lead_change_count -= u64::from(is_lead_change);
+                            second += skipped_seconds;
+                            continue;
+                        }
+                    } else {
+                        period_end = start.second + finder.length - 1;
+                    }
+                } else {
+                    finder.start = Some(LeadPeriodStart {
+                        second,
+                        points: points.clone(),
+                        lead_change_count,
+                    });
+                    period_end = second + finder.length - 1;
                 }
             }
 
-            ticks_remaining -= 1;
+            let leader = &reindeer[leader_ids[0]];
+            let next_change = (0..reindeer.len())
+                .filter(|id| *id != leader_ids[0])
+                .filter_map(|id| find_next_order_change(&reindeer[id], leader, second))
+                .min();
+            let last_second = next_change.map_or(period_end, |next_change| u64::min(next_change - 1, period_end));
+
+            for leader_id in &leader_ids {
+                points[*leader_id] += last_second - second + 1;
+            }
+            if let Some(lead_intervals) = &mut lead_intervals {
+                lead_intervals.push(LeadInterval {
+                    first_second: second,
+                    last_second,
+                    leader_ids: leader_ids.clone(),
+                });
+            }
+            previous_leader_ids = leader_ids;
+            second = last_second + 1;
         }
 
-        self.time_elapsed += ticks;
+        ReindeerRace {
+            reindeer,
+            duration,
+            points,
+            lead_change_count,
+            lead_intervals,
+        }
     }
 
-    // Returns the maximum distance any reindeer has traveled.
-    fn get_max_distance_traveled(&self) -> u32 {
-        self.reindeer_race_states
+    // Returns the maximum distance any reindeer has traveled by the end of the race.
+    fn get_max_distance_traveled(&self) -> u64 {
+        self.reindeer
             .iter()
-            .map(|r| r.0.distance_traveled)
+            .map(|r| r.distance_at(self.duration))
             .max()
             .unwrap()
     }
 
-    // Returns the reindeer currently in the lead.
+    // Returns the reindeer in the lead at the end of the race.
     fn get_lead_reindeer(&self) -> Vec<&Reindeer> {
         let max_distance = self.get_max_distance_traveled();
-        self.reindeer_race_states
+        self.reindeer
             .iter()
-            .filter(|r| r.0.distance_traveled == max_distance)
-            .map(|r| &r.0)
+            .filter(|r| r.distance_at(self.duration) == max_distance)
             .collect::<Vec<_>>()
     }
 
-    // Returns the reindeer with the most points.
-    fn get_winning_reindeer(&self) -> &(Reindeer, u32) {
-        self.reindeer_race_states.iter().max_by_key(|r| r.1).unwrap()
+    // Returns the reindeer with the most points, along with its points.
+    fn get_winning_reindeer(&self) -> (&Reindeer, u64) {
+        let (id, points) = self
+            .points
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, points)| **points)
+            .unwrap();
+        (&self.reindeer[id], *points)
+    }
+
+    // Writes the timeline of the race in CSV format to the given output, with a row for each stretch of the race
+    // during which the same reindeer were in the lead. Each row gives the distances and points of the reindeer at the
+    // end of its stretch. The race must have been run with its timeline.
+    fn write_timeline_csv(&self, output: &mut dyn Write) -> io::Result<()> {
+        let lead_intervals = self
+            .lead_intervals
+            .as_ref()
+            .expect("The race should have been run with its timeline");
+        let names: Vec<&str> = self.reindeer.iter().map(|r| r.description.name.as_str()).collect();
+        write!(output, "first_second,last_second,leaders")?;
+        for name in &names {
+            write!(output, ",{name}_distance")?;
+        }
+        for name in &names {
+            write!(output, ",{name}_points")?;
+        }
+        writeln!(output)?;
+
+        let mut points = vec![0; self.reindeer.len()];
+        for interval in lead_intervals {
+            for leader_id in &interval.leader_ids {
+                points[*leader_id] += interval.last_second - interval.first_second + 1;
+            }
+            let leader_names: Vec<&str> = interval.leader_ids.iter().map(|id| names[*id]).collect();
+            write!(
+                output,
+                "{},{},{}",
+                interval.first_second,
+                interval.last_second,
+                leader_names.join(" ")
+            )?;
+            for r in &self.reindeer {
+                write!(output, ",{}", r.distance_at(interval.last_second))?;
+            }
+            for reindeer_points in &points {
+                write!(output, ",{reindeer_points}")?;
+            }
+            writeln!(output)?;
+        }
+        Ok(())
     }
 }
 
-fn solve(input: &str, log_fn: Option<fn(&str)>) -> (String, String) {
+// Runs a race between the reindeer described in the file at the given path and writes its timeline in CSV format to the
+// given output (see ReindeerRace::write_timeline_csv). The race lasts as many seconds as the argument gives, or as long
+// as the puzzle's race if there is no argument.
+fn run_on_file(path: &Path, arguments: &[&str], output: &mut dyn Write) -> io::Result<()> {
+    let duration = match arguments {
+        [] => 2503,
+        [duration] => duration.parse::<u64>().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The race duration \"{duration}\" is not a number of seconds"),
+            )
+        })?,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The only argument is the race duration",
+            ));
+        }
+    };
+    let reindeer_descriptions = parse_reindeer_descriptions(&std::fs::read_to_string(path)?);
+    ReindeerRace::with_timeline(&reindeer_descriptions, duration).write_timeline_csv(output)
+}
+
+fn solve(input: &str, log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
     let reindeer_descriptions = parse_reindeer_descriptions(input);
-    let mut race = ReindeerRace::new(&reindeer_descriptions);
+    let race = ReindeerRace::new(&reindeer_descriptions, 2503);
 
     // Part 1: Find the distance the lead reindeer has traveled after 2503 seconds.
-    race.advance(2503);
     let part1_result = race.get_max_distance_traveled();
 
-    if let Some(log_fn) = log_fn {
-        log_fn("Part 1");
-        log_fn("----------");
-        log_fn(&format!("Lead reindeer: {:#?}", race.get_lead_reindeer()));
-        log_fn("-----");
-        log_fn(&format!("{race:#?}"));
-        log_fn("-----");
-    }
-
     // Part 2: Find the number of points the winning reindeer has after 2503 seconds.
-    let winning_reindeer = race.get_winning_reindeer();
-    let part2_result = winning_reindeer.1;
+    let (winning_reindeer, part2_result) = race.get_winning_reindeer();
 
+    // If logging is enabled, print the leaders and winner of the race, and the winner of a far longer race.
     if let Some(log_fn) = log_fn {
-        log_fn("Part 2");
-        log_fn("----------");
-        log_fn(&format!("Lead reindeer: {:#?}", race.get_lead_reindeer()));
-        log_fn(&format!("Winning reindeer: {winning_reindeer:#?}"));
-        log_fn("-----");
-        log_fn(&format!("{race:#?}"));
-        log_fn("-----");
+        let lead_reindeer_names: Vec<&str> = race
+            .get_lead_reindeer()
+            .iter()
+            .map(|r| r.description.name.as_str())
+            .collect();
+        log_fn(&format!("Lead reindeer: {}", lead_reindeer_names.join(", ")));
+        log_fn(&format!(
+            "Winning reindeer: {} with {part2_result} points",
+            winning_reindeer.description.name
+        ));
+        log_fn(&format!("Changes of the lead: {}", race.lead_change_count));
+
+        let long_race = ReindeerRace::new(&reindeer_descriptions, 1_000_000_000);
+        let (long_race_winner, long_race_points) = long_race.get_winning_reindeer();
+        log_fn(&format!(
+            "After 1000000000 seconds, the lead reindeer has traveled {} and {} has won with {long_race_points} points",
+            long_race.get_max_distance_traveled(),
+            long_race_winner.description.name
+        ));
     }
 
     (part1_result.to_string(), part2_result.to_string())
 }
 
 #[linkme::distributed_slice(crate::SOLUTIONS)]
-static SOLUTION: crate::Solution = crate::Solution::new(2015, 14, solve);
+static SOLUTION: crate::Solution = crate::Solution::new(2015, 14, solve).with_file_runner(run_on_file);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Comet can fly 14 km/s for 10 seconds, but then must rest for 127 seconds.
+Dancer can fly 16 km/s for 11 seconds, but then must rest for 162 seconds.";
+
+    #[test]
+    fn example_race() {
+        let race = ReindeerRace::new(&parse_reindeer_descriptions(EXAMPLE), 1000);
+        assert_eq!(race.get_max_distance_traveled(), 1120);
+        assert_eq!(race.points, [312, 689]);
+        let (winning_reindeer, points) = race.get_winning_reindeer();
+        assert_eq!((winning_reindeer.description.name.as_str(), points), ("Dancer", 689));
+    }
+
+    #[test]
+    fn skipping_repeated_lead_periods_keeps_points() {
+        // The first two reindeer are equally fast on average, so the lead keeps changing between them, and the third
+        // leads at first but falls behind for good.
+        let equal_speeds = "Fast can fly 2 km/s for 1 seconds, but then must rest for 1 seconds.
+Steady can fly 3 km/s for 1 seconds, but then must rest for 2 seconds.
+Sprinter can fly 10 km/s for 1 seconds, but then must rest for 20 seconds.";
+        for (input, duration) in [(equal_speeds, 600), (equal_speeds, 10_007), (EXAMPLE, 1_000_003)] {
+            let descriptions = parse_reindeer_descriptions(input);
+            let race = ReindeerRace::new(&descriptions, duration);
+            let expected_race = ReindeerRace::with_timeline(&descriptions, duration);
+            assert_eq!(race.points, expected_race.points);
+            assert_eq!(race.lead_change_count, expected_race.lead_change_count);
+        }
+    }
+
+    #[test]
+    fn timeline_csv_has_row_for_each_lead() {
+        let race = ReindeerRace::with_timeline(&parse_reindeer_descriptions(EXAMPLE), 150);
+        let mut output = Vec::new();
+        race.write_timeline_csv(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "first_second,last_second,leaders,Comet_distance,Dancer_distance,Comet_points,Dancer_points
+1,139,Dancer,168,176,0,139
+140,150,Comet,280,176,11,139
+"
+        );
+    }
+}
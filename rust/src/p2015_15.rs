@@ -3,7 +3,6 @@
 
 #[derive(Clone, Debug)]
 struct IngredientDescription {
-    #[allow(dead_code)]
     name: String,
     capacity: i32,
     durability: i32,
@@ -16,7 +15,7 @@ fn parse_ingredient_description(line: &str) -> IngredientDescription {
     let words = line.split(&[' ', ',']).collect::<Vec<_>>();
     let integers = words.iter().filter_map(|w| w.parse::<i32>().ok()).collect::<Vec<_>>();
     IngredientDescription {
-        name: words[0].to_string(),
+        name: words[0].trim_end_matches(':').to_string(),
         capacity: integers[0],
         durability: integers[1],
         flavor: integers[2],
@@ -29,6 +28,10 @@ fn parse_ingredient_descriptions(input: &str) -> Vec<IngredientDescription> {
     input.lines().map(parse_ingredient_description).collect()
 }
 
+// The properties of the ingredients that make up a recipe's score.
+const SCORED_PROPERTIES: [fn(&IngredientDescription) -> i32; 4] =
+    [|i| i.capacity, |i| i.durability, |i| i.flavor, |i| i.texture];
+
 // Represents a cookie recipe.
 #[derive(Clone, Debug)]
 struct CookieRecipe {
@@ -42,102 +45,783 @@ impl CookieRecipe {
     }
 
     // Returns the total score of the recipe.
-    pub fn score(&self) -> i32 {
-        std::cmp::max(self.sum_property_impact(|i| i.capacity), 0)
-            * std::cmp::max(self.sum_property_impact(|i| i.durability), 0)
-            * std::cmp::max(self.sum_property_impact(|i| i.flavor), 0)
-            * std::cmp::max(self.sum_property_impact(|i| i.texture), 0)
+    pub fn score(&self) -> i64 {
+        SCORED_PROPERTIES
+            .iter()
+            .map(|property| i64::max(self.sum_property_impact(*property), 0))
+            .product()
     }
 
     // Returns the total number of calories in the recipe.
-    pub fn calories(&self) -> i32 {
+    pub fn calories(&self) -> i64 {
         self.sum_property_impact(|i| i.calories)
     }
 
     // Helper function that sums the impact on the specified property across all ingredient usages.
-    fn sum_property_impact(&self, field: fn(&IngredientDescription) -> i32) -> i32 {
+    fn sum_property_impact(&self, field: fn(&IngredientDescription) -> i32) -> i64 {
         self.ingredient_usage
             .iter()
-            .map(|(ingredient, usage)| field(ingredient) * (*usage as i32))
+            .map(|(ingredient, usage)| i64::from(field(ingredient)) * i64::from(*usage))
             .sum()
     }
 }
 
-// Represents a cookie recipe generator that will generate all possible cookie recipes
-// using exactly the given number of teaspoons of the given ingredients.
+// Implement the Display trait for CookieRecipe, listing the amount of each ingredient used along with the recipe's
+// score and calories.
+impl std::fmt::Display for CookieRecipe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let usages: Vec<String> = self
+            .ingredient_usage
+            .iter()
+            .map(|(ingredient, usage)| format!("{usage} {}", ingredient.name))
+            .collect();
+        write!(
+            f,
+            "{} (score {}, {} calories)",
+            usages.join(", "),
+            self.score(),
+            self.calories()
+        )
+    }
+}
+
+// Returns the quotient of the given numbers, rounded down.
+fn floor_div(dividend: i64, divisor: i64) -> i64 {
+    let quotient = dividend / divisor;
+    if dividend % divisor != 0 && (dividend < 0) != (divisor < 0) {
+        quotient - 1
+    } else {
+        quotient
+    }
+}
+
+// The largest number of steps taken towards the best parameters for bounding the score of a partial recipe. More
+// steps give tighter bounds, which cut off more of the search, but take longer to calculate.
+const MAX_BOUND_STEP_COUNT: usize = 100;
+
+// The number of scored properties.
+const PROPERTY_COUNT: usize = SCORED_PROPERTIES.len();
+
+// Converts the given whole number to a floating-point number, which is exact for the values that recipes can have.
+#[allow(clippy::cast_precision_loss)]
+fn to_float(value: i64) -> f64 {
+    value as f64
+}
+
+// Describes how the value of a linear constraint's expression must compare with its bound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Comparison {
+    AtMost,
+    Equal,
+    AtLeast,
+}
+
+// Represents a linear constraint on a recipe: the sum of the amount of each ingredient multiplied by its coefficient
+// must compare with the bound in the given way.
 #[derive(Clone, Debug)]
-struct CookieRecipeGenerator {
-    ingredients: Vec<IngredientDescription>,
-    ingredient_amounts: Vec<u32>,
-    required_total_teaspoons: u32,
+struct LinearConstraint {
+    coefficients: Vec<i32>,
+    comparison: Comparison,
+    bound: i32,
 }
 
-impl CookieRecipeGenerator {
-    // Creates a new CookieRecipeGenerator with the given ingredients and total number of teaspoons.
-    fn new(ingredients: Vec<IngredientDescription>, total_teaspoons: u32) -> CookieRecipeGenerator {
-        let ingredient_amounts = vec![0; ingredients.len()];
-        CookieRecipeGenerator {
-            ingredients,
-            ingredient_amounts,
-            required_total_teaspoons: total_teaspoons,
+impl LinearConstraint {
+    // Creates a constraint on the total of the given property over the given ingredients, e.g. that a recipe has
+    // exactly 500 calories.
+    fn on_property(
+        ingredients: &[IngredientDescription],
+        property: fn(&IngredientDescription) -> i32,
+        comparison: Comparison,
+        bound: i32,
+    ) -> LinearConstraint {
+        LinearConstraint {
+            coefficients: ingredients.iter().map(property).collect(),
+            comparison,
+            bound,
+        }
+    }
+
+    // Creates a constraint on the amount of the ingredient with the given index, out of the given number of
+    // ingredients, e.g. that a recipe uses at least 10 teaspoons of it.
+    fn on_ingredient(ingredient_count: usize, index: usize, comparison: Comparison, amount: i32) -> LinearConstraint {
+        let mut coefficients = vec![0; ingredient_count];
+        coefficients[index] = 1;
+        LinearConstraint {
+            coefficients,
+            comparison,
+            bound: amount,
+        }
+    }
+
+    // Returns the value of the constraint's expression for the given amounts of the ingredients.
+    fn evaluate(&self, amounts: &[u32]) -> i64 {
+        self.coefficients
+            .iter()
+            .zip(amounts)
+            .map(|(c, amount)| i64::from(*c) * i64::from(*amount))
+            .sum()
+    }
+
+    // Returns the sign that a multiplier for the constraint must have when it is relaxed into a bound: positive if
+    // its expression must be at most its bound, negative if it must be at least its bound, and zero if either sign
+    // will do.
+    fn get_multiplier_sign(&self) -> f64 {
+        match self.comparison {
+            Comparison::AtMost => 1.0,
+            Comparison::Equal => 0.0,
+            Comparison::AtLeast => -1.0,
+        }
+    }
+
+    // Returns whether some value in the given range of values of the constraint's expression satisfies it.
+    fn can_be_satisfied_in(&self, min_value: f64, max_value: f64) -> bool {
+        let bound = f64::from(self.bound);
+        match self.comparison {
+            Comparison::AtMost => min_value <= bound,
+            Comparison::Equal => min_value <= bound && bound <= max_value,
+            Comparison::AtLeast => max_value >= bound,
+        }
+    }
+
+    // If the constraint only involves a single ingredient, narrows the given ranges of allowed amounts of each
+    // ingredient to the amounts of that ingredient that satisfy it.
+    fn narrow_amount_ranges(&self, min_amounts: &mut [u32], max_amounts: &mut [u32]) {
+        let mut nonzero_coefficients = self.coefficients.iter().enumerate().filter(|(_, c)| **c != 0);
+        let (Some((index, coefficient)), None) = (nonzero_coefficients.next(), nonzero_coefficients.next()) else {
+            return;
+        };
+
+        // The constraint says how the amount compares with the bound divided by the coefficient, with the comparison
+        // reversed if the coefficient is negative. Amounts are whole, so the quotient is rounded inwards.
+        let (bound, coefficient) = (i64::from(self.bound), i64::from(*coefficient));
+        let (floor, ceiling) = (floor_div(bound, coefficient), -floor_div(-bound, coefficient));
+        let clamp = |amount: i64| u32::try_from(amount.max(0)).unwrap_or(u32::MAX);
+        let (has_upper_bound, has_lower_bound) = match (self.comparison, coefficient > 0) {
+            (Comparison::Equal, _) => (true, true),
+            (Comparison::AtMost, is_positive) => (is_positive, !is_positive),
+            (Comparison::AtLeast, is_positive) => (!is_positive, is_positive),
+        };
+        if has_upper_bound {
+            max_amounts[index] = max_amounts[index].min(clamp(floor));
+        }
+        if has_lower_bound {
+            min_amounts[index] = min_amounts[index].max(clamp(ceiling));
         }
     }
 }
 
-impl Iterator for CookieRecipeGenerator {
-    type Item = CookieRecipe;
+// Describes the state of a search for the best recipe.
+struct RecipeSearch {
+    // The smallest and largest amount allowed for each ingredient.
+    min_amounts: Vec<u32>,
+    max_amounts: Vec<u32>,
+    // The amount chosen for each ingredient so far.
+    amounts: Vec<u32>,
+    // The best recipe found so far, given as its score and the amount of each ingredient.
+    best: Option<(i64, Vec<u32>)>,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        // Increment the ingredient amounts to the next valid recipe.
-        let mut index = 1;
-        loop {
-            self.ingredient_amounts[index] += 1;
-            if self.ingredient_amounts[1..].iter().sum::<u32>() <= self.required_total_teaspoons {
-                break;
-            }
+impl RecipeSearch {
+    // Returns the largest value of the sum of the given coefficients multiplied by the amounts of the ingredients,
+    // along with the amounts that give it, over recipes that use the given total number of teaspoons, keep the
+    // amounts chosen for the ingredients before the given index and keep every other amount in its allowed range.
+    // Returns None if there is no such recipe.
+    fn maximize(&self, coefficients: &[f64], first_index: usize, total_teaspoons: u32) -> Option<(f64, Vec<u32>)> {
+        let teaspoons = total_teaspoons.checked_sub(self.amounts[..first_index].iter().sum())?;
+        let range = first_index..coefficients.len();
+        let min_teaspoons: u32 = self.min_amounts[range.clone()].iter().sum();
+        let max_teaspoons: u32 = self.max_amounts[range.clone()].iter().sum();
+        if teaspoons < min_teaspoons || teaspoons > max_teaspoons {
+            return None;
+        }
 
-            self.ingredient_amounts[index] = 0;
-            index += 1;
-            if index >= self.ingredient_amounts.len() {
-                return None;
-            }
+        // Start with the smallest amount of each remaining ingredient, then share out the rest of the teaspoons,
+        // giving as many as possible to the ingredients with the largest coefficients first.
+        let mut amounts = self.amounts[..first_index].to_vec();
+        amounts.extend_from_slice(&self.min_amounts[range.clone()]);
+        let mut indices: Vec<usize> = range.collect();
+        indices.sort_unstable_by(|a, b| coefficients[*b].total_cmp(&coefficients[*a]));
+        let mut teaspoons_left = teaspoons - min_teaspoons;
+        for index in indices {
+            let extra_amount = u32::min(teaspoons_left, self.max_amounts[index] - self.min_amounts[index]);
+            amounts[index] += extra_amount;
+            teaspoons_left -= extra_amount;
         }
-        let partial_total_ingredients_amount = self.ingredient_amounts[1..].iter().sum::<u32>();
-        self.ingredient_amounts[0] = self.required_total_teaspoons - partial_total_ingredients_amount;
 
-        // Create a new recipe from the current ingredient amounts.
-        let ingredient_usage = self
+        let value = coefficients
+            .iter()
+            .zip(&amounts)
+            .map(|(c, amount)| c * f64::from(*amount))
+            .sum();
+        Some((value, amounts))
+    }
+
+    // Returns whether a recipe whose score is at most the given bound could be better than the best recipe found so
+    // far. Scores are whole numbers, so it can only be better if the bound is at least one more than the best score,
+    // allowing for a little floating-point error in the bound.
+    fn can_improve_with(&self, bound: f64) -> bool {
+        self.best
+            .as_ref()
+            .is_none_or(|(best_score, _)| bound >= to_float(*best_score) + 1.0 - 1e-6)
+    }
+}
+
+// Returns an upper bound on the score of any recipe whose scored properties, multiplied by the given positive weights,
+// add up to at most the given sum.
+//
+// The bound comes from the inequality of arithmetic and geometric means: when every property is positive, the
+// product of the weighted properties is at most their mean raised to the number of properties. The properties of a
+// recipe are also whole numbers, so its score is zero unless every property is at least one, which can't be the case
+// if their weighted average is less than one.
+fn get_weighted_score_bound(property_weights: &[f64], max_weighted_sum: f64) -> f64 {
+    if max_weighted_sum < property_weights.iter().sum() {
+        return 0.0;
+    }
+    let property_count = i32::try_from(PROPERTY_COUNT).unwrap();
+    (max_weighted_sum / f64::from(property_count)).powi(property_count) / property_weights.iter().product::<f64>()
+}
+
+// Describes a constraint that can't be used for a set of ingredients, because it doesn't have a coefficient for each
+// of them.
+#[derive(Debug, Clone, Copy)]
+struct MismatchedConstraint {
+    coefficient_count: usize,
+    ingredient_count: usize,
+}
+
+impl std::fmt::Display for MismatchedConstraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The constraint has {} coefficients, but there are {} ingredients",
+            self.coefficient_count, self.ingredient_count
+        )
+    }
+}
+
+// Finds the best recipe that uses exactly a given number of teaspoons of the given ingredients and satisfies a set of
+// linear constraints.
+//
+// This is a branch and bound search, which chooses the amount of each ingredient in turn. The amounts that are left
+// to choose lie within per-ingredient limits and add up to the remaining number of teaspoons, and the largest and
+// smallest values of any linear expression over such amounts can be found exactly by giving as much as possible to
+// the ingredients with the largest or smallest coefficients. That is used both to drop partial recipes that can't
+// satisfy every constraint and to bound the best score that a partial recipe can lead to.
+struct RecipeOptimizer {
+    ingredients: Vec<IngredientDescription>,
+    total_teaspoons: u32,
+    constraints: Vec<LinearConstraint>,
+}
+
+impl RecipeOptimizer {
+    // Creates a new RecipeOptimizer for recipes that use exactly the given number of teaspoons of the given
+    // ingredients.
+    fn new(ingredients: &[IngredientDescription], total_teaspoons: u32) -> RecipeOptimizer {
+        RecipeOptimizer {
+            ingredients: ingredients.to_vec(),
+            total_teaspoons,
+            constraints: Vec::new(),
+        }
+    }
+
+    // Adds the given constraint to the ones that recipes must satisfy, or returns an error if it doesn't have a
+    // coefficient for each ingredient.
+    fn with_constraint(mut self, constraint: LinearConstraint) -> Result<RecipeOptimizer, MismatchedConstraint> {
+        if constraint.coefficients.len() != self.ingredients.len() {
+            return Err(MismatchedConstraint {
+                coefficient_count: constraint.coefficients.len(),
+                ingredient_count: self.ingredients.len(),
+            });
+        }
+        self.constraints.push(constraint);
+        Ok(self)
+    }
+
+    // Returns whether some recipe that completes the search's current amounts, which are chosen for the ingredients
+    // before the given index, could satisfy every constraint. Each constraint is checked against the range of values
+    // that its expression can take, so this may be true even if no recipe satisfies all of them at once.
+    fn can_satisfy_constraints(&self, search: &RecipeSearch, first_index: usize) -> bool {
+        let maximize = |coefficients: &[f64]| search.maximize(coefficients, first_index, self.total_teaspoons);
+        self.constraints.iter().all(|constraint| {
+            let coefficients: Vec<f64> = constraint.coefficients.iter().map(|c| f64::from(*c)).collect();
+            let negated_coefficients: Vec<f64> = coefficients.iter().map(|c| -c).collect();
+            match (maximize(&coefficients), maximize(&negated_coefficients)) {
+                (Some((max_value, _)), Some((negated_min_value, _))) => {
+                    constraint.can_be_satisfied_in(-negated_min_value, max_value)
+                }
+                _ => false,
+            }
+        })
+    }
+
+    // Returns the constraints that involve several ingredients. Constraints on a single ingredient narrow its range
+    // of allowed amounts instead, so bounds already take them into account.
+    fn get_coupling_constraints(&self) -> Vec<&LinearConstraint> {
+        self.constraints
+            .iter()
+            .filter(|constraint| constraint.coefficients.iter().filter(|c| **c != 0).count() > 1)
+            .collect()
+    }
+
+    // Returns the largest value of a function of the recipes that complete the search's current amounts, which are
+    // chosen for the ingredients before the given index, along with the function's gradient with respect to the given
+    // parameters at the recipe that gives it. Returns None if there is no such recipe.
+    //
+    // The parameters are a weight for each scored property, followed by a multiplier for each coupling constraint.
+    // The function is the weighted sum of the properties plus, for each coupling constraint, its multiplier times how
+    // far its expression is below its bound. When each multiplier is positive for a constraint that its expression
+    // be at most its bound, negative for one that it be at least its bound, and either for one that it be equal, the
+    // added terms are never negative for recipes that satisfy the constraints. So the largest value is at least the
+    // largest weighted sum over those recipes.
+    fn maximize_weighted_sum(
+        &self,
+        search: &RecipeSearch,
+        first_index: usize,
+        parameters: &[f64],
+    ) -> Option<(f64, Vec<f64>)> {
+        let constraints = self.get_coupling_constraints();
+        let (property_weights, multipliers) = parameters.split_at(PROPERTY_COUNT);
+        let coefficients: Vec<f64> = self
             .ingredients
             .iter()
-            .zip(self.ingredient_amounts.iter())
-            .map(|(ingredient, usage)| (ingredient.clone(), *usage))
+            .enumerate()
+            .map(|(index, ingredient)| {
+                let property_sum: f64 = SCORED_PROPERTIES
+                    .iter()
+                    .zip(property_weights)
+                    .map(|(property, weight)| f64::from(property(ingredient)) * weight)
+                    .sum();
+                let constraint_sum: f64 = constraints
+                    .iter()
+                    .zip(multipliers)
+                    .map(|(constraint, multiplier)| f64::from(constraint.coefficients[index]) * multiplier)
+                    .sum();
+                property_sum - constraint_sum
+            })
+            .collect();
+        let (_, amounts) = search.maximize(&coefficients, first_index, self.total_teaspoons)?;
+
+        // The function is linear in the parameters, so its value is the sum of the parameters multiplied by its
+        // gradient.
+        let mut gradient: Vec<f64> = SCORED_PROPERTIES
+            .iter()
+            .map(|property| {
+                let values = self.ingredients.iter().zip(&amounts);
+                to_float(
+                    values
+                        .map(|(i, amount)| i64::from(property(i)) * i64::from(*amount))
+                        .sum(),
+                )
+            })
             .collect();
-        Some(CookieRecipe::new(ingredient_usage))
+        gradient.extend(
+            constraints
+                .iter()
+                .map(|constraint| to_float(i64::from(constraint.bound) - constraint.evaluate(&amounts))),
+        );
+        let max_value = parameters.iter().zip(&gradient).map(|(p, g)| p * g).sum();
+        Some((max_value, gradient))
+    }
+
+    // Returns an upper bound on the score of any recipe that completes the search's current amounts, which are
+    // chosen for the ingredients before the given index, using the given weights for the scored properties and
+    // multipliers for the coupling constraints.
+    fn get_score_bound_with(&self, search: &RecipeSearch, first_index: usize, parameters: &[f64]) -> Option<f64> {
+        let (max_weighted_sum, _) = self.maximize_weighted_sum(search, first_index, parameters)?;
+        Some(get_weighted_score_bound(
+            &parameters[..PROPERTY_COUNT],
+            max_weighted_sum,
+        ))
+    }
+
+    // Returns an upper bound on the score of any recipe that completes the search's current amounts, which are
+    // chosen for the ingredients before the given index, along with the weights for the scored properties and
+    // multipliers for the coupling constraints that give it. Returns None if no such recipe satisfies every
+    // constraint.
+    //
+    // Any positive weights and multipliers with the right signs give a bound, and the best ones are found with the
+    // ellipsoid method, scaling the weights so that they add up to one. The largest weighted sum is a convex function
+    // of the parameters, and the weighted geometric mean that matches the current bound is a concave one, so the
+    // parameters that give a bound no larger than the current one lie on one side of the plane where their linear
+    // approximations at the current parameters are equal. Each step cuts the ellipsoid that contains the best
+    // parameters in half along that plane and replaces it with the smallest ellipsoid that contains the remaining
+    // half.
+    fn get_score_bound(&self, search: &RecipeSearch, first_index: usize) -> Option<(f64, Vec<f64>)> {
+        if !self.can_satisfy_constraints(search, first_index) {
+            return None;
+        }
+        // Once at most one amount is left to choose, the number of teaspoons left fixes it, so the score of the only
+        // recipe left is the tightest bound.
+        if first_index + 1 >= self.ingredients.len() {
+            let (_, amounts) =
+                search.maximize(&vec![0.0; self.ingredients.len()], first_index, self.total_teaspoons)?;
+            return Some((to_float(self.create_recipe(&amounts).score()), Vec::new()));
+        }
+        let constraints = self.get_coupling_constraints();
+        let parameter_count = PROPERTY_COUNT + constraints.len();
+        #[allow(clippy::cast_precision_loss)]
+        let (property_count, dimension) = (PROPERTY_COUNT as f64, (parameter_count - 1) as f64);
+
+        // Start with equal weights and no multipliers, in the smallest sphere around the weights that add up to one,
+        // stretched along each multiplier to cover the values that balance the largest property coefficient against
+        // the constraint's smallest coefficient.
+        let mut center = vec![1.0 / property_count; PROPERTY_COUNT];
+        center.resize(parameter_count, 0.0);
+        let mut shape = vec![vec![0.0; parameter_count]; parameter_count];
+        for (i, row) in shape.iter_mut().enumerate().take(PROPERTY_COUNT) {
+            for (j, value) in row.iter_mut().enumerate().take(PROPERTY_COUNT) {
+                *value =
+                    (if i == j { 1.0 } else { 0.0 } - 1.0 / property_count) * (property_count - 1.0) / property_count;
+            }
+        }
+        let max_property_coefficient = self
+            .ingredients
+            .iter()
+            .flat_map(|ingredient| SCORED_PROPERTIES.map(|property| property(ingredient).abs()))
+            .max()
+            .unwrap_or(0);
+        for (index, constraint) in constraints.iter().enumerate() {
+            let min_coefficient = constraint
+                .coefficients
+                .iter()
+                .filter(|c| **c != 0)
+                .map(|c| c.abs())
+                .min()
+                .unwrap();
+            let radius = 2.0 * f64::from(max_property_coefficient) / f64::from(min_coefficient);
+            shape[PROPERTY_COUNT + index][PROPERTY_COUNT + index] = radius * radius * dimension;
+        }
+
+        // Weights must be positive, and multipliers must have the signs that their constraints need.
+        let mut signs = vec![1.0; PROPERTY_COUNT];
+        signs.extend(constraints.iter().map(|constraint| constraint.get_multiplier_sign()));
+
+        let mut bound = self.get_score_bound_with(search, first_index, &center)?;
+        let mut best_parameters = center.clone();
+        for _ in 0..MAX_BOUND_STEP_COUNT {
+            if bound == 0.0 || !search.can_improve_with(bound) {
+                break;
+            }
+
+            // Parameters with the wrong signs are cut off directly.
+            let wrong_sign_index = (0..parameter_count).find(|index| {
+                center[*index] * signs[*index] < 0.0 || (*index < PROPERTY_COUNT && center[*index] == 0.0)
+            });
+            let normal: Vec<f64> = if let Some(wrong_sign_index) = wrong_sign_index {
+                (0..parameter_count)
+                    .map(|index| if index == wrong_sign_index { -signs[index] } else { 0.0 })
+                    .collect()
+            } else {
+                let (max_weighted_sum, mut gradient) = self.maximize_weighted_sum(search, first_index, &center)?;
+                let center_bound = get_weighted_score_bound(&center[..PROPERTY_COUNT], max_weighted_sum);
+                if center_bound < bound {
+                    bound = center_bound;
+                    best_parameters.clone_from(&center);
+                }
+                for (g, weight) in gradient.iter_mut().zip(&center[..PROPERTY_COUNT]) {
+                    *g -= max_weighted_sum / (property_count * weight);
+                }
+                gradient
+            };
+
+            let shaped_normal: Vec<f64> = shape
+                .iter()
+                .map(|row| row.iter().zip(&normal).map(|(s, n)| s * n).sum())
+                .collect();
+            let width_squared: f64 = shaped_normal.iter().zip(&normal).map(|(s, n)| s * n).sum();
+            if width_squared <= 1e-24 {
+                break;
+            }
+            let width = width_squared.sqrt();
+            for (parameter, s) in center.iter_mut().zip(&shaped_normal) {
+                *parameter -= s / width / (dimension + 1.0);
+            }
+            let scale = dimension * dimension / (dimension * dimension - 1.0);
+            for (i, row) in shape.iter_mut().enumerate() {
+                for (j, value) in row.iter_mut().enumerate() {
+                    let cut = 2.0 / (dimension + 1.0) * shaped_normal[i] * shaped_normal[j] / width_squared;
+                    *value = scale * (*value - cut);
+                }
+            }
+        }
+        Some((bound, best_parameters))
+    }
+
+    // Chooses the amount of the ingredient with the given index and each one after it, recording any recipe that is
+    // better than the best one found so far. The given parameters gave the best bound on the score of the current
+    // amounts, so they are used to bound the score for each possible amount quickly. Amounts are tried in order of
+    // those bounds, so that good recipes are found early and more of the search can be cut off, and each one gets a
+    // tighter bound of its own before it is searched. The search stops early if the given context is cancelled.
+    fn search(&self, search: &mut RecipeSearch, index: usize, parameters: &[f64], context: &crate::RunContext) {
+        if context.is_cancelled() {
+            return;
+        }
+
+        // The number of teaspoons left fixes the amount of the last ingredient, and its bound has already shown that
+        // the amount is allowed.
+        if index + 1 == self.ingredients.len() {
+            search.amounts[index] = self.total_teaspoons - search.amounts[..index].iter().sum::<u32>();
+            let score = self.create_recipe(&search.amounts).score();
+            if search.best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+                search.best = Some((score, search.amounts.clone()));
+            }
+            search.amounts[index] = 0;
+            return;
+        }
+
+        let mut candidates = Vec::new();
+        for amount in search.min_amounts[index]..=search.max_amounts[index] {
+            search.amounts[index] = amount;
+            if !self.can_satisfy_constraints(search, index + 1) {
+                continue;
+            }
+            if let Some(bound) = self.get_score_bound_with(search, index + 1, parameters) {
+                candidates.push((bound, amount));
+            }
+        }
+        candidates.sort_unstable_by(|a, b| b.0.total_cmp(&a.0));
+
+        for (bound, amount) in candidates {
+            if !search.can_improve_with(bound) {
+                break;
+            }
+            search.amounts[index] = amount;
+            if let Some((bound, parameters)) = self.get_score_bound(search, index + 1) {
+                if search.can_improve_with(bound) {
+                    self.search(search, index + 1, &parameters, context);
+                }
+            }
+        }
+        search.amounts[index] = 0;
+    }
+
+    // Creates a recipe that uses the given amount of each ingredient.
+    fn create_recipe(&self, amounts: &[u32]) -> CookieRecipe {
+        let ingredient_usage = self.ingredients.iter().cloned().zip(amounts.iter().copied()).collect();
+        CookieRecipe::new(ingredient_usage)
+    }
+
+    // Returns the best recipe that satisfies every constraint, or None if there is no such recipe. If the given context
+    // is cancelled, then the best recipe found so far is returned instead, if any.
+    fn find_best_recipe(&self, context: &crate::RunContext) -> Option<CookieRecipe> {
+        let ingredient_count = self.ingredients.len();
+        let mut search = RecipeSearch {
+            min_amounts: vec![0; ingredient_count],
+            max_amounts: vec![self.total_teaspoons; ingredient_count],
+            amounts: vec![0; ingredient_count],
+            best: None,
+        };
+        for constraint in &self.constraints {
+            constraint.narrow_amount_ranges(&mut search.min_amounts, &mut search.max_amounts);
+        }
+        if (0..ingredient_count).any(|index| search.min_amounts[index] > search.max_amounts[index]) {
+            return None;
+        }
+
+        if ingredient_count > 0 {
+            if let Some((_, parameters)) = self.get_score_bound(&search, 0) {
+                self.search(&mut search, 0, &parameters, context);
+            }
+        }
+        search.best.map(|(_, amounts)| self.create_recipe(&amounts))
     }
 }
 
-fn solve(input: &str, _log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, log_fn: Option<fn(&str)>, context: &crate::RunContext) -> (String, String) {
     let ingredients = parse_ingredient_descriptions(input);
 
     // Part 1: Find the highest scoring cookie that can be made with the given ingredients.
+    let best_recipe = RecipeOptimizer::new(&ingredients, 100).find_best_recipe(context);
+    let part1_result = best_recipe.as_ref().map_or(0, CookieRecipe::score);
+
     // Part 2: Find the highest scoring cookie that can be made with the given ingredients, but
     // with 500 calories.
-    let generator = CookieRecipeGenerator::new(ingredients.clone(), 100);
-    let (part1_result, part2_result) = generator.fold((0, 0), |(part1, part2), recipe| {
-        let score = recipe.score();
-        let calories = recipe.calories();
-        (
-            std::cmp::max(part1, score),
-            if calories == 500 {
-                std::cmp::max(part2, score)
-            } else {
-                part2
-            },
-        )
-    });
+    let best_diet_recipe = RecipeOptimizer::new(&ingredients, 100)
+        .with_constraint(LinearConstraint::on_property(
+            &ingredients,
+            |i| i.calories,
+            Comparison::Equal,
+            500,
+        ))
+        .expect("The calorie constraint has a coefficient for each ingredient")
+        .find_best_recipe(context);
+    let part2_result = best_diet_recipe.as_ref().map_or(0, CookieRecipe::score);
+
+    // If logging is enabled, print the best recipes, along with the best recipe with at most 500 calories that uses
+    // at least 10 teaspoons of every ingredient.
+    if let Some(log_fn) = log_fn {
+        let describe = |recipe: Option<&CookieRecipe>| recipe.map_or_else(|| "none".to_string(), ToString::to_string);
+        log_fn(&format!("Best recipe: {}", describe(best_recipe.as_ref())));
+        log_fn(&format!(
+            "Best recipe with 500 calories: {}",
+            describe(best_diet_recipe.as_ref())
+        ));
+
+        let optimizer = RecipeOptimizer::new(&ingredients, 100)
+            .with_constraint(LinearConstraint::on_property(
+                &ingredients,
+                |i| i.calories,
+                Comparison::AtMost,
+                500,
+            ))
+            .and_then(|optimizer| {
+                (0..ingredients.len()).try_fold(optimizer, |optimizer, index| {
+                    optimizer.with_constraint(LinearConstraint::on_ingredient(
+                        ingredients.len(),
+                        index,
+                        Comparison::AtLeast,
+                        10,
+                    ))
+                })
+            });
+        let description = match optimizer {
+            Ok(optimizer) => describe(optimizer.find_best_recipe(context).as_ref()),
+            Err(e) => e.to_string(),
+        };
+        log_fn(&format!(
+            "Best recipe with at most 500 calories and at least 10 teaspoons of each ingredient: {description}"
+        ));
+    }
 
     (part1_result.to_string(), part2_result.to_string())
 }
 
 #[linkme::distributed_slice(crate::SOLUTIONS)]
 static SOLUTION: crate::Solution = crate::Solution::new(2015, 15, solve);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run_context::CancellationToken;
+
+    const EXAMPLE_INPUT: &str = "\
+Butterscotch: capacity -1, durability -2, flavor 6, texture 3, calories 8
+Cinnamon: capacity 2, durability 3, flavor -2, texture -1, calories 3";
+
+    #[test]
+    fn solve_finds_best_recipes() {
+        let context = crate::RunContext::new(CancellationToken::new());
+        let (part1, part2) = solve(EXAMPLE_INPUT, None, &context);
+        assert_eq!((part1.as_str(), part2.as_str()), ("62842880", "57600000"));
+    }
+
+    // Returns the amounts of the ingredients in the best recipes that use exactly the given number of teaspoons and
+    // satisfy the given constraints, found by trying every recipe.
+    fn find_best_amounts_by_enumeration(
+        ingredients: &[IngredientDescription],
+        total_teaspoons: u32,
+        constraints: &[LinearConstraint],
+    ) -> (i64, Vec<Vec<u32>>) {
+        let mut best = (-1, Vec::new());
+        let mut amounts = vec![0; ingredients.len()];
+        loop {
+            let last_amount = total_teaspoons.checked_sub(amounts[..amounts.len() - 1].iter().sum::<u32>());
+            if let Some(last_amount) = last_amount {
+                *amounts.last_mut().unwrap() = last_amount;
+                let is_satisfied = |constraint: &LinearConstraint| {
+                    let (value, bound) = (constraint.evaluate(&amounts), i64::from(constraint.bound));
+                    match constraint.comparison {
+                        Comparison::AtMost => value <= bound,
+                        Comparison::Equal => value == bound,
+                        Comparison::AtLeast => value >= bound,
+                    }
+                };
+                if constraints.iter().all(is_satisfied) {
+                    let usage = ingredients.iter().cloned().zip(amounts.iter().copied()).collect();
+                    let score = CookieRecipe::new(usage).score();
+                    if score > best.0 {
+                        best = (score, Vec::new());
+                    }
+                    if score == best.0 {
+                        best.1.push(amounts.clone());
+                    }
+                }
+            }
+
+            // Move on to the next amounts of all but the last ingredient, like counting in base total_teaspoons + 1.
+            let Some(index) = (0..amounts.len() - 1).find(|index| amounts[*index] < total_teaspoons) else {
+                return best;
+            };
+            amounts[..index].fill(0);
+            amounts[index] += 1;
+        }
+    }
+
+    #[test]
+    fn search_matches_enumeration_with_constraints() {
+        let ingredients = parse_ingredient_descriptions(
+            "\
+Almond: capacity -1, durability 3, flavor 3, texture 3, calories 8
+Butter: capacity 5, durability -1, flavor -1, texture 2, calories 8
+Cocoa: capacity -1, durability -2, flavor 2, texture 5, calories 5
+Date: capacity 4, durability 3, flavor -2, texture 5, calories 6",
+        );
+        let context = crate::RunContext::new(CancellationToken::new());
+
+        // Each set of constraints has a single best recipe, except for the last, which can't be satisfied as every
+        // ingredient has at least 5 calories.
+        for (max_calories, min_amounts) in [
+            (706, [0, 20, 0, 10]),
+            (650, [0, 0, 0, 0]),
+            (800, [10, 10, 10, 10]),
+            (450, [0, 0, 0, 0]),
+        ] {
+            let mut constraints = vec![LinearConstraint::on_property(
+                &ingredients,
+                |i| i.calories,
+                Comparison::AtMost,
+                max_calories,
+            )];
+            for (index, min_amount) in min_amounts.into_iter().enumerate() {
+                constraints.push(LinearConstraint::on_ingredient(
+                    ingredients.len(),
+                    index,
+                    Comparison::AtLeast,
+                    min_amount,
+                ));
+            }
+
+            let (best_score, best_amounts) = find_best_amounts_by_enumeration(&ingredients, 100, &constraints);
+            assert!(best_amounts.len() <= 1, "{max_calories} {min_amounts:?}");
+            let optimizer = constraints
+                .into_iter()
+                .try_fold(
+                    RecipeOptimizer::new(&ingredients, 100),
+                    RecipeOptimizer::with_constraint,
+                )
+                .unwrap();
+            let recipe = optimizer.find_best_recipe(&context);
+            let amounts = recipe.as_ref().map(|recipe| {
+                let amounts: Vec<u32> = recipe.ingredient_usage.iter().map(|(_, amount)| *amount).collect();
+                (amounts, recipe.score())
+            });
+            let expected_amounts = best_amounts.first().map(|amounts| (amounts.clone(), best_score));
+            assert_eq!(amounts, expected_amounts, "{max_calories} {min_amounts:?}");
+        }
+    }
+
+    #[test]
+    fn search_stops_when_cancelled() {
+        let ingredients = parse_ingredient_descriptions(EXAMPLE_INPUT);
+        let cancellation_token = CancellationToken::new();
+        cancellation_token.cancel();
+        let context = crate::RunContext::new(cancellation_token);
+        assert!(RecipeOptimizer::new(&ingredients, 100)
+            .find_best_recipe(&context)
+            .is_none());
+    }
+
+    #[test]
+    fn with_constraint_rejects_mismatched_constraint() {
+        let ingredients = parse_ingredient_descriptions(EXAMPLE_INPUT);
+        let constraint = LinearConstraint::on_ingredient(3, 0, Comparison::AtLeast, 10);
+        let error = RecipeOptimizer::new(&ingredients, 100)
+            .with_constraint(constraint)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "The constraint has 3 coefficients, but there are 2 ingredients"
+        );
+    }
+}
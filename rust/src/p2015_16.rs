@@ -27,7 +27,7 @@ fn parse_sue_descriptions(input: &str) -> Vec<SueDescription> {
     input.lines().map(parse_sue_description).collect()
 }
 
-fn solve(input: &str, _log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, _log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
     let sue_desciptions = parse_sue_descriptions(input);
 
     let measured_sue_characteristics: HashMap<String, u32> = [
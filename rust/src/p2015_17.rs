@@ -91,7 +91,7 @@ fn parse_container_sizes(input: &str) -> Vec<u32> {
     input.lines().map(|line| line.parse::<u32>().unwrap()).collect()
 }
 
-fn solve(input: &str, log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
     let container_sizes = parse_container_sizes(input);
 
     // Part 1: Find the number of ways to fill the containers with 150 liters of eggnog.
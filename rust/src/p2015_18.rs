@@ -143,7 +143,7 @@ fn run_animation(grid: &mut LightGrid, count: usize, log_fn: Option<fn(&str)>) {
     }
 }
 
-fn solve(input: &str, log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
     let mut light_grid = parse_light_grid(input);
     let mut light_grid2 = light_grid.clone();
 
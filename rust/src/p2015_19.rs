@@ -1,7 +1,6 @@
 // Advent of Code 2015, Day 19: "Medicine for Rudolph"
 // https://adventofcode.com/2015/day/19
 
-use priority_queue::PriorityQueue;
 use std::{
     cell::RefCell,
     collections::{HashMap, HashSet},
@@ -64,35 +63,22 @@ impl ReplacementRule {
 
 struct MoleculeMachine {
     replacement_rules: HashMap<usize, Vec<ReplacementRule>>,
-    replacement_rules_reverse: HashMap<Vec<usize>, usize>,
     chemistry: Rc<RefCell<Chemistry>>,
-    terminal_elements: HashSet<usize>,
 }
 
 impl MoleculeMachine {
     pub fn new(replacement_rules: Vec<ReplacementRule>, chemistry: Rc<RefCell<Chemistry>>) -> MoleculeMachine {
         let mut machine = MoleculeMachine {
             replacement_rules: HashMap::new(),
-            replacement_rules_reverse: HashMap::new(),
             chemistry,
-            terminal_elements: HashSet::new(),
         };
 
         for rule in replacement_rules {
             machine
                 .replacement_rules
                 .entry(rule.from_element_id)
-                .or_insert_with(Vec::new)
-                .push(rule.clone());
-            machine
-                .replacement_rules_reverse
-                .insert(rule.to_element_ids.clone(), rule.from_element_id);
-        }
-
-        for element_id in machine.chemistry.borrow().element_ids.values() {
-            if !machine.replacement_rules.contains_key(element_id) {
-                machine.terminal_elements.insert(*element_id);
-            }
+                .or_default()
+                .push(rule);
         }
         machine
     }
@@ -111,61 +97,270 @@ impl MoleculeMachine {
         unique_molecules.into_iter().collect()
     }
 
-    fn find_shortest_replacement_sequence(&self, start_molecule: &Molecule, end_molecule: &Molecule) -> Option<usize> {
-        fn estimate_distance(start_molecule: &Molecule, end_molecule: &Molecule) -> usize {
-            let mut distance = 0;
-            for (start_element_id, end_element_id) in
-                start_molecule.element_ids.iter().zip(end_molecule.element_ids.iter())
-            {
-                if start_element_id != end_element_id {
-                    distance += 1;
+    // Finds the fewest replacements needed to turn the given start element into the given end molecule, and returns
+    // the derivation that does so, or None if the end molecule can't be made from the start element or the given
+    // context is cancelled before a derivation is found.
+    //
+    // Rather than searching forward through the (enormous) space of intermediate molecules, the replacement rules
+    // are treated as a context-free grammar, and the end molecule is parsed with it (see ReplacementParser).
+    pub fn find_shortest_derivation(
+        &self,
+        start_element_id: usize,
+        end_molecule: &Molecule,
+        context: &crate::RunContext,
+    ) -> Option<DerivationNode> {
+        let rules: Vec<&ReplacementRule> = self.replacement_rules.values().flatten().collect();
+        let parser = ReplacementParser::new(&rules, self.chemistry.borrow().elements.len());
+        parser.parse(start_element_id, end_molecule, context)
+    }
+
+    // Returns the sequence of molecules produced by applying the replacements in the given derivation one at a time,
+    // always replacing the leftmost element that still needs to be replaced.
+    pub fn get_derivation_steps(&self, derivation: &DerivationNode) -> Vec<String> {
+        let chemistry = self.chemistry.borrow();
+        let mut steps = Vec::new();
+        let mut current_nodes = vec![derivation];
+        while let Some(i) = current_nodes.iter().position(|node| !node.children.is_empty()) {
+            let node = current_nodes[i];
+            current_nodes.splice(i..=i, node.children.iter());
+            steps.push(
+                current_nodes
+                    .iter()
+                    .map(|node| chemistry.elements[node.element_id].as_str())
+                    .collect(),
+            );
+        }
+        steps
+    }
+}
+
+// Represents a node in the derivation of a molecule. A node with children was replaced by them through a single
+// replacement rule, and a node without children is an element of the final molecule.
+#[derive(Debug)]
+struct DerivationNode {
+    element_id: usize,
+    children: Vec<DerivationNode>,
+}
+
+impl DerivationNode {
+    // Returns the number of replacements made in the derivation rooted at this node.
+    pub fn replacement_count(&self) -> usize {
+        if self.children.is_empty() {
+            0
+        } else {
+            1 + self
+                .children
+                .iter()
+                .map(DerivationNode::replacement_count)
+                .sum::<usize>()
+        }
+    }
+}
+
+// Describes how the cheapest derivation of a symbol over a span of the molecule was found.
+#[derive(Debug, Clone, Copy)]
+enum ChartEntrySource {
+    // The symbol is the element at the start of the span, which must be one element long.
+    Element,
+    // The symbol was replaced by the given child symbol, which covers the whole span.
+    Unary(usize),
+    // The symbol was replaced by the given pair of child symbols, the first of which covers the given number of
+    // elements at the start of the span, and the second of which covers the rest.
+    Binary(usize, usize, usize),
+}
+
+// A cell of the parse chart, which maps each symbol that can derive a span of the molecule to the fewest
+// replacements it takes and how that derivation was found.
+type ChartCell = HashMap<usize, (usize, ChartEntrySource)>;
+
+// Parses molecules using a set of replacement rules, finding the derivation of a molecule from a single element
+// that uses the fewest replacements.
+//
+// This uses the CYK algorithm, which requires rules that replace a symbol with exactly two others. Longer rules are
+// split into chains of two-symbol rules through intermediate symbols that don't represent elements. Only the first
+// rule in such a chain counts as a replacement, so the number of replacements is preserved. Rules that replace an
+// element with a single other element are handled separately, after the two-symbol rules have been applied to a span.
+struct ReplacementParser {
+    // Maps the first symbol of each two-symbol rule to the second symbol, the symbol being replaced and the number
+    // of replacements the rule counts as.
+    binary_rules: HashMap<usize, Vec<(usize, usize, usize)>>,
+    // Maps the replacement of each single-element rule to the element being replaced.
+    unary_rules: HashMap<usize, Vec<usize>>,
+    // The symbols with ids at or above this one are intermediate symbols, rather than elements.
+    first_intermediate_symbol: usize,
+}
+
+impl ReplacementParser {
+    // Creates a parser for the given rules over a chemistry with the given number of elements.
+    pub fn new(rules: &[&ReplacementRule], element_count: usize) -> ReplacementParser {
+        let mut parser = ReplacementParser {
+            binary_rules: HashMap::new(),
+            unary_rules: HashMap::new(),
+            first_intermediate_symbol: element_count,
+        };
+
+        let mut next_symbol = element_count;
+        for rule in rules {
+            match rule.to_element_ids.as_slice() {
+                [] => panic!("Replacement rules must produce at least one element"),
+                [element_id] => parser
+                    .unary_rules
+                    .entry(*element_id)
+                    .or_default()
+                    .push(rule.from_element_id),
+                [first_element_ids @ .., second_last_element_id, last_element_id] => {
+                    // A rule A => X1 X2 ... Xn becomes A => X1 N1, N1 => X2 N2, ..., Nn-2 => Xn-1 Xn.
+                    let mut parent_symbol = rule.from_element_id;
+                    let mut cost = 1;
+                    for element_id in first_element_ids {
+                        let intermediate_symbol = next_symbol;
+                        next_symbol += 1;
+                        parser.add_binary_rule(parent_symbol, *element_id, intermediate_symbol, cost);
+                        parent_symbol = intermediate_symbol;
+                        cost = 0;
+                    }
+                    parser.add_binary_rule(parent_symbol, *second_last_element_id, *last_element_id, cost);
                 }
             }
-            distance += end_molecule.element_ids.len() - start_molecule.element_ids.len();
-            distance
         }
 
-        let mut search_queue: PriorityQueue<Molecule, usize> = PriorityQueue::new();
-        let mut best_distances = HashMap::new();
+        parser
+    }
 
-        let estimated_distance_to_goal = estimate_distance(start_molecule, end_molecule);
-        search_queue.push(start_molecule.clone(), usize::MAX - estimated_distance_to_goal);
-        best_distances.insert(start_molecule.clone(), 0);
+    // Adds a rule that replaces the given parent symbol with the given pair of symbols.
+    fn add_binary_rule(&mut self, parent_symbol: usize, first_symbol: usize, second_symbol: usize, cost: usize) {
+        self.binary_rules
+            .entry(first_symbol)
+            .or_default()
+            .push((second_symbol, parent_symbol, cost));
+    }
 
-        let mut i = 0;
-        while let Some((current_molecule, score)) = search_queue.pop() {
-            let current_distance = *best_distances.get(&current_molecule).unwrap();
-            if current_molecule == *end_molecule {
-                return Some(current_distance);
+    // Adds the given symbol to the given cell if it isn't there already or if this derivation is cheaper, and
+    // returns whether the cell changed.
+    fn update_cell(cell: &mut ChartCell, symbol: usize, cost: usize, source: ChartEntrySource) -> bool {
+        match cell.get(&symbol) {
+            Some((existing_cost, _)) if *existing_cost <= cost => false,
+            _ => {
+                cell.insert(symbol, (cost, source));
+                true
             }
+        }
+    }
 
-            if i % 1000 == 0 {
-                println!(
-                    "Iteration: {}, queue size: {}, score: {}",
-                    i,
-                    search_queue.len(),
-                    usize::MAX - score
-                );
-                println!("Current molecule: {current_molecule:#?}");
+    // Applies the single-element rules to the given cell until no cheaper derivations are found.
+    fn apply_unary_rules(&self, cell: &mut ChartCell) {
+        let mut is_changed = !self.unary_rules.is_empty();
+        while is_changed {
+            is_changed = false;
+            let entries: Vec<(usize, usize)> = cell.iter().map(|(symbol, (cost, _))| (*symbol, *cost)).collect();
+            for (symbol, cost) in entries {
+                for parent_symbol in self.unary_rules.get(&symbol).into_iter().flatten() {
+                    is_changed |= Self::update_cell(cell, *parent_symbol, cost + 1, ChartEntrySource::Unary(symbol));
+                }
             }
+        }
+    }
 
-            let next_distance = current_distance + 1;
-            for next_molecule in self.generate_all_single_replacements(&current_molecule) {
-                if next_molecule.element_ids.len() > end_molecule.element_ids.len() {
-                    continue;
-                }
+    // Finds the derivation of the given molecule from the given start element that uses the fewest replacements.
+    pub fn parse(
+        &self,
+        start_element_id: usize,
+        molecule: &Molecule,
+        context: &crate::RunContext,
+    ) -> Option<DerivationNode> {
+        let element_ids = &molecule.element_ids;
+        let element_count = element_ids.len();
+        if element_count == 0 {
+            return None;
+        }
+
+        // chart[start][length - 1] holds the symbols that can derive the span of the given length at the given start.
+        let mut chart: Vec<Vec<ChartCell>> = (0..element_count)
+            .map(|start| vec![ChartCell::new(); element_count - start])
+            .collect();
+        for (start, element_id) in element_ids.iter().enumerate() {
+            let cell = &mut chart[start][0];
+            cell.insert(*element_id, (0, ChartEntrySource::Element));
+            self.apply_unary_rules(cell);
+        }
 
-                let current_best_distance = best_distances.get(&next_molecule).copied().unwrap_or(usize::MAX);
-                if next_distance < current_best_distance {
-                    best_distances.insert(next_molecule.clone(), next_distance);
-                    let estimated_distance_to_goal = estimate_distance(&next_molecule, end_molecule);
-                    search_queue.push(next_molecule, usize::MAX - estimated_distance_to_goal);
+        for length in 2..=element_count {
+            if context.is_cancelled() {
+                return None;
+            }
+            context.report_progress(length as u64, Some(element_count as u64));
+
+            for start in 0..=element_count - length {
+                let mut cell = ChartCell::new();
+                for first_length in 1..length {
+                    let first_cell = &chart[start][first_length - 1];
+                    let second_cell = &chart[start + first_length][length - first_length - 1];
+                    if first_cell.is_empty() || second_cell.is_empty() {
+                        continue;
+                    }
+
+                    for (first_symbol, (first_cost, _)) in first_cell {
+                        for (second_symbol, parent_symbol, rule_cost) in
+                            self.binary_rules.get(first_symbol).into_iter().flatten()
+                        {
+                            if let Some((second_cost, _)) = second_cell.get(second_symbol) {
+                                Self::update_cell(
+                                    &mut cell,
+                                    *parent_symbol,
+                                    first_cost + second_cost + rule_cost,
+                                    ChartEntrySource::Binary(first_length, *first_symbol, *second_symbol),
+                                );
+                            }
+                        }
+                    }
                 }
+                self.apply_unary_rules(&mut cell);
+                chart[start][length - 1] = cell;
             }
-            i += 1;
         }
 
-        None
+        chart[0][element_count - 1]
+            .contains_key(&start_element_id)
+            .then(|| self.build_derivation(&chart, start_element_id, 0, element_count))
+    }
+
+    // Builds the derivation of the given symbol over the given span from the given parse chart. Intermediate symbols
+    // are folded into their parents, so that each node in the derivation represents one replacement.
+    fn build_derivation(&self, chart: &[Vec<ChartCell>], symbol: usize, start: usize, length: usize) -> DerivationNode {
+        let mut node = DerivationNode {
+            element_id: symbol,
+            children: Vec::new(),
+        };
+        self.collect_derivation_children(chart, symbol, start, length, &mut node.children);
+        node
+    }
+
+    // Adds the children of the given symbol's derivation over the given span to the given list.
+    fn collect_derivation_children(
+        &self,
+        chart: &[Vec<ChartCell>],
+        symbol: usize,
+        start: usize,
+        length: usize,
+        children: &mut Vec<DerivationNode>,
+    ) {
+        let (_, source) = chart[start][length - 1][&symbol];
+        match source {
+            ChartEntrySource::Element => {}
+            ChartEntrySource::Unary(child_symbol) => {
+                children.push(self.build_derivation(chart, child_symbol, start, length));
+            }
+            ChartEntrySource::Binary(first_length, first_symbol, second_symbol) => {
+                children.push(self.build_derivation(chart, first_symbol, start, first_length));
+                let second_start = start + first_length;
+                let second_length = length - first_length;
+                if second_symbol >= self.first_intermediate_symbol {
+                    self.collect_derivation_children(chart, second_symbol, second_start, second_length, children);
+                } else {
+                    children.push(self.build_derivation(chart, second_symbol, second_start, second_length));
+                }
+            }
+        }
     }
 }
 
@@ -218,7 +413,7 @@ fn parse_input(input: &str, chemistry: &mut Chemistry) -> (Vec<ReplacementRule>,
     (replacement_rules, calibration_molecule)
 }
 
-fn solve(input: &str, log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, log_fn: Option<fn(&str)>, context: &crate::RunContext) -> (String, String) {
     let chemistry = Rc::new(RefCell::new(Chemistry::new()));
     let (replacement_rules, medicine_molecule) = parse_input(input, &mut chemistry.borrow_mut());
 
@@ -240,13 +435,64 @@ fn solve(input: &str, log_fn: Option<fn(&str)>) -> (String, String) {
 
     // Part 2: What is the fewest number of steps to go from the calibration input molecule to the
     // medicine molecule?
-    let seed_molecule = Molecule::new(vec![chemistry.borrow_mut().ensure_element("e")]);
-    let part2_result = molecule_machine
-        .find_shortest_replacement_sequence(&seed_molecule, &medicine_molecule)
-        .unwrap();
+    let seed_element_id = chemistry.borrow_mut().ensure_element("e");
+    // The search gives up early if the run is cancelled, in which case there is no result to report. Otherwise, no
+    // derivation means the medicine molecule really can't be made.
+    let Some(derivation) = molecule_machine.find_shortest_derivation(seed_element_id, &medicine_molecule, context)
+    else {
+        if let Some(log_fn) = log_fn {
+            if !context.is_cancelled() {
+                log_fn("The medicine molecule can't be made from the seed element");
+            }
+        }
+        return (part1_result.to_string(), String::from("-"));
+    };
+    let part2_result = derivation.replacement_count();
+
+    if let Some(log_fn) = log_fn {
+        log_fn("Medicine derivation:");
+        for (i, step) in molecule_machine.get_derivation_steps(&derivation).iter().enumerate() {
+            log_fn(&format!("{:>4}: {step}", i + 1));
+        }
+    }
 
     (part1_result.to_string(), part2_result.to_string())
 }
 
 #[linkme::distributed_slice(crate::SOLUTIONS)]
 static SOLUTION: crate::Solution = crate::Solution::new(2015, 19, solve);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run_context::CancellationToken;
+
+    // The example replacements and medicine molecule from the puzzle description.
+    const EXAMPLE_INPUT: &str = "e => H
+e => O
+H => HO
+H => OH
+O => HH
+
+HOHOHO";
+
+    #[test]
+    fn solve_finds_shortest_derivation() {
+        let context = crate::RunContext::new(CancellationToken::new());
+        assert_eq!(
+            solve(EXAMPLE_INPUT, None, &context),
+            (String::from("7"), String::from("6"))
+        );
+    }
+
+    #[test]
+    fn solve_gives_no_derivation_when_cancelled() {
+        let cancellation_token = CancellationToken::new();
+        cancellation_token.cancel();
+        let context = crate::RunContext::new(cancellation_token);
+        assert_eq!(
+            solve(EXAMPLE_INPUT, None, &context),
+            (String::from("7"), String::from("-"))
+        );
+    }
+}
@@ -24,7 +24,7 @@ impl PresentCountGenerator<'_> {
         present_multipler: u64,
         house_limit: Option<u64>,
         factor_cache: &mut HashMap<u64, HashSet<u64>>,
-    ) -> PresentCountGenerator {
+    ) -> PresentCountGenerator<'_> {
         PresentCountGenerator {
             house_number: 0,
             present_multiplier: present_multipler,
@@ -89,25 +89,63 @@ impl Iterator for PresentCountGenerator<'_> {
     }
 }
 
-fn solve(input: &str, _log_fn: Option<fn(&str)>) -> (String, String) {
+// Returns the number of the first house to receive at least the given number of presents from the given generator,
+// or None if the given context is cancelled before such a house is found. The given house limit is the number of a
+// house known to receive enough presents, and it is used to report progress to the context.
+fn find_first_house_with_presents(
+    present_count_generator: PresentCountGenerator,
+    minimum_presents: u64,
+    house_limit: u64,
+    context: &crate::RunContext,
+) -> Option<u64> {
+    // How many houses are checked between each check for cancellation and progress report.
+    const HOUSES_PER_PROGRESS_REPORT: u64 = 10000;
+
+    for (house_number, present_count) in (1..).zip(present_count_generator) {
+        if present_count >= minimum_presents {
+            return Some(house_number);
+        }
+
+        if house_number % HOUSES_PER_PROGRESS_REPORT == 0 {
+            if context.is_cancelled() {
+                return None;
+            }
+            context.report_progress(house_number, Some(house_limit));
+        }
+    }
+
+    None
+}
+
+fn solve(input: &str, _log_fn: Option<fn(&str)>, context: &crate::RunContext) -> (String, String) {
     let minimum_presents = input.parse::<u64>().unwrap();
 
     let mut factor_cache = HashMap::new();
 
     // Part 1: Find the first house to receive at least the given number of presents.
-    let mut present_count_generator = PresentCountGenerator::new(10, None, &mut factor_cache);
-    let minimum_house_index = present_count_generator
-        .position(|present_count| present_count >= minimum_presents)
-        .unwrap() as u64;
-    let part1_result = minimum_house_index + 1;
+    // Since each house receives at least ten times its own number of presents from its own elf, the house whose
+    // number is a tenth of the minimum number of presents is the furthest the search can go.
+    let present_count_generator = PresentCountGenerator::new(10, None, &mut factor_cache);
+    let part1_result = find_first_house_with_presents(
+        present_count_generator,
+        minimum_presents,
+        minimum_presents / 10,
+        context,
+    )
+    .unwrap_or_default();
 
     // Part 2: Find the first house to receive at least the given number of presents, where each
     // elf only delivers presents to 50 houses.
-    let mut present_count_generator = PresentCountGenerator::new(11, Some(50), &mut factor_cache);
-    let minimum_house_index = present_count_generator
-        .position(|present_count| present_count >= minimum_presents)
-        .unwrap() as u64;
-    let part2_result = minimum_house_index + 1;
+    // By the same reasoning as above, the house whose number is an eleventh of the minimum number of presents is
+    // the furthest the search can go.
+    let present_count_generator = PresentCountGenerator::new(11, Some(50), &mut factor_cache);
+    let part2_result = find_first_house_with_presents(
+        present_count_generator,
+        minimum_presents,
+        minimum_presents / 11,
+        context,
+    )
+    .unwrap_or_default();
 
     (part1_result.to_string(), part2_result.to_string())
 }
@@ -111,6 +111,7 @@ impl Loadout {
 }
 
 // An iterator that generates all possible loadouts of items that can be equipped by the player.
+#[allow(clippy::struct_field_names)]
 struct LoadoutGenerator {
     weapon_index: usize,
     armor_index: usize,
@@ -184,23 +185,30 @@ impl Iterator for LoadoutGenerator {
     }
 }
 
-// Defines the properties of a combatant.
+// Defines the properties of a combatant. Shared with the 2015 day 22 solution, where combatants also start with mana.
 #[derive(Debug)]
-struct CombatantDescription {
-    damage: u32,
-    armor: u32,
-    starting_hit_points: u32,
+pub(crate) struct CombatantDescription {
+    pub(crate) damage: u32,
+    pub(crate) armor: u32,
+    pub(crate) starting_hit_points: u32,
+    pub(crate) starting_mana: u32,
 }
 
 impl CombatantDescription {
-    // Creates a new instance with the given properties.
+    // Creates a new instance with the given properties and no mana.
     pub fn new(damage: u32, armor: u32, starting_hit_points: u32) -> CombatantDescription {
         CombatantDescription {
             damage,
             armor,
             starting_hit_points,
+            starting_mana: 0,
         }
     }
+
+    // Returns the description with its starting mana set to the given value.
+    pub fn with_starting_mana(self, starting_mana: u32) -> CombatantDescription {
+        CombatantDescription { starting_mana, ..self }
+    }
 }
 
 // Defines the possible combatant types.
@@ -289,7 +297,7 @@ fn simulate_combat(
 }
 
 // Returns the first integer value successfully parsed from the given string.
-fn get_integer_from_line(input: &str) -> Result<u32, ()> {
+pub(crate) fn get_integer_from_line(input: &str) -> Result<u32, ()> {
     for word in input.split_whitespace() {
         if let Ok(value) = word.parse::<u32>() {
             return Ok(value);
@@ -307,7 +315,7 @@ fn load_boss_description(input: &str) -> CombatantDescription {
     CombatantDescription::new(boss_damage, boss_armor, boss_hit_points)
 }
 
-fn solve(input: &str, log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
     let boss_description = load_boss_description(input);
 
     // Generate all possible loadouts of items that can be equipped by the player.
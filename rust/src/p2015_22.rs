@@ -0,0 +1,269 @@
+// Advent of Code 2015, Day 22: "Wizard Simulator 20XX"
+// https://adventofcode.com/2015/day/22
+
+use crate::p2015_21::{get_integer_from_line, CombatantDescription};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+// Identifies a spell that can be cast by the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Spell {
+    MagicMissile,
+    Drain,
+    Shield,
+    Poison,
+    Recharge,
+}
+
+impl Spell {
+    // The set of spells that can be cast by the player.
+    const ALL: [Spell; 5] = [
+        Spell::MagicMissile,
+        Spell::Drain,
+        Spell::Shield,
+        Spell::Poison,
+        Spell::Recharge,
+    ];
+
+    // Returns the amount of mana it costs to cast this spell.
+    pub fn cost(self) -> u32 {
+        match self {
+            Spell::MagicMissile => 53,
+            Spell::Drain => 73,
+            Spell::Shield => 113,
+            Spell::Poison => 173,
+            Spell::Recharge => 229,
+        }
+    }
+}
+
+// Defines the difficulty levels that a combat can be simulated at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Difficulty {
+    Normal,
+    // The player loses one hit point at the start of each of their turns.
+    Hard,
+}
+
+// Represents the state of a combat at the start of one of the player's turns.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct CombatState {
+    player_hit_points: u32,
+    player_mana: u32,
+    boss_hit_points: u32,
+    shield_turns_remaining: u32,
+    poison_turns_remaining: u32,
+    recharge_turns_remaining: u32,
+}
+
+// Identifies the possible outcomes of a single round of combat.
+#[derive(Debug)]
+enum RoundOutcome {
+    PlayerWins,
+    BossWins,
+    Continue(CombatState),
+}
+
+impl CombatState {
+    // Creates the state at the start of a combat between the given combatants.
+    pub fn new(player_description: &CombatantDescription, boss_description: &CombatantDescription) -> CombatState {
+        CombatState {
+            player_hit_points: player_description.starting_hit_points,
+            player_mana: player_description.starting_mana,
+            boss_hit_points: boss_description.starting_hit_points,
+            shield_turns_remaining: 0,
+            poison_turns_remaining: 0,
+            recharge_turns_remaining: 0,
+        }
+    }
+
+    // Applies any active effects at the start of a turn, and returns the player's armor for the turn.
+    fn apply_effects(&mut self) -> u32 {
+        let mut player_armor = 0;
+        if self.shield_turns_remaining > 0 {
+            player_armor = 7;
+            self.shield_turns_remaining -= 1;
+        }
+        if self.poison_turns_remaining > 0 {
+            self.boss_hit_points = self.boss_hit_points.saturating_sub(3);
+            self.poison_turns_remaining -= 1;
+        }
+        if self.recharge_turns_remaining > 0 {
+            self.player_mana += 101;
+            self.recharge_turns_remaining -= 1;
+        }
+        player_armor
+    }
+
+    // Starts the player's turn by applying hard mode's hit point loss and any active effects, and returns the
+    // outcome. The player hasn't cast a spell yet if the combat continues.
+    pub fn begin_player_turn(&self, difficulty: Difficulty) -> RoundOutcome {
+        let mut state = self.clone();
+        if difficulty == Difficulty::Hard {
+            state.player_hit_points -= 1;
+            if state.player_hit_points == 0 {
+                return RoundOutcome::BossWins;
+            }
+        }
+
+        state.apply_effects();
+        if state.boss_hit_points == 0 {
+            return RoundOutcome::PlayerWins;
+        }
+
+        RoundOutcome::Continue(state)
+    }
+
+    // Finishes a player's turn that was started by begin_player_turn by casting the given spell, simulates the
+    // boss's turn, and returns the outcome. Returns None if the given spell can't be cast, either because the player
+    // can't afford it or because its effect is already active.
+    pub fn finish_round(&self, spell: Spell, boss_description: &CombatantDescription) -> Option<RoundOutcome> {
+        let mut state = self.clone();
+
+        // Cast the given spell, if possible. An effect can be started again on the same turn that it ends.
+        if spell.cost() > state.player_mana {
+            return None;
+        }
+        state.player_mana -= spell.cost();
+        match spell {
+            Spell::MagicMissile => state.boss_hit_points = state.boss_hit_points.saturating_sub(4),
+            Spell::Drain => {
+                state.boss_hit_points = state.boss_hit_points.saturating_sub(2);
+                state.player_hit_points += 2;
+            }
+            Spell::Shield if state.shield_turns_remaining == 0 => state.shield_turns_remaining = 6,
+            Spell::Poison if state.poison_turns_remaining == 0 => state.poison_turns_remaining = 6,
+            Spell::Recharge if state.recharge_turns_remaining == 0 => state.recharge_turns_remaining = 5,
+            Spell::Shield | Spell::Poison | Spell::Recharge => return None,
+        }
+        if state.boss_hit_points == 0 {
+            return Some(RoundOutcome::PlayerWins);
+        }
+
+        // Simulate the boss's turn.
+        let player_armor = state.apply_effects();
+        if state.boss_hit_points == 0 {
+            return Some(RoundOutcome::PlayerWins);
+        }
+
+        let boss_damage = std::cmp::max(boss_description.damage.saturating_sub(player_armor), 1);
+        state.player_hit_points = state.player_hit_points.saturating_sub(boss_damage);
+        if state.player_hit_points == 0 {
+            return Some(RoundOutcome::BossWins);
+        }
+
+        Some(RoundOutcome::Continue(state))
+    }
+}
+
+// Returns the final state of a combat that was won from the given state.
+fn get_won_state(state: &CombatState) -> CombatState {
+    CombatState {
+        boss_hit_points: 0,
+        ..state.clone()
+    }
+}
+
+// Finds the least amount of mana that the given player can spend and still win against the given boss at the given
+// difficulty. Returns the amount of mana spent along with the sequence of spells cast to win, or None if the player
+// can't win.
+fn find_minimum_mana_win(
+    player_description: &CombatantDescription,
+    boss_description: &CombatantDescription,
+    difficulty: Difficulty,
+) -> Option<(u32, Vec<Spell>)> {
+    // Search the space of combat states in order of the amount of mana spent to reach them (Dijkstra's algorithm),
+    // so that the first win found is the one that spent the least mana.
+    let mut search_queue = BinaryHeap::new();
+    let mut visited_states = HashSet::new();
+    search_queue.push(Reverse((
+        0,
+        CombatState::new(player_description, boss_description),
+        Vec::new(),
+    )));
+
+    while let Some(Reverse((mana_spent, state, spells_cast))) = search_queue.pop() {
+        // Since states are visited in order of mana spent, there is no need to consider a state again once it has
+        // been visited, as any later visit will have spent at least as much mana to reach it.
+        if !visited_states.insert(state.clone()) {
+            continue;
+        }
+
+        // A state where the boss has no hit points left was queued as a win, and since it has been reached in order,
+        // no other win can be cheaper.
+        if state.boss_hit_points == 0 {
+            return Some((mana_spent, spells_cast));
+        }
+
+        // Effects may defeat the boss at the start of the player's turn, before another spell needs to be cast. The
+        // win might not be the cheapest one yet, since there may be cheaper states still waiting in the queue, so a
+        // final state is queued up instead, which will be recognized once it is reached in order.
+        let turn_state = match state.begin_player_turn(difficulty) {
+            RoundOutcome::PlayerWins => {
+                search_queue.push(Reverse((mana_spent, get_won_state(&state), spells_cast)));
+                continue;
+            }
+            RoundOutcome::BossWins => continue,
+            RoundOutcome::Continue(turn_state) => turn_state,
+        };
+
+        for spell in Spell::ALL {
+            let next_mana_spent = mana_spent + spell.cost();
+            let mut next_spells_cast = spells_cast.clone();
+            next_spells_cast.push(spell);
+            match turn_state.finish_round(spell, boss_description) {
+                Some(RoundOutcome::PlayerWins) => {
+                    search_queue.push(Reverse((next_mana_spent, get_won_state(&state), next_spells_cast)));
+                }
+                Some(RoundOutcome::Continue(next_state)) => {
+                    search_queue.push(Reverse((next_mana_spent, next_state, next_spells_cast)));
+                }
+                Some(RoundOutcome::BossWins) | None => {}
+            }
+        }
+    }
+
+    None
+}
+
+// Parses the given input string into a CombatantDescription for the boss.
+fn load_boss_description(input: &str) -> CombatantDescription {
+    let mut line_iter = input.lines();
+    let boss_hit_points = get_integer_from_line(line_iter.next().unwrap()).unwrap();
+    let boss_damage = get_integer_from_line(line_iter.next().unwrap()).unwrap();
+    CombatantDescription::new(boss_damage, 0, boss_hit_points)
+}
+
+// Logs the given winning sequence of spells if a log function was provided.
+fn log_winning_spells(label: &str, mana_spent: u32, spells_cast: &[Spell], log_fn: Option<fn(&str)>) {
+    if let Some(log_fn) = log_fn {
+        log_fn(label);
+        log_fn("----------");
+        for (turn, spell) in spells_cast.iter().enumerate() {
+            log_fn(&format!("Turn {}: {spell:?} ({} mana)", turn + 1, spell.cost()));
+        }
+        log_fn(&format!("Total mana spent: {mana_spent}"));
+        log_fn("-----");
+    }
+}
+
+fn solve(input: &str, log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
+    let boss_description = load_boss_description(input);
+    let player_description = CombatantDescription::new(0, 0, 50).with_starting_mana(500);
+
+    // Part 1: Find the least amount of mana that can be spent to win the combat.
+    let (part1_result, part1_spells_cast) =
+        find_minimum_mana_win(&player_description, &boss_description, Difficulty::Normal).unwrap();
+    log_winning_spells("Part 1", part1_result, &part1_spells_cast, log_fn);
+
+    // Part 2: Find the least amount of mana that can be spent to win the combat when the player loses one hit point
+    // at the start of each of their turns.
+    let (part2_result, part2_spells_cast) =
+        find_minimum_mana_win(&player_description, &boss_description, Difficulty::Hard).unwrap();
+    log_winning_spells("Part 2", part2_result, &part2_spells_cast, log_fn);
+
+    (part1_result.to_string(), part2_result.to_string())
+}
+
+#[linkme::distributed_slice(crate::SOLUTIONS)]
+static SOLUTION: crate::Solution = crate::Solution::new(2015, 22, solve);
@@ -1,267 +1,236 @@
 // Advent of Code 2015, Day 23: "Opening the Turing Lock"
 // https://adventofcode.com/2015/day/23
 
-// Identifies a register in the emulated machine's CPU.
-#[derive(Debug, Clone, Copy)]
-enum RegisterId {
-    A = 0,
-    B = 1,
-}
-
-// Insruction set supported by the emulated CPU.
-#[derive(Debug, Clone, Copy)]
-enum Instruction {
-    //                       ___________________________________________________________
-    Hlf(RegisterId),        // |Halve       |hlf r        |r /= 2                        |
-    Tpl(RegisterId),        // |Triple      |tpl r        |r *= 3                        |
-    Inc(RegisterId),        // |Increment   |inc r        |r += 1                        |
-    Jmp(isize),             // |Jump        |jmp offset   |ip += offset                  |
-    Jie(RegisterId, isize), // |Jump if even|jie r, offset|if r % 2 == 0 { ip += offset }|
-    Jio(RegisterId, isize), // |Jump if one |jio r, offset|if r == 1 { ip += offset }    |
-                            // -----------------------------------------------------------
-}
+use crate::vm::{Condition, Effect, Flow, InstructionSet, Operand, Vm, Word};
 
-// Represents the emulated machine's CPU.
-struct Cpu {
-    registers: [usize; 2],
-    ip: usize,
+// The index of register a.
+const REGISTER_A: usize = 0;
 
-    is_halted: bool,
+// The index of register b.
+const REGISTER_B: usize = 1;
 
-    fetch: Box<dyn Fn(usize) -> Option<Instruction>>,
+// Insruction set supported by the emulated CPU. Registers are given by their indices.
+#[derive(Debug, Clone, Copy)]
+enum Instruction {
+    //                  ___________________________________________________________
+    Hlf(usize),       // |Halve       |hlf r        |r /= 2                        |
+    Tpl(usize),       // |Triple      |tpl r        |r *= 3                        |
+    Inc(usize),       // |Increment   |inc r        |r += 1                        |
+    Jmp(Word),        // |Jump        |jmp offset   |ip += offset                  |
+    Jie(usize, Word), // |Jump if even|jie r, offset|if r % 2 == 0 { ip += offset }|
+    Jio(usize, Word), // |Jump if one |jio r, offset|if r == 1 { ip += offset }    |
+                      // -----------------------------------------------------------
 }
 
-// Provides methods for creating and interacting with a Cpu instance.
-impl Cpu {
-    // Creates a new Cpu instance with the given fetch function.
-    // The fetch function provides the CPU with the ability to fetch an instruction
-    // from memory at the given instruction pointer.
-    fn new(fetch: Box<dyn Fn(usize) -> Option<Instruction>>) -> Self {
-        Self {
-            registers: [0; 2],
-            ip: 0,
-            is_halted: false,
-            fetch,
+impl std::fmt::Display for Instruction {
+    // Formats the instruction in the same syntax as the puzzle input, e.g. "jio a, +8".
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = TuringLock::REGISTER_NAMES;
+        match self {
+            Instruction::Hlf(register) => write!(f, "hlf {}", names[*register]),
+            Instruction::Tpl(register) => write!(f, "tpl {}", names[*register]),
+            Instruction::Inc(register) => write!(f, "inc {}", names[*register]),
+            Instruction::Jmp(offset) => write!(f, "jmp {offset:+}"),
+            Instruction::Jie(register, offset) => write!(f, "jie {}, {offset:+}", names[*register]),
+            Instruction::Jio(register, offset) => write!(f, "jio {}, {offset:+}", names[*register]),
         }
     }
+}
 
-    // Resets the CPU to its initial state.
-    // If the given arguments are Some, then the registers are initialized to the given values.
-    fn reset(&mut self, a: Option<usize>, b: Option<usize>) {
-        self.registers[0] = a.unwrap_or(0);
-        self.registers[1] = b.unwrap_or(0);
-        self.ip = 0;
-        self.is_halted = false;
-    }
+// The instruction set of the computer in the Turing lock, which has two registers.
+struct TuringLock;
+
+impl InstructionSet for TuringLock {
+    type Instruction = Instruction;
 
-    // Tells the CPU to execute the next instruction.
-    fn step(&mut self) {
-        let instruction = (self.fetch)(self.ip);
-        if let Some(instruction) = instruction {
-            self._run_instruction(&instruction);
-        } else {
-            self.is_halted = true;
+    const REGISTER_NAMES: &'static [&'static str] = &["a", "b"];
+
+    fn decode(opcode: &str, operands: &[Operand]) -> Option<Instruction> {
+        use Operand::{Immediate, Register};
+        match (opcode, operands) {
+            ("hlf", [Register(register)]) => Some(Instruction::Hlf(*register)),
+            ("tpl", [Register(register)]) => Some(Instruction::Tpl(*register)),
+            ("inc", [Register(register)]) => Some(Instruction::Inc(*register)),
+            ("jmp", [Immediate(offset)]) => Some(Instruction::Jmp(*offset)),
+            ("jie", [Register(register), Immediate(offset)]) => Some(Instruction::Jie(*register, *offset)),
+            ("jio", [Register(register), Immediate(offset)]) => Some(Instruction::Jio(*register, *offset)),
+            _ => None,
         }
     }
 
-    // Private helper method that executes the given instruction.
-    fn _run_instruction(&mut self, instruction: &Instruction) {
-        // By default, the instruction pointer is incremented by 1.
-        // Some instructions may result in a different next instruction pointer value.
-        let mut next_ip = self.ip + 1;
-
-        // Execute the given instruction.
-        match instruction {
-            Instruction::Hlf(register) => {
-                let register = self._get_register_data_mut(*register);
-                *register /= 2;
-            }
-            Instruction::Tpl(register) => {
-                let register = self._get_register_data_mut(*register);
-                *register *= 3;
-            }
-            Instruction::Inc(register) => {
-                let register = self._get_register_data_mut(*register);
-                *register += 1;
-            }
-            Instruction::Jmp(offset) => {
-                next_ip = (self.ip as isize + offset) as usize;
-            }
+    fn execute(instruction: &Instruction, registers: &mut [Word]) -> Flow {
+        match *instruction {
+            Instruction::Hlf(register) => registers[register] /= 2,
+            Instruction::Tpl(register) => registers[register] *= 3,
+            Instruction::Inc(register) => registers[register] += 1,
+            Instruction::Jmp(offset) => return Flow::Jump(offset),
             Instruction::Jie(register, offset) => {
-                let register = self._get_register_data_mut(*register);
-                if *register % 2 == 0 {
-                    next_ip = (self.ip as isize + offset) as usize;
+                if registers[register] % 2 == 0 {
+                    return Flow::Jump(offset);
                 }
             }
             Instruction::Jio(register, offset) => {
-                let register = self._get_register_data_mut(*register);
-                if *register == 1 {
-                    next_ip = (self.ip as isize + offset) as usize;
+                if registers[register] == 1 {
+                    return Flow::Jump(offset);
                 }
             }
         }
-
-        self.ip = next_ip;
-    }
-
-    // Private helper method that returns a mutable reference to the given register's data.
-    fn _get_register_data_mut(&mut self, register: RegisterId) -> &mut usize {
-        &mut self.registers[register as usize]
-    }
-}
-
-// Implement the Debug trait for the Cpu struct, so that its state can be printed
-// using the {:?} format specifier.
-impl std::fmt::Debug for Cpu {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Cpu")
-            .field("registers", &self.registers)
-            .field("ip", &self.ip)
-            .field("is_halted", &self.is_halted)
-            .finish_non_exhaustive()
-    }
-}
-
-// Represents the emulated machine.
-#[derive(Debug)]
-struct Machine {
-    cpu: Cpu,
-}
-
-// Provides methods for creating and interacting with a Machine instance.
-impl Machine {
-    // Creates a new Machine instance with the given instructions in its memory.
-    fn new(instructions: Vec<Instruction>) -> Self {
-        let fetch = Box::new(move |ip| instructions.get(ip).copied());
-        Self { cpu: Cpu::new(fetch) }
+        Flow::Next
     }
 
-    // Runs the machine until it halts.
-    fn run(&mut self) {
-        while !self.cpu.is_halted {
-            self.cpu.step();
+    fn get_jump_offset(instruction: &Instruction) -> Option<Word> {
+        match instruction {
+            Instruction::Jmp(offset) | Instruction::Jie(_, offset) | Instruction::Jio(_, offset) => Some(*offset),
+            Instruction::Hlf(_) | Instruction::Tpl(_) | Instruction::Inc(_) => None,
         }
     }
-}
-
-// Parses the given register name into a RegisterId.
-fn parse_register(register_name: &str) -> RegisterId {
-    match register_name {
-        "a" => RegisterId::A,
-        "b" => RegisterId::B,
-        _ => panic!("Invalid register name"),
-    }
-}
-
-// Parses the given offset string into an isize.
-fn parse_offset(offset: &str) -> isize {
-    let mut chars = offset.chars();
-    let sign = chars.next().unwrap();
-    let value = chars.collect::<String>().parse::<isize>().unwrap();
-    match sign {
-        '+' => value,
-        '-' => -value,
-        _ => panic!("Invalid offset sign"),
-    }
-}
-
-// Parses the given instruction string into an instruction of the form "<instruction_name> <register_name>".
-fn parse_register_instruction<'a>(
-    instruction_name: &'a str,
-    arguments: &mut impl Iterator<Item = &'a str>,
-) -> Instruction {
-    let register = parse_register(arguments.next().unwrap());
-
-    match instruction_name {
-        "hlf" => Instruction::Hlf(register),
-        "tpl" => Instruction::Tpl(register),
-        "inc" => Instruction::Inc(register),
-        _ => panic!("Invalid instruction name"),
-    }
-}
-
-// Parses the given instruction string of the form "<instruction_name> <offset>".
-fn parse_offset_instruction<'a>(
-    instruction_name: &'a str,
-    arguments: &mut impl Iterator<Item = &'a str>,
-) -> Instruction {
-    let offset = parse_offset(arguments.next().unwrap());
-
-    match instruction_name {
-        "jmp" => Instruction::Jmp(offset),
-        _ => panic!("Invalid instruction name"),
-    }
-}
-
-// Parses the given instruction string of the form "<instruction_name> <register_name>, <offset>".
-fn parse_register_offset_instruction<'a>(
-    instruction_name: &'a str,
-    arguments: &mut impl Iterator<Item = &'a str>,
-) -> Instruction {
-    let register = parse_register(arguments.next().unwrap());
-    let offset = parse_offset(arguments.next().unwrap());
 
-    match instruction_name {
-        "jie" => Instruction::Jie(register, offset),
-        "jio" => Instruction::Jio(register, offset),
-        _ => panic!("Invalid instruction name"),
+    fn get_effect(instruction: &Instruction) -> Option<Effect> {
+        match *instruction {
+            Instruction::Inc(register) => Some(Effect::Add(register, 1)),
+            Instruction::Jmp(_) => Some(Effect::Jump),
+            Instruction::Jie(register, _) => Some(Effect::Branch(register, Condition::IsEven)),
+            Instruction::Jio(register, _) => Some(Effect::Branch(register, Condition::IsEqualTo(1))),
+            Instruction::Hlf(register) | Instruction::Tpl(register) => Some(Effect::Modify(register)),
+        }
     }
 }
 
-// Parses the given instruction string into an Instruction.
-fn parse_instruction(input: &str) -> Instruction {
-    // Split the instruction string into the instruction name and its arguments.
-    let parts = input.split_once(' ').unwrap();
-    let instruction_name = parts.0;
-    let mut arguments = parts.1.split(',').map(str::trim);
-
-    // Determine the type of instruction and parse it accordingly.
-    match instruction_name {
-        "hlf" | "tpl" | "inc" => parse_register_instruction(instruction_name, &mut arguments),
-        "jmp" => parse_offset_instruction(instruction_name, &mut arguments),
-        "jie" | "jio" => parse_register_offset_instruction(instruction_name, &mut arguments),
-        _ => panic!("Invalid instruction name"),
-    }
-}
+// Runs the given machine until it halts, and returns the value of register b. Loops that the machine would never
+// leave are reported rather than run forever.
+fn run_program(machine: &mut Vm<TuringLock>, part_name: &str, log_fn: Option<fn(&str)>) -> Word {
+    let statistics = machine
+        .run_with_loop_analysis()
+        .unwrap_or_else(|infinite_loop| panic!("{part_name}: {infinite_loop}"));
 
-// Parses the given input string into a vector of Instructions.
-fn parse_instructions(input: &str) -> Vec<Instruction> {
-    input.lines().map(parse_instruction).collect()
+    // If logging is enabled, print the final state of the machine and how much work loop analysis saved.
+    if let Some(log_fn) = log_fn {
+        log_fn(&format!("{part_name}: {machine:#?}"));
+        log_fn(&format!(
+            "{part_name}: executed {} instructions, and skipped {} by fast-forwarding loops {} times and jumping to \
+             the end of an earlier run {} times",
+            statistics.executed_instructions,
+            statistics.skipped_instructions,
+            statistics.fast_forwards,
+            statistics.remembered_skips
+        ));
+    }
+
+    machine.registers()[REGISTER_B]
 }
 
-fn solve(input: &str, log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
     // Parse the input into a vector of Instructions.
-    let instructions = parse_instructions(input);
+    let instructions = crate::vm::parse_program::<TuringLock>(input);
 
     // If logging is enabled, print the parsed instructions.
     if let Some(log_fn) = log_fn {
         for (i, instruction) in instructions.iter().enumerate() {
-            log_fn(&format!("{i}: {instruction:#?}"));
+            log_fn(&format!("{i}: {instruction}"));
         }
     }
 
     // Part 1: Run the program with register A initialized to 0.
-    let mut machine = Machine::new(instructions);
-    machine.run();
-    let part1_result = machine.cpu.registers[1];
-
-    // If logging is enabled, print the final state of the machine.
-    if let Some(log_fn) = log_fn {
-        log_fn(&format!("Part 1: {machine:#?}"));
-    }
+    let mut machine = Vm::<TuringLock>::new(instructions);
+    let part1_result = run_program(&mut machine, "Part 1", log_fn);
 
     // Part 2: Run the program with register A initialized to 1.
-    machine.cpu.reset(Some(1), None);
-    machine.run();
-    let part2_result = machine.cpu.registers[1];
-
-    // If logging is enabled, print the final state of the machine.
-    if let Some(log_fn) = log_fn {
-        log_fn(&format!("Part 2: {machine:#?}"));
-    }
+    let mut initial_register_values = [0; 2];
+    initial_register_values[REGISTER_A] = 1;
+    machine.reset(&initial_register_values);
+    let part2_result = run_program(&mut machine, "Part 2", log_fn);
 
     (part1_result.to_string(), part2_result.to_string())
 }
 
 #[linkme::distributed_slice(crate::SOLUTIONS)]
-static SOLUTION: crate::Solution = crate::Solution::new(2015, 23, solve);
+static SOLUTION: crate::Solution =
+    crate::Solution::new(2015, 23, solve).with_debugger(crate::vm::debugger::debug::<TuringLock>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run_context::CancellationToken;
+    use crate::vm::debugger::Debugger;
+
+    // Creates a machine running the given program, with register a set to the given value.
+    fn create_machine(program: &str, value_of_a: Word) -> Vm<TuringLock> {
+        let mut machine = Vm::new(crate::vm::parse_program::<TuringLock>(program));
+        machine.reset(&[value_of_a]);
+        machine
+    }
+
+    #[test]
+    fn loop_analysis_fast_forwards_counting_loop() {
+        // Each iteration of the loop adds one to a until it is one, and two identical iterations are needed before
+        // the rest are skipped.
+        let mut machine = create_machine("jio a, +3\ninc a\njmp -2", -1000);
+        let statistics = machine.run_with_loop_analysis().unwrap();
+        assert_eq!(machine.registers(), [1, 0]);
+        assert_eq!(machine.cycle_count(), 1001 * 3 + 1);
+        assert_eq!(statistics.executed_instructions, 7);
+        assert_eq!(statistics.skipped_instructions, 999 * 3);
+        assert_eq!(statistics.fast_forwards, 1);
+    }
+
+    #[test]
+    fn loop_analysis_reports_infinite_loops() {
+        // A loop that repeats the same iteration forever is found from its iterations.
+        let infinite_loop = create_machine("inc b\njmp -1", 0).run_with_loop_analysis().unwrap_err();
+        assert_eq!((infinite_loop.first_ip, infinite_loop.last_ip), (0, 1));
+
+        // A loop whose iterations alternate between two paths is found from the machine returning to the same state.
+        let program = "jie a, +3\ninc a\njmp -2\nhlf a\njmp -4";
+        let infinite_loop = create_machine(program, 2).run_with_loop_analysis().unwrap_err();
+        assert_eq!((infinite_loop.first_ip, infinite_loop.last_ip), (0, 4));
+        assert_eq!(
+            infinite_loop.to_string(),
+            "The program loops forever over instructions 0..=4 (detected after 9 instructions)"
+        );
+    }
+
+    #[test]
+    fn loop_analysis_skips_to_end_of_earlier_run() {
+        // The program counts the steps of the Collatz sequence from a in b, like the puzzle's program does.
+        const COLLATZ_PROGRAM: &str = "jio a, +8\ninc b\njie a, +4\ntpl a\ninc a\njmp +2\nhlf a\njmp -7";
+        let mut machine = create_machine(COLLATZ_PROGRAM, 3);
+        machine.run_with_loop_analysis().unwrap();
+        assert_eq!(machine.registers(), [1, 7]);
+
+        // The sequence from 6 goes on to 3 and then 10, which the sequence from 3 has already passed through.
+        machine.reset(&[6]);
+        let statistics = machine.run_with_loop_analysis().unwrap();
+        let mut expected_machine = create_machine(COLLATZ_PROGRAM, 6);
+        expected_machine.run_with_loop_analysis().unwrap();
+        assert_eq!(machine.registers(), [1, 8]);
+        assert_eq!(machine.registers(), expected_machine.registers());
+        assert_eq!(machine.cycle_count(), expected_machine.cycle_count());
+        assert_eq!(statistics.remembered_skips, 1);
+        assert_eq!(
+            statistics.executed_instructions + statistics.skipped_instructions,
+            machine.cycle_count()
+        );
+    }
+
+    #[test]
+    fn debugger_continue_stops_when_interrupted() {
+        // The program loops forever, so continuing only stops when it is interrupted.
+        let program = crate::vm::parse_program::<TuringLock>("inc a\njmp -1");
+        let interrupt_token = CancellationToken::new();
+        let mut debugger = Debugger::<TuringLock>::new(program, interrupt_token.clone());
+
+        let interrupter = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            interrupt_token.cancel();
+        });
+        let mut output = Vec::new();
+        assert!(debugger.execute_command("continue", &mut output).unwrap());
+        interrupter.join().unwrap();
+        assert!(String::from_utf8(output).unwrap().starts_with("Interrupted\n"));
+
+        // The interrupt has been dealt with, so it doesn't stop the next command.
+        let mut output = Vec::new();
+        assert!(debugger.execute_command("step 2", &mut output).unwrap());
+        assert!(!String::from_utf8(output).unwrap().contains("Interrupted"));
+    }
+}
@@ -0,0 +1,174 @@
+// Advent of Code 2015, Day 24: "It Hangs in the Balance"
+// https://adventofcode.com/2015/day/24
+
+use std::ops::ControlFlow;
+
+// Calls the given visitor with the indices of each subset of the given weights that sums to the given target, until
+// the visitor breaks. If a size is given, only subsets with that many weights are visited. The weights must be
+// sorted in descending order, which allows most dead ends to be skipped without being explored. Returns whether the
+// visitor broke.
+fn for_each_subset_with_sum<F>(weights: &[u64], target: u64, size: Option<usize>, visitor: &mut F) -> bool
+where
+    F: FnMut(&[usize]) -> ControlFlow<()>,
+{
+    // Extends the current subset with weights from the given start index onward.
+    fn visit_recursive<F>(
+        weights: &[u64],
+        start: usize,
+        remaining_target: u64,
+        remaining_size: Option<usize>,
+        subset: &mut Vec<usize>,
+        visitor: &mut F,
+    ) -> ControlFlow<()>
+    where
+        F: FnMut(&[usize]) -> ControlFlow<()>,
+    {
+        if remaining_target == 0 && remaining_size.unwrap_or(0) == 0 {
+            return visitor(subset);
+        }
+        if remaining_size == Some(0) {
+            return ControlFlow::Continue(());
+        }
+
+        for i in start..weights.len() {
+            // Since the weights are sorted in descending order, if the current weight together with as many copies
+            // of it as are still needed can't reach the target, then no later weight can either.
+            if let Some(remaining_size) = remaining_size {
+                if u128::from(weights[i]) * (remaining_size as u128) < u128::from(remaining_target) {
+                    break;
+                }
+            }
+            if weights[i] > remaining_target {
+                continue;
+            }
+
+            subset.push(i);
+            let result = visit_recursive(
+                weights,
+                i + 1,
+                remaining_target - weights[i],
+                remaining_size.map(|s| s - 1),
+                subset,
+                visitor,
+            );
+            subset.pop();
+            result?;
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    visit_recursive(weights, 0, target, size, &mut Vec::new(), visitor).is_break()
+}
+
+// Returns the given weights, excluding the weights at the given indices.
+fn get_remaining_weights(weights: &[u64], excluded_indices: &[usize]) -> Vec<u64> {
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !excluded_indices.contains(i))
+        .map(|(_, w)| *w)
+        .collect()
+}
+
+// Returns whether the given weights, sorted in descending order, can be split into the given number of groups that
+// each weigh the given target. The total of the weights must be the group count times the target.
+fn can_split_into_groups(weights: &[u64], group_count: usize, target: u64) -> bool {
+    if group_count <= 1 {
+        return true;
+    }
+
+    // The heaviest weight has to be in some group, so only groups containing it need to be tried. This avoids
+    // trying the same split several times with its groups in a different order.
+    let (heaviest_weight, other_weights) = weights.split_first().unwrap();
+    if *heaviest_weight > target {
+        return false;
+    }
+
+    for_each_subset_with_sum(other_weights, target - heaviest_weight, None, &mut |subset| {
+        let remaining_weights = get_remaining_weights(other_weights, subset);
+        if can_split_into_groups(&remaining_weights, group_count - 1, target) {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })
+}
+
+// Returns the quantum entanglement of the given group of weights, which is the product of the weights.
+fn get_quantum_entanglement(group: &[u64]) -> u128 {
+    group
+        .iter()
+        .try_fold(1u128, |product, weight| product.checked_mul(u128::from(*weight)))
+        .expect("The quantum entanglement of the group is too large to represent")
+}
+
+// Finds the ideal first group of packages when the given package weights are split into the given number of
+// equally heavy groups. The ideal group has as few packages as possible, and has the lowest quantum entanglement
+// among those groups. Returns the ideal group along with its quantum entanglement, or None if the packages can't be
+// split evenly.
+fn find_ideal_first_group(package_weights: &[u64], group_count: usize) -> Option<(Vec<u64>, u128)> {
+    let total_weight: u64 = package_weights.iter().sum();
+    if group_count == 0 || !total_weight.is_multiple_of(group_count as u64) {
+        return None;
+    }
+    let target = total_weight / group_count as u64;
+
+    let mut sorted_weights = package_weights.to_vec();
+    sorted_weights.sort_unstable_by(|a, b| b.cmp(a));
+
+    // Try group sizes from the smallest upward. For each size, consider the candidate groups in order of quantum
+    // entanglement, and pick the first one whose remaining packages can actually be split into the other groups.
+    for group_size in 1..=sorted_weights.len() {
+        let mut candidate_groups = Vec::new();
+        for_each_subset_with_sum(&sorted_weights, target, Some(group_size), &mut |subset| {
+            let group: Vec<u64> = subset.iter().map(|i| sorted_weights[*i]).collect();
+            candidate_groups.push((get_quantum_entanglement(&group), subset.to_vec(), group));
+            ControlFlow::Continue(())
+        });
+        candidate_groups.sort_unstable();
+
+        for (quantum_entanglement, subset, group) in candidate_groups {
+            let remaining_weights = get_remaining_weights(&sorted_weights, &subset);
+            if can_split_into_groups(&remaining_weights, group_count - 1, target) {
+                return Some((group, quantum_entanglement));
+            }
+        }
+    }
+
+    None
+}
+
+// Parses the package weights from the given input string.
+fn parse_package_weights(input: &str) -> Vec<u64> {
+    input.lines().map(|line| line.trim().parse::<u64>().unwrap()).collect()
+}
+
+// Finds the quantum entanglement of the ideal first group for the given number of groups, logging the group if
+// a log function was provided.
+fn solve_for_group_count(package_weights: &[u64], group_count: usize, log_fn: Option<fn(&str)>) -> u128 {
+    let (group, quantum_entanglement) = find_ideal_first_group(package_weights, group_count)
+        .unwrap_or_else(|| panic!("The packages can't be split into {group_count} equally heavy groups"));
+    if let Some(log_fn) = log_fn {
+        log_fn(&format!(
+            "{group_count} groups: first group {group:?} ({} packages, quantum entanglement {quantum_entanglement})",
+            group.len()
+        ));
+    }
+    quantum_entanglement
+}
+
+fn solve(input: &str, log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
+    let package_weights = parse_package_weights(input);
+
+    // Part 1: Find the quantum entanglement of the ideal first group when the packages are split into three groups.
+    let part1_result = solve_for_group_count(&package_weights, 3, log_fn);
+
+    // Part 2: Find the quantum entanglement of the ideal first group when the packages are split into four groups.
+    let part2_result = solve_for_group_count(&package_weights, 4, log_fn);
+
+    (part1_result.to_string(), part2_result.to_string())
+}
+
+#[linkme::distributed_slice(crate::SOLUTIONS)]
+static SOLUTION: crate::Solution = crate::Solution::new(2015, 24, solve);
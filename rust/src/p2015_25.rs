@@ -45,7 +45,7 @@ fn get_code((row, col): (i32, i32)) -> i64 {
     get_code_sequence_iterator().nth(index as usize - 1).unwrap()
 }
 
-fn solve(input: &str, log_fn: Option<fn(&str)>) -> (String, String) {
+fn solve(input: &str, log_fn: Option<fn(&str)>, _context: &crate::RunContext) -> (String, String) {
     // Part 1: Find the code at the given coordinate in the table of codes.
     let input_coordinate = parse_coordinate(input);
     let code = get_code(input_coordinate);
@@ -0,0 +1,164 @@
+// Provides the context that a solution is run in, which allows long-running solutions to report their progress
+// and to notice when they have been asked to stop, and which provides the thread pool that data-parallel solutions
+// should run on.
+
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// A shared flag that can be used to ask a running solution to stop. Clones of a token all refer to the same
+// flag, so a token can be handed to whatever might need to cancel the run (e.g. a Ctrl-C handler or a timeout).
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    is_cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    // Creates a new token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Asks anything observing this token to stop.
+    pub fn cancel(&self) {
+        self.is_cancelled.store(true, Ordering::Relaxed);
+    }
+
+    // Returns whether this token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.is_cancelled.load(Ordering::Relaxed)
+    }
+
+    // Clears any earlier cancellation, so that the token can be used to stop something else.
+    pub fn reset(&self) {
+        self.is_cancelled.store(false, Ordering::Relaxed);
+    }
+}
+
+// A callback that receives the number of items processed so far and, if known, the total number of items.
+pub type ProgressFn = Box<dyn Fn(u64, Option<u64>) + Send + Sync>;
+
+// The context a solution is run in.
+//
+// Solutions that may run for a long time should periodically call report_progress and check is_cancelled.
+// A cancelled solution should return as soon as it can; its results are discarded by the caller, so it may
+// return whatever is convenient.
+//
+// Solutions that do work in parallel should do so within a call to install, so that the number of threads they
+// use can be controlled by whoever is running them.
+pub struct RunContext {
+    cancellation_token: CancellationToken,
+    progress_fn: Option<ProgressFn>,
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
+}
+
+impl RunContext {
+    // Creates a new context that can be cancelled through the given token and that ignores progress reports.
+    pub fn new(cancellation_token: CancellationToken) -> Self {
+        Self {
+            cancellation_token,
+            progress_fn: None,
+            thread_pool: None,
+        }
+    }
+
+    // Returns this context with the given callback receiving progress reports.
+    pub fn with_progress_fn(mut self, progress_fn: ProgressFn) -> Self {
+        self.progress_fn = Some(progress_fn);
+        self
+    }
+
+    // Returns this context with the given thread pool used for parallel work. If no thread pool is given, then
+    // rayon's global thread pool is used.
+    pub fn with_thread_pool(mut self, thread_pool: Option<Arc<rayon::ThreadPool>>) -> Self {
+        self.thread_pool = thread_pool;
+        self
+    }
+
+    // Runs the given operation with this context's thread pool as the pool that any rayon parallel iterators,
+    // joins or scopes within it use, and returns its result.
+    pub fn install<R, F>(&self, op: F) -> R
+    where
+        R: Send,
+        F: FnOnce() -> R + Send,
+    {
+        match &self.thread_pool {
+            Some(thread_pool) => thread_pool.install(op),
+            None => op(),
+        }
+    }
+
+    // Returns whether the solution has been asked to stop.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation_token.is_cancelled()
+    }
+
+    // Reports that the given number of items have been processed so far, out of the given total if it is known.
+    pub fn report_progress(&self, processed: u64, total: Option<u64>) {
+        if let Some(progress_fn) = &self.progress_fn {
+            progress_fn(processed, total);
+        }
+    }
+}
+
+// Renders progress reports as a single, continually updated line on stderr.
+pub struct ProgressLine {
+    label: String,
+    last_render_time: Mutex<Option<Instant>>,
+}
+
+impl ProgressLine {
+    // How often the progress line is redrawn. Reports in between redraws are dropped, so that solutions can
+    // report progress as often as is convenient for them without slowing down.
+    const RENDER_INTERVAL: Duration = Duration::from_millis(100);
+
+    // Creates a new progress line that prefixes each report with the given label.
+    pub fn new(label: String) -> Arc<Self> {
+        Arc::new(Self {
+            label,
+            last_render_time: Mutex::new(None),
+        })
+    }
+
+    // Returns a progress callback that renders reports to this line. Nothing is rendered unless stderr is a
+    // terminal, since the line is redrawn in place.
+    pub fn progress_fn(self: &Arc<Self>) -> ProgressFn {
+        let progress_line = Arc::clone(self);
+        Box::new(move |processed, total| {
+            if std::io::stderr().is_terminal() {
+                progress_line.render(processed, total);
+            }
+        })
+    }
+
+    // Draws the given progress report, unless the line was drawn too recently.
+    fn render(&self, processed: u64, total: Option<u64>) {
+        let mut last_render_time = self.last_render_time.lock().unwrap();
+        if last_render_time.is_some_and(|t| t.elapsed() < Self::RENDER_INTERVAL) {
+            return;
+        }
+        *last_render_time = Some(Instant::now());
+
+        let label = &self.label;
+        let mut stderr = std::io::stderr().lock();
+        let _ = match total {
+            Some(total) if total > 0 => write!(
+                stderr,
+                "\r\x1b[2K{label}: {processed}/{total} ({}%)",
+                u128::from(processed) * 100 / u128::from(total)
+            ),
+            _ => write!(stderr, "\r\x1b[2K{label}: {processed} processed"),
+        };
+        let _ = stderr.flush();
+    }
+
+    // Erases the line, if anything was drawn on it.
+    pub fn clear(&self) {
+        if self.last_render_time.lock().unwrap().take().is_some() {
+            let mut stderr = std::io::stderr().lock();
+            let _ = write!(stderr, "\r\x1b[2K");
+            let _ = stderr.flush();
+        }
+    }
+}
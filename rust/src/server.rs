@@ -0,0 +1,469 @@
+// A small HTTP/1.1 server that allows the registered solutions to be run without executing this program directly.
+//
+// The following endpoints are supported:
+//     GET  /solutions           Lists the year and day of every registered solution.
+//     POST /solve/<year>/<day>  Runs the solution for the given year and day using the request body as the
+//                               problem input, and returns the results along with how long they took to compute.
+//
+// All responses have a JSON body, and connections are closed after each response. Only a limited number of
+// connections are handled at once, and further clients wait to be accepted until one of them is closed.
+//
+// A solution that runs for too long is asked to stop and its request fails, but solutions that never check for
+// cancellation can't be stopped, and keep running in the background until they finish. Only a limited number of
+// solutions may be running at once, so once that many are stuck like this, every further solve request is rejected
+// until the server is restarted.
+
+use serde_json::json;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+// How long a client may take to send its whole request, or to receive each part of its response, before the
+// connection is dropped.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+// The maximum number of connections that may be handled at once. Each one is handled on its own thread, so this
+// limits how many threads clients can make the server start.
+const MAX_OPEN_CONNECTION_COUNT: usize = 32;
+
+// The number of connections that are currently being handled, and the condition that is signalled when one of them
+// is closed.
+static OPEN_CONNECTION_COUNT: Mutex<usize> = Mutex::new(0);
+static CONNECTION_CLOSED: Condvar = Condvar::new();
+
+// How long a solution may run before its request is abandoned with an error response.
+const SOLVE_TIMEOUT: Duration = Duration::from_mins(1);
+
+// The maximum number of solutions that may be running at once, including ones whose requests timed out but that are
+// still running because they don't check for cancellation. Requests beyond this are rejected rather than started, so
+// that repeated requests with inputs that a solution can't finish can't exhaust the server's threads.
+const MAX_RUNNING_SOLUTION_COUNT: usize = 8;
+
+// The number of solutions that are currently running.
+static RUNNING_SOLUTION_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+// The maximum size of the request line and headers together.
+const MAX_HEADER_LENGTH: u64 = 64 * 1024;
+
+// The maximum size of a request body, which is large enough for any Advent of Code problem input.
+const MAX_BODY_LENGTH: usize = 16 * 1024 * 1024;
+
+// Represents the parts of an HTTP request that the server cares about.
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+// Represents an HTTP response with a JSON body.
+struct Response {
+    status: u16,
+    body: serde_json::Value,
+}
+
+impl Response {
+    // Creates a new successful response with the given JSON body.
+    fn ok(body: serde_json::Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    // Creates a new error response with the given status code and a JSON body describing the error.
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message }),
+        }
+    }
+
+    // Returns the standard reason phrase for this response's status code.
+    fn reason_phrase(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            411 => "Length Required",
+            413 => "Payload Too Large",
+            500 => "Internal Server Error",
+            503 => "Service Unavailable",
+            504 => "Gateway Timeout",
+            _ => "Unknown",
+        }
+    }
+}
+
+// Represents a place among the connections that are being handled, which is given up when this is dropped.
+struct ConnectionSlot;
+
+impl ConnectionSlot {
+    // Waits until fewer than the maximum number of connections are being handled, and then takes a place among them.
+    fn acquire() -> ConnectionSlot {
+        let open_connection_count = OPEN_CONNECTION_COUNT
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let mut open_connection_count = CONNECTION_CLOSED
+            .wait_while(open_connection_count, |count| *count >= MAX_OPEN_CONNECTION_COUNT)
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        *open_connection_count += 1;
+        ConnectionSlot
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        *OPEN_CONNECTION_COUNT
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) -= 1;
+        CONNECTION_CLOSED.notify_one();
+    }
+}
+
+// Reads from a connection until a deadline, however the client spreads out the data it sends, so that a client that
+// sends its request slowly can't hold on to the connection's thread indefinitely.
+struct DeadlineReader<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining_time = self.deadline.saturating_duration_since(Instant::now());
+        if remaining_time.is_zero() {
+            return Err(std::io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(remaining_time))?;
+        self.stream.read(buf)
+    }
+}
+
+// Listens for HTTP requests on the given local port and serves them until the process is terminated.
+// Each connection is handled on its own thread, and each solution is run on its own thread, so a slow or
+// failing request does not prevent other requests from being served. Once the maximum number of connections are
+// being handled, no more are accepted until one of them is closed. Any parallel work done by the solutions is done
+// on the given thread pool, or on rayon's global thread pool if none is given.
+pub fn serve(port: u16, thread_pool: Option<&Arc<rayon::ThreadPool>>) {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .unwrap_or_else(|e| panic!("Unable to listen for connections on port {port}: {e}"));
+    println!("Serving solutions at http://127.0.0.1:{port}/");
+
+    loop {
+        let slot = ConnectionSlot::acquire();
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let thread_pool = thread_pool.cloned();
+                let spawn_result = std::thread::Builder::new().spawn(move || {
+                    handle_connection(stream, thread_pool);
+                    drop(slot);
+                });
+                if let Err(e) = spawn_result {
+                    eprintln!("Unable to start handling connection: {e}");
+                }
+            }
+            Err(e) => eprintln!("Unable to accept connection: {e}"),
+        }
+    }
+}
+
+// Reads a single request from the given connection, and writes the response to it.
+fn handle_connection(mut stream: TcpStream, thread_pool: Option<Arc<rayon::ThreadPool>>) {
+    // If the timeout can't be set, then a misbehaving client could hold on to this thread indefinitely,
+    // so refuse to handle the connection at all.
+    if stream.set_write_timeout(Some(CONNECTION_TIMEOUT)).is_err() {
+        return;
+    }
+
+    let response = match read_request(&stream, Instant::now() + CONNECTION_TIMEOUT) {
+        Ok(request) => {
+            let response = route_request(&request, thread_pool);
+            println!("{} {} -> {}", request.method, request.path, response.status);
+            response
+        }
+        Err(response) => response,
+    };
+
+    if let Err(e) = write_response(&mut stream, &response) {
+        eprintln!("Unable to send response: {e}");
+    }
+}
+
+// Reads and parses an HTTP request from the given stream, which must have been sent by the given deadline. If the
+// request can't be read, then an error response describing the problem is returned instead.
+fn read_request(stream: &TcpStream, deadline: Instant) -> Result<Request, Response> {
+    // Converts an I/O error encountered while reading the request into an error response.
+    fn read_error(e: &std::io::Error) -> Response {
+        match e.kind() {
+            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => {
+                Response::error(408, "Timed out while reading the request.")
+            }
+            _ => Response::error(400, &format!("Unable to read the request: {e}")),
+        }
+    }
+
+    // Read the request line and headers, limiting how much can be read so that a client can't make the
+    // server buffer an unbounded amount of data.
+    let mut reader = BufReader::new(DeadlineReader { stream, deadline }.take(MAX_HEADER_LENGTH));
+
+    // The request line is of the form:
+    //     <method> <path> <version>
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).map_err(|e| read_error(&e))?;
+    let mut request_line_parts = request_line.split_whitespace();
+    let (Some(method), Some(target), Some(_)) = (
+        request_line_parts.next(),
+        request_line_parts.next(),
+        request_line_parts.next(),
+    ) else {
+        return Err(Response::error(400, "Malformed request line."));
+    };
+
+    // Ignore any query string, since no endpoint uses one.
+    let path = target.split('?').next().unwrap_or_default().to_owned();
+    let method = method.to_owned();
+
+    // Read the headers, keeping track of the only one that matters: the length of the body.
+    let mut content_length = None;
+    loop {
+        let mut header_line = String::new();
+        if reader.read_line(&mut header_line).map_err(|e| read_error(&e))? == 0 {
+            return Err(Response::error(400, "The request headers were incomplete or too long."));
+        }
+
+        let header_line = header_line.trim_end();
+        if header_line.is_empty() {
+            break;
+        }
+
+        let Some((name, value)) = header_line.split_once(':') else {
+            return Err(Response::error(400, "Malformed request header."));
+        };
+
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = Some(
+                value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| Response::error(400, "Invalid Content-Length header."))?,
+            );
+        } else if name.eq_ignore_ascii_case("transfer-encoding") {
+            return Err(Response::error(411, "Chunked request bodies are not supported."));
+        }
+    }
+
+    // Read the body, if there is one.
+    let mut body = Vec::new();
+    if let Some(content_length) = content_length {
+        if content_length > MAX_BODY_LENGTH {
+            return Err(Response::error(413, "The request body is too large."));
+        }
+
+        // Any part of the body that was already buffered while reading the headers is still in the
+        // reader, so lift the header length limit and read the rest of the body through it.
+        reader.get_mut().set_limit(content_length as u64);
+        body.resize(content_length, 0);
+        reader.read_exact(&mut body).map_err(|e| read_error(&e))?;
+    } else if method == "POST" {
+        return Err(Response::error(411, "A Content-Length header is required."));
+    }
+
+    Ok(Request { method, path, body })
+}
+
+// Writes the given response to the given stream.
+fn write_response(stream: &mut TcpStream, response: &Response) -> std::io::Result<()> {
+    let body = response.body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.reason_phrase(),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+// Dispatches the given request to the handler for the endpoint it targets.
+fn route_request(request: &Request, thread_pool: Option<Arc<rayon::ThreadPool>>) -> Response {
+    let segments = request.path.trim_matches('/').split('/').collect::<Vec<_>>();
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["solutions"]) => list_solutions(),
+        ("POST", ["solve", year, day]) => match (year.parse::<i32>(), day.parse::<i32>()) {
+            (Ok(year), Ok(day)) => run_solution(year, day, &request.body, thread_pool),
+            _ => Response::error(400, "The year and day must be integers."),
+        },
+        (_, ["solutions" | "solve", ..]) => Response::error(405, "Method not allowed for this endpoint."),
+        _ => Response::error(404, "Unknown endpoint."),
+    }
+}
+
+// Returns a response listing the year and day of every registered solution, in order.
+fn list_solutions() -> Response {
+    let mut solutions = crate::SOLUTIONS.iter().map(|s| (s.year, s.day)).collect::<Vec<_>>();
+    solutions.sort_unstable();
+    Response::ok(
+        solutions
+            .into_iter()
+            .map(|(year, day)| json!({ "year": year, "day": day }))
+            .collect(),
+    )
+}
+
+// Runs the solution for the given year and day on the given problem input and returns a response containing
+// its results. The solution is run on a separate thread so that a panic or a runaway solution only fails
+// this request rather than taking down the server.
+fn run_solution(year: i32, day: i32, body: &[u8], thread_pool: Option<Arc<rayon::ThreadPool>>) -> Response {
+    let Some(solution) = crate::find_solution(year, day) else {
+        return Response::error(404, &format!("There is no solution for year {year}, day {day}."));
+    };
+
+    let Ok(problem_input) = std::str::from_utf8(body) else {
+        return Response::error(400, "The problem input must be valid UTF-8.");
+    };
+    let problem_input = problem_input.trim().to_owned();
+
+    // Reserve a place for the solution among the ones that are running, giving it up again if there isn't one.
+    if RUNNING_SOLUTION_COUNT.fetch_add(1, Ordering::SeqCst) >= MAX_RUNNING_SOLUTION_COUNT {
+        RUNNING_SOLUTION_COUNT.fetch_sub(1, Ordering::SeqCst);
+        return Response::error(503, "Too many solutions are already running.");
+    }
+
+    // Run the solution, sending back either its results or the message it panicked with.
+    let cancellation_token = crate::CancellationToken::new();
+    let context = crate::RunContext::new(cancellation_token.clone()).with_thread_pool(thread_pool);
+    let (result_sender, result_receiver) = mpsc::channel();
+    let spawn_result = std::thread::Builder::new()
+        .name(format!("solve {year}/{day}"))
+        .spawn(move || {
+            // The context is discarded along with the rest of the run if the solution panics, so it can't be
+            // observed in an inconsistent state.
+            let result = std::panic::catch_unwind(AssertUnwindSafe(|| solution.run(&problem_input, None, &context)))
+                .map_err(|payload| crate::get_panic_message(payload.as_ref()));
+            RUNNING_SOLUTION_COUNT.fetch_sub(1, Ordering::SeqCst);
+
+            // The receiver may have stopped waiting if the solution took too long, in which case there is
+            // nobody left to tell about the result.
+            let _ = result_sender.send(result);
+        });
+    if let Err(e) = spawn_result {
+        RUNNING_SOLUTION_COUNT.fetch_sub(1, Ordering::SeqCst);
+        return Response::error(500, &format!("Unable to start the solution: {e}"));
+    }
+
+    // Wait for the solution to finish. If it takes too long, then ask it to stop and respond with an error.
+    // Not every solution checks for cancellation, so the solution's thread is left to finish in the background
+    // and its result is discarded. Such a thread keeps its place among the running solutions until it finishes, and
+    // a solution that never finishes keeps it for as long as the server runs.
+    let result = result_receiver.recv_timeout(SOLVE_TIMEOUT);
+    if result.is_err() {
+        cancellation_token.cancel();
+    }
+
+    match result {
+        Ok(Ok(result)) => Response::ok(json!({
+            "year": year,
+            "day": day,
+            "part1": result.part1_result,
+            "part2": result.part2_result,
+            "duration_seconds": result.duration.as_secs_f64(),
+        })),
+        Ok(Err(panic_message)) => Response::error(500, &format!("The solution panicked: {panic_message}")),
+        Err(mpsc::RecvTimeoutError::Timeout) => Response::error(
+            504,
+            &format!(
+                "The solution did not finish within {} seconds.",
+                SOLVE_TIMEOUT.as_secs()
+            ),
+        ),
+        Err(mpsc::RecvTimeoutError::Disconnected) => Response::error(500, "The solution stopped unexpectedly."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sends the given request to handle_connection over a loopback connection and returns the response's status line
+    // and body.
+    fn send_request(request: &str) -> (String, String) {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        client.write_all(request.as_bytes()).unwrap();
+        handle_connection(stream, None);
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (head.lines().next().unwrap().to_owned(), body.to_owned())
+    }
+
+    #[test]
+    fn handles_requests() {
+        let (status_line, body) = send_request("GET /solutions?all HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert_eq!(status_line, "HTTP/1.1 200 OK");
+        let solutions: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert!(solutions
+            .as_array()
+            .unwrap()
+            .contains(&json!({ "year": 2015, "day": 1 })));
+
+        let (status_line, body) = send_request("POST /solve/2015/1 HTTP/1.1\r\ncontent-length: 4\r\n\r\n())\n");
+        assert_eq!(status_line, "HTTP/1.1 200 OK");
+        let results: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!((&results["part1"], &results["part2"]), (&json!("-1"), &json!("3")));
+    }
+
+    #[test]
+    fn rejects_invalid_requests() {
+        for (request, expected_status_line) in [
+            ("GARBAGE\r\n\r\n", "HTTP/1.1 400 Bad Request"),
+            (
+                "GET /solutions HTTP/1.1\r\nNo colon\r\n\r\n",
+                "HTTP/1.1 400 Bad Request",
+            ),
+            ("GET /nothing HTTP/1.1\r\n\r\n", "HTTP/1.1 404 Not Found"),
+            ("DELETE /solutions HTTP/1.1\r\n\r\n", "HTTP/1.1 405 Method Not Allowed"),
+            ("POST /solve/2015/1 HTTP/1.1\r\n\r\n", "HTTP/1.1 411 Length Required"),
+            (
+                "POST /solve/2015/1 HTTP/1.1\r\nContent-Length: 1000000000\r\n\r\n",
+                "HTTP/1.1 413 Payload Too Large",
+            ),
+            (
+                "POST /solve/2015/x HTTP/1.1\r\nContent-Length: 0\r\n\r\n",
+                "HTTP/1.1 400 Bad Request",
+            ),
+        ] {
+            assert_eq!(send_request(request).0, expected_status_line, "{request}");
+        }
+    }
+
+    #[test]
+    fn read_request_times_out_when_request_is_sent_slowly() {
+        // The client sends a byte of its request at a time, more often than the deadline is away, so that only a
+        // deadline for the whole request stops it.
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let client_thread = std::thread::spawn(move || {
+            for byte in b"GET /solutions HTTP/1.1\r\nHost: localhost\r\n\r\n" {
+                if client.write_all(&[*byte]).is_err() {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(20));
+            }
+        });
+
+        let start_time = Instant::now();
+        let Err(response) = read_request(&stream, start_time + Duration::from_millis(200)) else {
+            panic!("The request should not have been read in time");
+        };
+        assert_eq!(response.status, 408);
+        assert!(start_time.elapsed() < Duration::from_millis(500));
+        drop(stream);
+        client_thread.join().unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+// Finds optimal tours through weighted graphs, which several puzzles need in one form or another: the best route
+// through a set of cities, or the best way to seat guests around a table. Graphs may be directed, tours may be open
+// paths or closed cycles, and they may be required to start at a given vertex.
+
+// The largest number of vertices that tours can be found for. The tour finder keeps a table entry for every subset
+// of the vertices, so its memory use doubles with each additional vertex.
+const MAX_VERTEX_COUNT: usize = 20;
+
+// Marks an entry in the tour finder's table for which there is no tour.
+const UNREACHABLE: i64 = i64::MIN;
+
+// Represents a directed graph with weighted edges. Some pairs of vertices may have no edge between them, in which
+// case a tour can't go directly from one to the other.
+pub struct Graph {
+    vertex_count: usize,
+    weights: Vec<Option<i64>>,
+}
+
+impl Graph {
+    // Creates a graph with the given number of vertices and no edges.
+    pub fn new(vertex_count: usize) -> Self {
+        Self {
+            vertex_count,
+            weights: vec![None; vertex_count * vertex_count],
+        }
+    }
+
+    // Returns the number of vertices in the graph.
+    pub fn vertex_count(&self) -> usize {
+        self.vertex_count
+    }
+
+    // Sets the weight of the edge from the first given vertex to the second.
+    pub fn set_weight(&mut self, from_id: usize, to_id: usize, weight: i64) {
+        self.weights[from_id * self.vertex_count + to_id] = Some(weight);
+    }
+
+    // Returns the weight of the edge from the first given vertex to the second, or None if there is no such edge.
+    pub fn get_weight(&self, from_id: usize, to_id: usize) -> Option<i64> {
+        self.weights[from_id * self.vertex_count + to_id]
+    }
+}
+
+// Describes what makes one tour better than another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    Minimize,
+    Maximize,
+}
+
+impl Objective {
+    // Returns whether a tour with the given weight is better than one with the other given weight.
+    fn is_better(self, weight: i64, other_weight: i64) -> bool {
+        match self {
+            Objective::Minimize => weight < other_weight,
+            Objective::Maximize => weight > other_weight,
+        }
+    }
+}
+
+// Describes the kind of tour to find.
+#[derive(Debug, Clone, Copy)]
+pub struct TourOptions {
+    pub objective: Objective,
+    // Whether the tour returns to its first vertex at the end, which adds the weight of that final edge.
+    pub is_closed: bool,
+    // The vertex that the tour must start at, if any. Closed tours can be rotated to start anywhere, so they start
+    // at the first vertex if no start is given.
+    pub start_id: Option<usize>,
+}
+
+// Represents a tour that visits the given vertices in order. For a closed tour, the final edge back to the first
+// vertex is included in the weight, but the first vertex isn't repeated at the end.
+#[derive(Debug, Clone)]
+pub struct Tour {
+    pub vertex_ids: Vec<usize>,
+    pub weight: i64,
+}
+
+// Finds the best tour of the given kind that visits every vertex in the given graph exactly once, or returns None if
+// no such tour exists because some vertices aren't connected.
+//
+// This uses the Held-Karp dynamic programming algorithm. The table holds the weight of the best path through each
+// subset of the vertices, given as a bitmask, that starts at an allowed start vertex and ends at each vertex in the
+// subset. Paths through a subset are built by extending the best paths through smaller subsets, so each subset and
+// end vertex is only considered once.
+pub fn find_best_tour(graph: &Graph, options: &TourOptions) -> Option<Tour> {
+    let vertex_count = graph.vertex_count();
+    assert!(
+        vertex_count <= MAX_VERTEX_COUNT,
+        "Tours can only be found through up to {MAX_VERTEX_COUNT} vertices, but there are {vertex_count}"
+    );
+    if vertex_count == 0 {
+        return None;
+    }
+    let start_id = options.start_id.or(options.is_closed.then_some(0));
+    if let Some(start_id) = start_id {
+        assert!(
+            start_id < vertex_count,
+            "The start vertex {start_id} isn't in the graph"
+        );
+    }
+
+    // Each path through a single allowed start vertex has a weight of zero.
+    let full_mask = (1usize << vertex_count) - 1;
+    let mut table = vec![UNREACHABLE; (full_mask + 1) * vertex_count];
+    for vertex_id in (0..vertex_count).filter(|id| start_id.is_none_or(|start_id| start_id == *id)) {
+        table[(1 << vertex_id) * vertex_count + vertex_id] = 0;
+    }
+
+    // Masks are visited in increasing order, so every subset is finished before the larger subsets built from it.
+    for mask in 1..=full_mask {
+        for last_id in (0..vertex_count).filter(|id| mask & (1 << id) != 0) {
+            let weight = table[mask * vertex_count + last_id];
+            if weight == UNREACHABLE {
+                continue;
+            }
+            for next_id in (0..vertex_count).filter(|id| mask & (1 << id) == 0) {
+                let Some(edge_weight) = graph.get_weight(last_id, next_id) else {
+                    continue;
+                };
+                let next_weight = weight + edge_weight;
+                let next_entry = &mut table[(mask | (1 << next_id)) * vertex_count + next_id];
+                if *next_entry == UNREACHABLE || options.objective.is_better(next_weight, *next_entry) {
+                    *next_entry = next_weight;
+                }
+            }
+        }
+    }
+
+    // Find the best tour through all the vertices, adding the edge back to the start for a closed tour.
+    let (mut last_id, weight) = (0..vertex_count)
+        .filter_map(|id| {
+            let weight = table[full_mask * vertex_count + id];
+            if weight == UNREACHABLE {
+                return None;
+            }
+            match (options.is_closed, start_id) {
+                (true, Some(start_id)) if vertex_count > 1 => graph
+                    .get_weight(id, start_id)
+                    .map(|edge_weight| (id, weight + edge_weight)),
+                _ => Some((id, weight)),
+            }
+        })
+        .reduce(|best, candidate| {
+            if options.objective.is_better(candidate.1, best.1) {
+                candidate
+            } else {
+                best
+            }
+        })?;
+
+    // Walk backwards through the table to find the vertices that the tour visits. At each step, the previous vertex
+    // is one whose best path, extended to the current vertex, gives the current vertex's entry.
+    let mut vertex_ids = vec![last_id];
+    let mut mask = full_mask;
+    while mask.count_ones() > 1 {
+        let current_weight = table[mask * vertex_count + last_id];
+        mask &= !(1 << last_id);
+        last_id = (0..vertex_count)
+            .filter(|id| mask & (1 << id) != 0)
+            .find(|id| {
+                let previous_weight = table[mask * vertex_count + id];
+                previous_weight != UNREACHABLE
+                    && graph
+                        .get_weight(*id, last_id)
+                        .is_some_and(|edge_weight| previous_weight + edge_weight == current_weight)
+            })
+            .expect("Every entry in the tour table is built from an entry for a smaller set of vertices");
+        vertex_ids.push(last_id);
+    }
+    vertex_ids.reverse();
+
+    Some(Tour { vertex_ids, weight })
+}
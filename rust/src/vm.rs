@@ -0,0 +1,226 @@
+// Provides a small virtual machine for the register-machine puzzles, which all run a program of simple instructions
+// over a handful of integer registers. Each puzzle defines its own instruction set, and shares the machine, the
+// program parser and the interactive debugger.
+
+pub mod debugger;
+mod loops;
+
+// The type of the values held in the machine's registers and used as immediate operands.
+pub type Word = i64;
+
+// Represents an operand of an instruction in a program's text: either the name of a register, resolved to its
+// index, or an immediate value such as "5" or "+19".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Register(usize),
+    Immediate(Word),
+}
+
+// Describes what the machine should do after executing an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    // Continue with the instruction that follows.
+    Next,
+    // Jump by the given offset from the current instruction.
+    Jump(Word),
+}
+
+// Describes a condition on a register's value that a conditional jump tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    IsEven,
+    IsEqualTo(Word),
+}
+
+impl Condition {
+    // Returns whether the given value meets this condition.
+    fn is_met_by(self, value: Word) -> bool {
+        match self {
+            Condition::IsEven => value % 2 == 0,
+            Condition::IsEqualTo(target) => value == target,
+        }
+    }
+}
+
+// Describes the effect of an instruction in terms that loop analysis can reason about, so that loops made only of
+// such instructions can be run in closed form, and so that registers that only count things can be told apart from
+// the ones that decide what the program does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    // Adds the given amount to the given register, whatever its value.
+    Add(usize, Word),
+    // Jumps if the given register meets the given condition, without changing any registers.
+    Branch(usize, Condition),
+    // Jumps unconditionally, without changing any registers.
+    Jump,
+    // Changes the given register by an amount that depends on its value, e.g. by halving it.
+    Modify(usize),
+}
+
+// Defines the registers and instructions of a particular machine.
+pub trait InstructionSet {
+    // The type of a decoded instruction. Displaying an instruction should give it in the syntax of the program's
+    // text.
+    type Instruction: Copy + std::fmt::Debug + std::fmt::Display;
+
+    // The names of the machine's registers, in order of their indices. This also sets how many registers the
+    // machine has.
+    const REGISTER_NAMES: &'static [&'static str];
+
+    // Decodes the instruction with the given opcode and operands, or returns None if they don't form a valid
+    // instruction.
+    fn decode(opcode: &str, operands: &[Operand]) -> Option<Self::Instruction>;
+
+    // Executes the given instruction on the given registers, and returns where execution should continue.
+    fn execute(instruction: &Self::Instruction, registers: &mut [Word]) -> Flow;
+
+    // Returns the offset that the given instruction may jump by, if it is a jump instruction. This is only used to
+    // show the targets of jumps in disassembly listings.
+    fn get_jump_offset(instruction: &Self::Instruction) -> Option<Word>;
+
+    // Returns the effect of the given instruction, if it can be described as an Effect. Loops containing instructions
+    // without a describable effect, or that modify registers, are never run in closed form.
+    fn get_effect(instruction: &Self::Instruction) -> Option<Effect>;
+
+    // Returns the index of the register with the given name, if there is one.
+    fn get_register_index(name: &str) -> Option<usize> {
+        Self::REGISTER_NAMES
+            .iter()
+            .position(|register_name| *register_name == name)
+    }
+}
+
+// Parses the given operand text, resolving register names for the given instruction set.
+fn parse_operand<S: InstructionSet>(text: &str) -> Option<Operand> {
+    match S::get_register_index(text) {
+        Some(index) => Some(Operand::Register(index)),
+        None => text.parse::<Word>().ok().map(Operand::Immediate),
+    }
+}
+
+// Parses a program for the given instruction set from the given text, which has one instruction per line of the
+// form "<opcode> <operand>, <operand>, ...".
+pub fn parse_program<S: InstructionSet>(input: &str) -> Vec<S::Instruction> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let (opcode, operands) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let operands: Option<Vec<Operand>> = operands
+                .split(',')
+                .map(str::trim)
+                .filter(|operand| !operand.is_empty())
+                .map(parse_operand::<S>)
+                .collect();
+            operands
+                .and_then(|operands| S::decode(opcode, &operands))
+                .unwrap_or_else(|| panic!("Invalid instruction on line {}: \"{line}\"", i + 1))
+        })
+        .collect()
+}
+
+// Represents a machine running a program from the given instruction set.
+pub struct Vm<S: InstructionSet> {
+    program: Vec<S::Instruction>,
+    registers: Vec<Word>,
+    ip: usize,
+
+    is_halted: bool,
+
+    // The number of instructions executed since the machine was last reset.
+    cycle_count: u64,
+
+    // The outcomes of earlier runs with loop analysis, which later runs that reach the same states can skip to. They
+    // only depend on the program, so they are kept when the machine is reset.
+    halting_outcomes: loops::HaltingOutcomes,
+}
+
+impl<S: InstructionSet> Vm<S> {
+    // Creates a new machine with the given program in its memory and all registers set to zero.
+    pub fn new(program: Vec<S::Instruction>) -> Self {
+        Self {
+            program,
+            registers: vec![0; S::REGISTER_NAMES.len()],
+            ip: 0,
+            is_halted: false,
+            cycle_count: 0,
+            halting_outcomes: loops::HaltingOutcomes::default(),
+        }
+    }
+
+    // Resets the machine to its initial state. The registers are set to the given values, in order of their indices,
+    // and any registers without a given value are set to zero.
+    pub fn reset(&mut self, initial_register_values: &[Word]) {
+        assert!(
+            initial_register_values.len() <= self.registers.len(),
+            "The machine only has {} registers",
+            self.registers.len()
+        );
+        self.registers.fill(0);
+        self.registers[..initial_register_values.len()].copy_from_slice(initial_register_values);
+        self.ip = 0;
+        self.is_halted = false;
+        self.cycle_count = 0;
+    }
+
+    // Returns the program in the machine's memory.
+    pub fn program(&self) -> &[S::Instruction] {
+        &self.program
+    }
+
+    // Returns the values of the machine's registers, in order of their indices.
+    pub fn registers(&self) -> &[Word] {
+        &self.registers
+    }
+
+    // Returns mutable access to the values of the machine's registers.
+    pub fn registers_mut(&mut self) -> &mut [Word] {
+        &mut self.registers
+    }
+
+    // Returns the position of the next instruction to execute.
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    // Returns whether the machine has halted, which happens when execution leaves the program.
+    pub fn is_halted(&self) -> bool {
+        self.is_halted
+    }
+
+    // Returns the number of instructions executed since the machine was last reset.
+    pub fn cycle_count(&self) -> u64 {
+        self.cycle_count
+    }
+
+    // Executes the next instruction, halting instead if there is no instruction at the instruction pointer. Returns
+    // the instruction that was executed, if any.
+    pub fn step(&mut self) -> Option<S::Instruction> {
+        let Some(instruction) = self.program.get(self.ip).copied() else {
+            self.is_halted = true;
+            return None;
+        };
+
+        match S::execute(&instruction, &mut self.registers) {
+            Flow::Next => self.ip += 1,
+            // A jump to before the start of the program leaves the program, just like one past its end, so it is
+            // sent to an instruction pointer that holds no instruction.
+            Flow::Jump(offset) => self.ip = self.ip.checked_add_signed(offset as isize).unwrap_or(usize::MAX),
+        }
+        self.cycle_count += 1;
+        Some(instruction)
+    }
+}
+
+// Implement the Debug trait for the Vm struct, so that its state can be printed using the {:?} format specifier.
+// The program is left out, since it is usually too long to be useful.
+impl<S: InstructionSet> std::fmt::Debug for Vm<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Vm")
+            .field("registers", &self.registers)
+            .field("ip", &self.ip)
+            .field("is_halted", &self.is_halted)
+            .field("cycle_count", &self.cycle_count)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,367 @@
+// Implements an interactive debugger for the virtual machine, which makes it practical to work out what a program
+// does. It supports breakpoints on instruction pointer values, stepping, register watches, an execution trace and a
+// disassembly listing.
+
+use super::{parse_program, InstructionSet, Vm, Word};
+use crate::run_context::CancellationToken;
+use std::collections::{BTreeSet, VecDeque};
+use std::io::{BufRead, Write};
+
+// The maximum number of executed instructions that are kept in the trace.
+const MAX_TRACE_LENGTH: usize = 10_000;
+
+// Describes the help text shown for the debugger's commands.
+const HELP_TEXT: &str = "\
+Commands:
+  s, step [<count>]          Execute the next instruction, or the given number of instructions
+  c, continue                Run until a breakpoint is reached, a watched register changes or the machine halts
+  b, break <ip>              Set a breakpoint at the given instruction pointer
+  d, delete <ip>             Remove the breakpoint at the given instruction pointer
+  w, watch <register>        Stop running when the given register changes
+  u, unwatch <register>      Stop watching the given register
+  r, regs, registers         Show the registers and the instruction pointer
+  l, list [<ip> [<count>]]   Show a disassembly of the program
+  t, trace [on|off|<count>]  Turn tracing on or off, or show the last traced instructions
+  set <register> <value>     Set a register to the given value
+  reset [<value>...]         Reset the machine, optionally with initial register values
+  q, quit                    Exit the debugger
+
+Press Ctrl-C to stop the machine while it is running.";
+
+// Describes an executed instruction, along with the registers before and after it ran.
+struct TraceEntry<S: InstructionSet> {
+    ip: usize,
+    instruction: S::Instruction,
+    registers_before: Vec<Word>,
+    registers_after: Vec<Word>,
+}
+
+// Describes why running the machine stopped.
+enum StopReason {
+    StepsCompleted,
+    Breakpoint,
+    RegisterChanged(usize, Word, Word),
+    Halted,
+    Interrupted,
+}
+
+// An interactive debugger for a machine running a given program.
+pub struct Debugger<S: InstructionSet> {
+    vm: Vm<S>,
+    breakpoints: BTreeSet<usize>,
+    watched_registers: Vec<usize>,
+    is_tracing: bool,
+    trace: VecDeque<TraceEntry<S>>,
+    // The token that is cancelled to stop the machine while it is running.
+    interrupt_token: CancellationToken,
+}
+
+impl<S: InstructionSet> Debugger<S> {
+    // Creates a new debugger for a machine running the given program, which stops running when the given token is
+    // cancelled.
+    pub fn new(program: Vec<S::Instruction>, interrupt_token: CancellationToken) -> Self {
+        Self {
+            vm: Vm::new(program),
+            breakpoints: BTreeSet::new(),
+            watched_registers: Vec::new(),
+            is_tracing: false,
+            trace: VecDeque::new(),
+            interrupt_token,
+        }
+    }
+
+    // Executes a single instruction, recording it in the trace if tracing is on.
+    fn step(&mut self) {
+        let ip = self.vm.ip();
+        let registers_before = self.vm.registers().to_vec();
+        let instruction = self.vm.step();
+
+        if let (true, Some(instruction)) = (self.is_tracing, instruction) {
+            if self.trace.len() == MAX_TRACE_LENGTH {
+                self.trace.pop_front();
+            }
+            self.trace.push_back(TraceEntry {
+                ip,
+                instruction,
+                registers_before,
+                registers_after: self.vm.registers().to_vec(),
+            });
+        }
+    }
+
+    // Runs the machine for up to the given number of instructions, or with no limit if none is given. Running stops
+    // early if the machine halts, a watched register changes, a breakpoint is reached or the interrupt token is
+    // cancelled. A breakpoint at the current instruction pointer doesn't stop the first instruction, so that running
+    // can resume from a breakpoint. Interrupts from before running started are ignored.
+    fn run(&mut self, step_limit: Option<u64>) -> StopReason {
+        self.interrupt_token.reset();
+        let mut step_count = 0;
+        loop {
+            if self.vm.is_halted() {
+                return StopReason::Halted;
+            }
+            if self.interrupt_token.is_cancelled() {
+                self.interrupt_token.reset();
+                return StopReason::Interrupted;
+            }
+            if step_limit.is_some_and(|limit| step_count >= limit) {
+                return StopReason::StepsCompleted;
+            }
+            if step_count > 0 && self.breakpoints.contains(&self.vm.ip()) {
+                return StopReason::Breakpoint;
+            }
+
+            let watched_values_before: Vec<Word> = self
+                .watched_registers
+                .iter()
+                .map(|register| self.vm.registers()[*register])
+                .collect();
+            self.step();
+            step_count += 1;
+
+            for (register, old_value) in self.watched_registers.iter().zip(watched_values_before) {
+                let new_value = self.vm.registers()[*register];
+                if old_value != new_value {
+                    return StopReason::RegisterChanged(*register, old_value, new_value);
+                }
+            }
+        }
+    }
+
+    // Writes the current state of the machine to the given output.
+    fn write_state(&self, output: &mut dyn Write) -> std::io::Result<()> {
+        for (name, value) in S::REGISTER_NAMES.iter().zip(self.vm.registers()) {
+            write!(output, "{name} = {value}, ")?;
+        }
+        write!(
+            output,
+            "ip = {} ({} instructions executed)",
+            self.vm.ip(),
+            self.vm.cycle_count()
+        )?;
+        if self.vm.is_halted() {
+            writeln!(output, ", halted")
+        } else {
+            writeln!(output)
+        }
+    }
+
+    // Writes a disassembly of the given range of the program to the given output. The current instruction is marked
+    // with ">", breakpoints are marked with "*", and the targets of jumps are shown.
+    fn write_listing(&self, output: &mut dyn Write, start: usize, count: usize) -> std::io::Result<()> {
+        let program = self.vm.program();
+        for (ip, instruction) in program.iter().enumerate().skip(start).take(count) {
+            let current_marker = if ip == self.vm.ip() { '>' } else { ' ' };
+            let breakpoint_marker = if self.breakpoints.contains(&ip) { '*' } else { ' ' };
+            let text = format!("{current_marker}{breakpoint_marker}{ip:>4}: {instruction}");
+            let target = S::get_jump_offset(instruction).map(|offset| ip.checked_add_signed(offset as isize));
+            match target {
+                Some(Some(target)) if target < program.len() => writeln!(output, "{text:<28}; -> {target}")?,
+                Some(_) => writeln!(output, "{text:<28}; -> halt")?,
+                None => writeln!(output, "{text}")?,
+            }
+        }
+        Ok(())
+    }
+
+    // Writes the last given number of traced instructions to the given output.
+    fn write_trace(&self, output: &mut dyn Write, count: usize) -> std::io::Result<()> {
+        if self.trace.is_empty() {
+            return writeln!(output, "The trace is empty. Use \"trace on\" to start tracing.");
+        }
+        for entry in self.trace.iter().skip(self.trace.len().saturating_sub(count)) {
+            let register_changes: Vec<String> = S::REGISTER_NAMES
+                .iter()
+                .zip(entry.registers_before.iter().zip(&entry.registers_after))
+                .map(|(name, (before, after))| format!("{name}: {before} -> {after}"))
+                .collect();
+            writeln!(
+                output,
+                "{:>4}: {:<16} {}",
+                entry.ip,
+                entry.instruction.to_string(),
+                register_changes.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+
+    // Executes the given debugger command, writing any output to the given output. Returns false if the debugger
+    // should exit. Commands that can't be understood are reported to the output rather than returned as errors.
+    pub fn execute_command(&mut self, command_line: &str, output: &mut dyn Write) -> std::io::Result<bool> {
+        let mut words = command_line.split_whitespace();
+        let Some(command) = words.next() else {
+            return Ok(true);
+        };
+        if matches!(command, "q" | "quit") {
+            return Ok(false);
+        }
+
+        let arguments: Vec<&str> = words.collect();
+        match self.run_command(command, &arguments, output) {
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidInput => writeln!(output, "{e}")?,
+            result => result?,
+        }
+        Ok(true)
+    }
+
+    // Runs the given debugger command with the given arguments. Invalid commands and arguments are returned as
+    // errors of kind InvalidInput.
+    fn run_command(&mut self, command: &str, arguments: &[&str], output: &mut dyn Write) -> std::io::Result<()> {
+        let first_argument = arguments.first().copied();
+        let second_argument = arguments.get(1).copied();
+        match command {
+            "s" | "step" => {
+                let count = parse_argument::<u64>(first_argument)?.unwrap_or(1);
+                let stop_reason = self.run(Some(count));
+                self.report_stop(&stop_reason, output)
+            }
+            "c" | "continue" => {
+                let stop_reason = self.run(None);
+                self.report_stop(&stop_reason, output)
+            }
+            "b" | "break" => {
+                let ip = parse_required_argument::<usize>(first_argument, "an instruction pointer")?;
+                self.breakpoints.insert(ip);
+                writeln!(output, "Breakpoint set at {ip}")
+            }
+            "d" | "delete" => {
+                let ip = parse_required_argument::<usize>(first_argument, "an instruction pointer")?;
+                if self.breakpoints.remove(&ip) {
+                    writeln!(output, "Breakpoint at {ip} removed")
+                } else {
+                    writeln!(output, "There is no breakpoint at {ip}")
+                }
+            }
+            "w" | "watch" => {
+                let register = parse_register_argument::<S>(first_argument)?;
+                if !self.watched_registers.contains(&register) {
+                    self.watched_registers.push(register);
+                }
+                writeln!(output, "Watching register {}", S::REGISTER_NAMES[register])
+            }
+            "u" | "unwatch" => {
+                let register = parse_register_argument::<S>(first_argument)?;
+                self.watched_registers.retain(|r| *r != register);
+                writeln!(output, "No longer watching register {}", S::REGISTER_NAMES[register])
+            }
+            "r" | "regs" | "registers" => self.write_state(output),
+            "l" | "list" => {
+                let start = parse_argument::<usize>(first_argument)?.unwrap_or(0);
+                let count = parse_argument::<usize>(second_argument)?.unwrap_or(self.vm.program().len());
+                self.write_listing(output, start, count)
+            }
+            "t" | "trace" => match first_argument {
+                Some("on") => {
+                    self.is_tracing = true;
+                    writeln!(output, "Tracing is on")
+                }
+                Some("off") => {
+                    self.is_tracing = false;
+                    writeln!(output, "Tracing is off")
+                }
+                _ => {
+                    let count = parse_argument::<usize>(first_argument)?.unwrap_or(20);
+                    self.write_trace(output, count)
+                }
+            },
+            "set" => {
+                let register = parse_register_argument::<S>(first_argument)?;
+                let value = parse_required_argument::<Word>(second_argument, "a value")?;
+                self.vm.registers_mut()[register] = value;
+                self.write_state(output)
+            }
+            "reset" => {
+                if arguments.len() > S::REGISTER_NAMES.len() {
+                    return Err(invalid_input(format!(
+                        "The machine only has {} registers",
+                        S::REGISTER_NAMES.len()
+                    )));
+                }
+                let initial_register_values = arguments
+                    .iter()
+                    .map(|argument| parse_required_argument::<Word>(Some(argument), "a value"))
+                    .collect::<std::io::Result<Vec<Word>>>()?;
+                self.vm.reset(&initial_register_values);
+                self.trace.clear();
+                self.write_state(output)
+            }
+            "h" | "help" => writeln!(output, "{HELP_TEXT}"),
+            _ => Err(invalid_input(format!(
+                "Unknown command \"{command}\". Type \"help\" for a list of commands."
+            ))),
+        }
+    }
+
+    // Writes why running stopped, followed by the current state, to the given output.
+    fn report_stop(&self, stop_reason: &StopReason, output: &mut dyn Write) -> std::io::Result<()> {
+        match stop_reason {
+            StopReason::StepsCompleted => {}
+            StopReason::Breakpoint => writeln!(output, "Stopped at breakpoint {}", self.vm.ip())?,
+            StopReason::RegisterChanged(register, old_value, new_value) => {
+                let name = S::REGISTER_NAMES[*register];
+                writeln!(output, "Register {name} changed from {old_value} to {new_value}")?;
+            }
+            StopReason::Halted => writeln!(output, "The machine halted")?,
+            StopReason::Interrupted => writeln!(output, "Interrupted")?,
+        }
+        self.write_state(output)?;
+        if !self.vm.is_halted() {
+            self.write_listing(output, self.vm.ip(), 1)?;
+        }
+        Ok(())
+    }
+}
+
+// Returns an error of kind InvalidInput with the given message, for reporting a command that can't be understood.
+fn invalid_input(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+// Parses the given optional command argument, returning an error if it is present but invalid.
+fn parse_argument<T: std::str::FromStr>(argument: Option<&str>) -> std::io::Result<Option<T>> {
+    argument
+        .map(|argument| {
+            argument
+                .parse::<T>()
+                .map_err(|_| invalid_input(format!("Invalid argument \"{argument}\"")))
+        })
+        .transpose()
+}
+
+// Parses the given command argument, returning an error if it is missing or invalid. The given description of the
+// argument is used in the error message.
+fn parse_required_argument<T: std::str::FromStr>(argument: Option<&str>, description: &str) -> std::io::Result<T> {
+    parse_argument(argument)?.ok_or_else(|| invalid_input(format!("Expected {description}")))
+}
+
+// Parses the given register name argument, returning the register's index, or an error if it is missing or invalid.
+fn parse_register_argument<S: InstructionSet>(argument: Option<&str>) -> std::io::Result<usize> {
+    argument
+        .and_then(S::get_register_index)
+        .ok_or_else(|| invalid_input(format!("Expected a register name ({})", S::REGISTER_NAMES.join(", "))))
+}
+
+// Runs an interactive debugger on the program for the given instruction set in the problem input, reading commands
+// from the given input and writing to the given output until the input ends or the debugger is told to quit. Running
+// the machine stops when the given token is cancelled.
+pub fn debug<S: InstructionSet>(
+    problem_input: &str,
+    interrupt_token: &CancellationToken,
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+) -> std::io::Result<()> {
+    let mut debugger = Debugger::<S>::new(parse_program::<S>(problem_input), interrupt_token.clone());
+    writeln!(output, "Type \"help\" for a list of commands.")?;
+    debugger.write_state(output)?;
+
+    let mut command_line = String::new();
+    loop {
+        write!(output, "(debug) ")?;
+        output.flush()?;
+        command_line.clear();
+        if input.read_line(&mut command_line)? == 0 || !debugger.execute_command(&command_line, output)? {
+            return Ok(());
+        }
+    }
+}
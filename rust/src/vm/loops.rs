@@ -0,0 +1,386 @@
+// Runs a machine with loop analysis. Loops whose iterations keep repeating in the same way are skipped over in closed
+// form, and programs that will never halt are detected and reported instead of running forever.
+//
+// Loops whose iterations don't repeat, such as the Collatz loop in day 23's program, which halves or triples its
+// register depending on whether it is even, have no closed form here. What is remembered instead is where each state
+// at the head of a loop led once the machine halted, so that a later run that reaches one of those states, e.g. a
+// Collatz sequence joining one that was followed before, skips straight to the end. Registers that are only ever
+// added to are left out of those states, since they can't affect where the program goes, and are adjusted by how
+// much they changed instead.
+
+use super::{Condition, Effect, InstructionSet, Vm, Word};
+use std::collections::HashMap;
+
+// The maximum number of states whose outcomes are remembered, so that the memory used stays bounded.
+const MAX_REMEMBERED_STATES: usize = 1 << 16;
+
+// Describes how a run with loop analysis went.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RunStatistics {
+    // The number of instructions that were actually executed.
+    pub executed_instructions: u64,
+    // The number of instructions that were skipped over by running loops in closed form.
+    pub skipped_instructions: u64,
+    // The number of times that a loop was run in closed form.
+    pub fast_forwards: u64,
+    // The number of times that the rest of the run was skipped because an earlier run reached the same state.
+    pub remembered_skips: u64,
+}
+
+// A state at the head of a loop, given by the instruction pointer and the registers, with any registers that are only
+// added to set to zero.
+type LoopHeadState = (usize, Vec<Word>);
+
+// Describes how a run that halted ended, as seen from a state at the head of a loop that it passed through.
+struct HaltingOutcome {
+    final_ip: usize,
+    // The final value of each register, or for registers that are only added to, how much they changed by.
+    register_results: Vec<Word>,
+    // The number of instructions executed from the state until the machine halted.
+    cycle_count: u64,
+}
+
+// Remembers the outcomes of runs that halted, keyed by the states at the heads of loops that they passed through.
+#[derive(Default)]
+pub struct HaltingOutcomes {
+    outcomes: HashMap<LoopHeadState, HaltingOutcome>,
+}
+
+// Describes a loop that a program will never leave.
+#[derive(Debug, Clone, Copy)]
+pub struct InfiniteLoop {
+    // The range of instruction pointers that the loop runs over.
+    pub first_ip: usize,
+    pub last_ip: usize,
+    // The number of instructions that had been executed when the loop was detected.
+    pub cycle_count: u64,
+}
+
+impl std::fmt::Display for InfiniteLoop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The program loops forever over instructions {}..={} (detected after {} instructions)",
+            self.first_ip, self.last_ip, self.cycle_count
+        )
+    }
+}
+
+// Describes a complete iteration of a loop: the instructions executed from the head of the loop until arriving back
+// at it, and the resulting change to each register.
+#[derive(PartialEq, Eq)]
+struct IterationPattern {
+    head_ip: usize,
+    path: Vec<usize>,
+    register_deltas: Vec<Word>,
+}
+
+// Detects a machine returning to a state that it was in before, which means that it will repeat the same states
+// forever. This uses Brent's algorithm: the state at a checkpoint is kept and compared with each later state, and the
+// checkpoint is moved to the current state at intervals that double, so that only a single state needs to be kept.
+// Only states at the heads of loops are checked, since a repeating machine must pass through one.
+struct RepetitionDetector {
+    checkpoint: Option<(usize, Vec<Word>)>,
+    checkpoint_interval: u64,
+    visits_since_checkpoint: u64,
+
+    // The range of instruction pointers executed since the checkpoint.
+    first_ip: usize,
+    last_ip: usize,
+}
+
+impl RepetitionDetector {
+    // Creates a new detector that hasn't seen any states yet.
+    fn new() -> Self {
+        Self {
+            checkpoint: None,
+            checkpoint_interval: 1,
+            visits_since_checkpoint: 0,
+            first_ip: usize::MAX,
+            last_ip: 0,
+        }
+    }
+
+    // Notes that the instruction at the given instruction pointer has been executed.
+    fn note_instruction(&mut self, ip: usize) {
+        self.first_ip = self.first_ip.min(ip);
+        self.last_ip = self.last_ip.max(ip);
+    }
+
+    // Checks the given state, which is at the head of a loop. If the machine has been in this state before, returns
+    // the range of instruction pointers that it has executed since, which make up the loop that it is stuck in.
+    fn check_state(&mut self, ip: usize, registers: &[Word]) -> Option<(usize, usize)> {
+        if let Some((checkpoint_ip, checkpoint_registers)) = &self.checkpoint {
+            if *checkpoint_ip == ip && checkpoint_registers == registers {
+                return Some((self.first_ip, self.last_ip));
+            }
+        }
+
+        self.visits_since_checkpoint += 1;
+        if self.checkpoint.is_none() || self.visits_since_checkpoint == self.checkpoint_interval {
+            self.checkpoint = Some((ip, registers.to_vec()));
+            self.checkpoint_interval *= 2;
+            self.visits_since_checkpoint = 0;
+            self.first_ip = usize::MAX;
+            self.last_ip = 0;
+        }
+        None
+    }
+}
+
+// Returns the first number of iterations from now, counting the upcoming iteration as zero, after which a branch on
+// the given condition will no longer have the given outcome, or None if it will have that outcome forever. The value
+// that the branch tests is the given value in the upcoming iteration, and changes by the given delta in each
+// iteration after that.
+fn get_first_changed_iteration(condition: Condition, value: Word, delta: Word, outcome: bool) -> Option<u64> {
+    if condition.is_met_by(value) != outcome {
+        return Some(0);
+    }
+    match condition {
+        Condition::IsEven => (delta % 2 != 0).then_some(1),
+        Condition::IsEqualTo(target) => {
+            if delta == 0 {
+                return None;
+            }
+            if outcome {
+                // The value is at the target, and moves away from it in the next iteration.
+                return Some(1);
+            }
+            let distance = i128::from(target) - i128::from(value);
+            let delta = i128::from(delta);
+            if distance % delta == 0 && distance / delta > 0 {
+                u64::try_from(distance / delta).ok()
+            } else {
+                None
+            }
+        }
+    }
+}
+
+impl<S: InstructionSet> Vm<S> {
+    // Returns how many times in a row the given iteration pattern, which the machine has just completed, will repeat
+    // from now on, or None if it will repeat forever. The pattern can only be analyzed if every instruction in it has
+    // a describable effect; otherwise it is treated as not repeating at all.
+    fn count_pattern_repetitions(&self, pattern: &IterationPattern) -> Option<u64> {
+        let mut values = self.registers.clone();
+        let mut repetition_count = None;
+        for (i, ip) in pattern.path.iter().enumerate() {
+            let instruction = &self.program[*ip];
+            let Some(effect) = S::get_effect(instruction) else {
+                return Some(0);
+            };
+            match effect {
+                Effect::Add(register, amount) => {
+                    let Some(value) = values[register].checked_add(amount) else {
+                        return Some(0);
+                    };
+                    values[register] = value;
+                }
+                Effect::Jump => {}
+                Effect::Modify(_) => return Some(0),
+                Effect::Branch(register, condition) => {
+                    // The outcome of the branch is known from where the path goes next. A branch to the following
+                    // instruction goes the same way whatever its outcome, so it doesn't matter.
+                    if S::get_jump_offset(instruction) == Some(1) {
+                        continue;
+                    }
+                    let next_ip = pattern.path.get(i + 1).copied().unwrap_or(pattern.head_ip);
+                    let outcome = next_ip != ip + 1;
+                    let branch_repetition_count = get_first_changed_iteration(
+                        condition,
+                        values[register],
+                        pattern.register_deltas[register],
+                        outcome,
+                    );
+                    repetition_count = match (repetition_count, branch_repetition_count) {
+                        (Some(count1), Some(count2)) => Some(u64::min(count1, count2)),
+                        (count1, count2) => count1.or(count2),
+                    };
+                }
+            }
+        }
+        repetition_count
+    }
+
+    // Skips the given number of repetitions of the given iteration pattern by applying their combined effect on the
+    // registers at once. Returns false, without changing anything, if a register would overflow.
+    fn skip_pattern_repetitions(&mut self, pattern: &IterationPattern, repetition_count: u64) -> bool {
+        let Ok(factor) = Word::try_from(repetition_count) else {
+            return false;
+        };
+        let new_registers: Option<Vec<Word>> = self
+            .registers
+            .iter()
+            .zip(&pattern.register_deltas)
+            .map(|(value, delta)| delta.checked_mul(factor).and_then(|change| value.checked_add(change)))
+            .collect();
+        let Some(new_registers) = new_registers else {
+            return false;
+        };
+        self.registers = new_registers;
+        self.cycle_count += repetition_count * pattern.path.len() as u64;
+        true
+    }
+
+    // Returns whether each register is only ever added to, so that its value can't affect what the program does. If
+    // any instruction's effect can't be described, then no register is known to be.
+    fn find_accumulator_registers(&self) -> Vec<bool> {
+        let mut is_accumulator = vec![true; self.registers.len()];
+        for instruction in &self.program {
+            match S::get_effect(instruction) {
+                None => return vec![false; self.registers.len()],
+                Some(Effect::Branch(register, _) | Effect::Modify(register)) => is_accumulator[register] = false,
+                Some(Effect::Add(..) | Effect::Jump) => {}
+            }
+        }
+        is_accumulator
+    }
+
+    // Skips to the end of the run if an earlier run passed through the current state, which is at the head of a loop.
+    // Returns the number of instructions skipped, or None,
+    // without changing anything, if the state's outcome isn't known or a register would overflow.
+    fn skip_to_known_outcome(&mut self, state: &LoopHeadState, is_accumulator: &[bool]) -> Option<u64> {
+        let outcome = self.halting_outcomes.outcomes.get(state)?;
+        let new_registers: Vec<Word> = self
+            .registers
+            .iter()
+            .zip(&outcome.register_results)
+            .zip(is_accumulator)
+            .map(|((value, result), is_accumulator)| {
+                if *is_accumulator {
+                    value.checked_add(*result)
+                } else {
+                    Some(*result)
+                }
+            })
+            .collect::<Option<_>>()?;
+        let (final_ip, cycle_count) = (outcome.final_ip, outcome.cycle_count);
+        self.registers = new_registers;
+        self.ip = final_ip;
+        self.cycle_count += cycle_count;
+        Some(cycle_count)
+    }
+
+    // Remembers how the run that just halted ended for each of the given states that it passed through, along with
+    // the registers and cycle count at each one.
+    fn remember_outcomes(&mut self, visited_states: Vec<(LoopHeadState, Vec<Word>, u64)>, is_accumulator: &[bool]) {
+        for (state, registers, cycle_count) in visited_states {
+            if self.halting_outcomes.outcomes.len() >= MAX_REMEMBERED_STATES {
+                return;
+            }
+            let register_results = self
+                .registers
+                .iter()
+                .zip(&registers)
+                .zip(is_accumulator)
+                .map(|((final_value, value), is_accumulator)| {
+                    if *is_accumulator {
+                        final_value.wrapping_sub(*value)
+                    } else {
+                        *final_value
+                    }
+                })
+                .collect();
+            self.halting_outcomes.outcomes.insert(
+                state,
+                HaltingOutcome {
+                    final_ip: self.ip,
+                    register_results,
+                    cycle_count: self.cycle_count - cycle_count,
+                },
+            );
+        }
+    }
+
+    // Runs the machine until it halts, like run, but analyzes loops as it goes. Whenever an iteration of a loop
+    // repeats the previous one exactly, following the same instructions and changing the registers by the same
+    // amounts, the iterations that will keep doing so are skipped over in closed form. If the machine is found to be
+    // stuck in a loop that it will never leave, returns a description of the loop instead of running forever. If the
+    // machine reaches a state that an earlier run passed through on its way to halting, it skips to where that run
+    // ended.
+    pub fn run_with_loop_analysis(&mut self) -> Result<RunStatistics, InfiniteLoop> {
+        let start_cycle_count = self.cycle_count;
+        let mut statistics = RunStatistics::default();
+        let mut repetition_detector = RepetitionDetector::new();
+        let is_accumulator = self.find_accumulator_registers();
+        let mut visited_states = Vec::new();
+
+        let mut head_ip = self.ip;
+        let mut head_registers = self.registers.clone();
+        let mut path = Vec::new();
+        let mut previous_pattern: Option<IterationPattern> = None;
+
+        while !self.is_halted {
+            let ip = self.ip;
+            if self.step().is_none() {
+                break;
+            }
+            path.push(ip);
+            repetition_detector.note_instruction(ip);
+            if self.ip > ip {
+                continue;
+            }
+
+            // The machine jumped backwards, so it has arrived at the head of a loop.
+            if let Some((first_ip, last_ip)) = repetition_detector.check_state(self.ip, &self.registers) {
+                return Err(InfiniteLoop {
+                    first_ip,
+                    last_ip,
+                    cycle_count: self.cycle_count,
+                });
+            }
+
+            let state: LoopHeadState = (
+                self.ip,
+                self.registers
+                    .iter()
+                    .zip(&is_accumulator)
+                    .map(|(value, is_accumulator)| if *is_accumulator { 0 } else { *value })
+                    .collect(),
+            );
+            if let Some(skipped_instructions) = self.skip_to_known_outcome(&state, &is_accumulator) {
+                statistics.skipped_instructions += skipped_instructions;
+                statistics.remembered_skips += 1;
+                continue;
+            }
+            if visited_states.len() < MAX_REMEMBERED_STATES {
+                visited_states.push((state, self.registers.clone(), self.cycle_count));
+            }
+
+            let pattern = (head_ip == self.ip).then(|| IterationPattern {
+                head_ip,
+                path: std::mem::take(&mut path),
+                register_deltas: self.registers.iter().zip(&head_registers).map(|(a, b)| a - b).collect(),
+            });
+            if let (Some(pattern), Some(previous_pattern)) = (&pattern, &previous_pattern) {
+                if pattern == previous_pattern {
+                    match self.count_pattern_repetitions(pattern) {
+                        None => {
+                            return Err(InfiniteLoop {
+                                first_ip: *pattern.path.iter().min().unwrap(),
+                                last_ip: *pattern.path.iter().max().unwrap(),
+                                cycle_count: self.cycle_count,
+                            });
+                        }
+                        Some(repetition_count) if repetition_count > 0 => {
+                            if self.skip_pattern_repetitions(pattern, repetition_count) {
+                                statistics.skipped_instructions += repetition_count * pattern.path.len() as u64;
+                                statistics.fast_forwards += 1;
+                            }
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+
+            previous_pattern = pattern;
+            head_ip = self.ip;
+            head_registers.clone_from(&self.registers);
+            path.clear();
+        }
+
+        self.remember_outcomes(visited_states, &is_accumulator);
+        statistics.executed_instructions = self.cycle_count - start_cycle_count - statistics.skipped_instructions;
+        Ok(statistics)
+    }
+}
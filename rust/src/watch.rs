@@ -0,0 +1,141 @@
+// Implements a mode that watches the input file for a single solution and re-runs the solution every time the
+// input file changes, reporting how the results and timing differ from the previous run. This is useful when
+// iterating on a solution using a hand-edited test input.
+
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+// How often the input file is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// How long the input file must remain unchanged after a change is detected before the solution is re-run.
+// This prevents editors that save in several steps from triggering several runs.
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(300);
+
+// The observable state of the input file. This is None if the file doesn't exist or can't be inspected.
+type InputFileState = Option<(SystemTime, u64)>;
+
+// Returns the current state of the file at the given path.
+fn get_input_file_state(input_path: &str) -> InputFileState {
+    let metadata = std::fs::metadata(input_path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+// Blocks until the state of the file at the given path differs from the given previous state and then remains
+// unchanged for the debounce interval, and then returns the new state.
+fn wait_for_input_file_change(input_path: &str, previous_state: InputFileState) -> InputFileState {
+    // Wait for the file to change at all.
+    let mut current_state = get_input_file_state(input_path);
+    while current_state == previous_state {
+        std::thread::sleep(POLL_INTERVAL);
+        current_state = get_input_file_state(input_path);
+    }
+
+    // Wait for the file to settle, restarting the wait every time another change is seen.
+    let mut settled_duration = Duration::ZERO;
+    while settled_duration < DEBOUNCE_INTERVAL {
+        std::thread::sleep(POLL_INTERVAL);
+        let latest_state = get_input_file_state(input_path);
+        if latest_state == current_state {
+            settled_duration += POLL_INTERVAL;
+        } else {
+            current_state = latest_state;
+            settled_duration = Duration::ZERO;
+        }
+    }
+
+    current_state
+}
+
+// Prints the results of the given run, along with how they differ from the results of the previous run if
+// there was one.
+fn print_run_result(result: &crate::SolutionRunResult, previous_result: Option<&crate::SolutionRunResult>) {
+    // Prints a single part result, noting whether it changed since the previous run.
+    fn print_part_result(label: &str, part_result: &str, previous_part_result: Option<&str>) {
+        match previous_part_result {
+            Some(previous_part_result) if previous_part_result == part_result => {
+                println!("  {label}: {part_result} (unchanged)");
+            }
+            Some(previous_part_result) => println!("  {label}: {part_result} (was {previous_part_result})"),
+            None => println!("  {label}: {part_result}"),
+        }
+    }
+
+    print_part_result(
+        "Part 1",
+        &result.part1_result,
+        previous_result.map(|r| r.part1_result.as_str()),
+    );
+    print_part_result(
+        "Part 2",
+        &result.part2_result,
+        previous_result.map(|r| r.part2_result.as_str()),
+    );
+
+    // Report the relative change in timing as well as the previous timing, since timings vary from run to run
+    // and the size of the change matters more than its direction.
+    if let Some(previous_result) = previous_result {
+        let previous_seconds = previous_result.duration.as_secs_f64();
+        let change_percentage = if previous_seconds > 0.0 {
+            (result.duration.as_secs_f64() - previous_seconds) / previous_seconds * 100.0
+        } else {
+            0.0
+        };
+        println!(
+            "  Time:   {} (was {}, {change_percentage:+.1}%)",
+            crate::format_duration(result.duration).trim_start(),
+            crate::format_duration(previous_result.duration).trim_start()
+        );
+    } else {
+        println!("  Time:   {}", crate::format_duration(result.duration).trim_start());
+    }
+}
+
+// Runs the solution for the given year and day every time its input file changes, until the process is
+// terminated. The solution is also run once immediately. Any parallel work done by the solution is done on the given
+// thread pool, or on rayon's global thread pool if none is given.
+pub fn watch(year: i32, day: i32, log_fn: Option<fn(&str)>, thread_pool: Option<&Arc<rayon::ThreadPool>>) {
+    let Some(solution) = crate::find_solution(year, day) else {
+        println!("There is no solution for year {year}, day {day}.");
+        std::process::exit(1);
+    };
+
+    let input_path = crate::get_input_path(year, day);
+    println!("Watching \"{input_path}\" for changes. Press Ctrl-C to stop.");
+
+    let mut input_file_state = get_input_file_state(&input_path);
+    let mut previous_result = None;
+    let mut run_number = 1;
+    loop {
+        println!();
+        println!("Run {run_number}");
+
+        // Read the input file directly rather than through get_problem_input, since a missing or unreadable
+        // input file is expected while it is being edited and shouldn't end the watch.
+        match std::fs::read_to_string(&input_path) {
+            Ok(problem_input) => {
+                // A panic in the solution is expected while an input is being edited, so report it and keep
+                // watching rather than letting it end the watch. The context is discarded along with the rest of
+                // the run if the solution panics, so it can't be observed in an inconsistent state.
+                let context =
+                    crate::RunContext::new(crate::CancellationToken::new()).with_thread_pool(thread_pool.cloned());
+                let run = AssertUnwindSafe(|| solution.run(problem_input.trim(), log_fn, &context));
+                match std::panic::catch_unwind(run) {
+                    Ok(result) => {
+                        print_run_result(&result, previous_result.as_ref());
+                        previous_result = Some(result);
+                    }
+                    Err(payload) => println!(
+                        "  The solution panicked: {}",
+                        crate::get_panic_message(payload.as_ref())
+                    ),
+                }
+            }
+            Err(e) => println!("  The input file could not be read: {e}"),
+        }
+
+        input_file_state = wait_for_input_file_change(&input_path, input_file_state);
+        run_number += 1;
+    }
+}